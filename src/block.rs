@@ -0,0 +1,122 @@
+use std::ops::RangeInclusive;
+
+use iced::widget::text_editor::{Content, Motion};
+use iced::Color;
+
+use crate::{buffer::{self, Position}, cursors::Cursor, decorations::Decoration, viewport::{self, VisibleLine}};
+
+/// A rectangular selection over the same columns of consecutive lines.
+///
+/// Columns may lie past the end of short lines, those lines just have less
+/// of their text selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Block {
+    pub anchor: Position,
+    pub head: Position,
+}
+
+impl Block {
+    pub fn new(anchor: Position) -> Self {
+        Self { anchor, head: anchor }
+    }
+
+    fn lines(&self) -> RangeInclusive<usize> {
+        self.anchor.line.min(self.head.line)..=self.anchor.line.max(self.head.line)
+    }
+
+    fn columns(&self) -> (usize, usize) {
+        (self.anchor.column.min(self.head.column), self.anchor.column.max(self.head.column))
+    }
+
+    /// Grows or shrinks the block by moving its head.
+    pub fn extend(&mut self, motion: Motion, content: &Content) {
+        let head = &mut self.head;
+
+        match motion {
+            Motion::Up => head.line = head.line.saturating_sub(1),
+            Motion::Down => head.line = (head.line + 1).min(content.line_count().saturating_sub(1)),
+            Motion::Left => head.column = head.column.saturating_sub(1),
+            Motion::Right => head.column += 1,
+            Motion::Home => head.column = 0,
+            Motion::End => head.column = buffer::line_length(content, head.line),
+            _ => {}
+        }
+    }
+
+    /// One cursor per line selecting the part of it inside the block, and
+    /// which of them sits on the line of the head.
+    pub fn cursors(&self, content: &Content) -> (Vec<Cursor>, usize) {
+        let (left, right) = self.columns();
+
+        let cursors = self.lines()
+            .map(|line| {
+                let length = buffer::line_length(content, line);
+                let from = buffer::offset(content, Position::new(line, left.min(length)));
+                let to = buffer::offset(content, Position::new(line, right.min(length)));
+
+                match self.head.column < self.anchor.column {
+                    true => Cursor::new(to, from),
+                    false => Cursor::new(from, to)
+                }
+            })
+            .collect();
+
+        (cursors, self.head.line - self.lines().start())
+    }
+
+    /// The area of the block on screen, including the parts past the end of lines.
+    pub fn decorations(&self, content: &Content, visible: &[VisibleLine], columns: usize, color: Color) -> Vec<Decoration> {
+        let (left, right) = self.columns();
+
+        self.lines()
+            .flat_map(|line| viewport::spans(content, visible, Position::new(line, left), Position::new(line, right), columns))
+            .map(|(row, from, to)| Decoration::Selection { row, from, to, color })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_head_moves_within_the_document() {
+        let content = Content::with_text("one\nthree\nfive");
+        let mut block = Block::new(Position::new(0, 1));
+
+        block.extend(Motion::Up, &content);
+        block.extend(Motion::Left, &content);
+        block.extend(Motion::Left, &content);
+        assert_eq!(block.head, Position::new(0, 0));
+
+        for _ in 0..4 {
+            block.extend(Motion::Down, &content);
+        }
+
+        block.extend(Motion::End, &content);
+        assert_eq!(block.head, Position::new(2, 4));
+        assert_eq!(block.anchor, Position::new(0, 1));
+    }
+
+    #[test]
+    fn each_line_gets_a_cursor_clipped_to_its_length() {
+        let content = Content::with_text("abcdef\nab\nabcdef");
+        let block = Block { anchor: Position::new(0, 1), head: Position::new(2, 4) };
+
+        let (cursors, primary) = block.cursors(&content);
+
+        assert_eq!(cursors, [Cursor::new(1, 4), Cursor::new(8, 9), Cursor::new(11, 14)]);
+        assert_eq!(primary, 2);
+    }
+
+    #[test]
+    fn dragging_left_and_up_selects_backwards() {
+        let content = Content::with_text("abcdef\nabcdef");
+        let block = Block { anchor: Position::new(1, 4), head: Position::new(0, 1) };
+
+        let (cursors, primary) = block.cursors(&content);
+
+        assert_eq!(cursors, [Cursor::new(4, 1), Cursor::new(11, 8)]);
+        assert_eq!(primary, 0);
+    }
+}
\ No newline at end of file
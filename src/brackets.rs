@@ -0,0 +1,255 @@
+use std::path::Path;
+
+use iced::widget::text_editor::{Action, Content, Edit, Motion};
+
+use crate::{buffer::{self, Position}, completion::is_word_character, config::file_type};
+
+const BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+/// How far away, in lines, a matching bracket is looked for.
+const SEARCH_LINES: usize = 1000;
+
+/// What typing or deleting a character does instead when brackets and quotes are paired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pairing {
+    /// Inserts both characters with the cursor in between.
+    Insert(char, char),
+    /// Puts the characters around the selection.
+    Wrap(char, char),
+    /// Steps over the closing character that's already there.
+    Skip,
+    /// Deletes both characters of an empty pair.
+    Delete,
+}
+
+/// The bracket at the cursor, or just before it, and the one it pairs with.
+pub fn matching(content: &Content, position: Position) -> Option<(Position, Position)> {
+    let line: Vec<char> = content.line(position.line)?.chars().collect();
+
+    let candidates = [Some(position.column), position.column.checked_sub(1)];
+
+    candidates.into_iter().flatten().find_map(|column| {
+        let character = *line.get(column)?;
+        let at = Position::new(position.line, column);
+
+        let (open, close) = BRACKETS.into_iter().find(|(open, close)| character == *open || character == *close)?;
+
+        let other = match character == open {
+            true => search(content, at, open, close, true),
+            false => search(content, at, close, open, false)
+        };
+
+        other.map(|other| (at, other))
+    })
+}
+
+/// Looks for the bracket `to` closing (or opening) the `from` at `start`, skipping nested pairs.
+fn search(content: &Content, start: Position, from: char, to: char, forward: bool) -> Option<Position> {
+    let mut depth = 0;
+
+    let lines: Box<dyn Iterator<Item = usize>> = match forward {
+        true => Box::new(start.line..content.line_count().min(start.line + SEARCH_LINES)),
+        false => Box::new((start.line.saturating_sub(SEARCH_LINES)..=start.line).rev())
+    };
+
+    for line in lines {
+        let characters: Vec<char> = content.line(line)?.chars().collect();
+
+        let columns: Box<dyn Iterator<Item = usize>> = match (forward, line == start.line) {
+            (true, true) => Box::new(start.column..characters.len()),
+            (true, false) => Box::new(0..characters.len()),
+            (false, true) => Box::new((0..=start.column).rev()),
+            (false, false) => Box::new((0..characters.len()).rev())
+        };
+
+        for column in columns {
+            match characters[column] {
+                character if character == from => depth += 1,
+                character if character == to => {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        return Some(Position::new(line, column));
+                    }
+                },
+                _ => {}
+            }
+        }
+    }
+
+    None
+}
+
+/// The quotes paired in files like the one at `path`. Languages where `'`
+/// also starts lifetimes or symbols, and prose, leave it alone.
+fn quotes(path: Option<&Path>) -> &'static [char] {
+    match file_type(path).as_str() {
+        "rs" | "el" | "lisp" | "clj" | "scm" => &['"'],
+        "md" | "txt" | "*" => &['"', '`'],
+        "js" | "mjs" | "cjs" | "ts" | "tsx" | "jsx" | "go" | "sh" | "bash" | "zsh" | "kt" => &['"', '\'', '`'],
+        _ => &['"', '\'']
+    }
+}
+
+/// What typing `character` at `cursor`, or over `selection`, should do
+/// instead of just inserting it.
+pub fn typed(content: &Content, cursor: Position, selection: Option<(Position, Position)>, character: char, path: Option<&Path>) -> Option<Pairing> {
+    let line: Vec<char> = content.line(cursor.line).map(|line| line.chars().collect()).unwrap_or_default();
+
+    let previous = cursor.column.checked_sub(1).and_then(|column| line.get(column)).copied();
+    let next = line.get(cursor.column).copied();
+
+    let is_quote = quotes(path).contains(&character);
+    let close = BRACKETS.iter().find(|(open, _)| *open == character).map(|(_, close)| *close);
+
+    if selection.is_some() {
+        return match (close, is_quote) {
+            (Some(close), _) => Some(Pairing::Wrap(character, close)),
+            (None, true) => Some(Pairing::Wrap(character, character)),
+            _ => None
+        };
+    }
+
+    let closes = is_quote || BRACKETS.iter().any(|(_, close)| *close == character);
+
+    if closes && next == Some(character) {
+        return Some(Pairing::Skip);
+    }
+
+    // Only pair in front of whitespace, closing brackets and punctuation that ends expressions.
+    let free = next.is_none_or(|next| next.is_whitespace() || matches!(next, ')' | ']' | '}' | ',' | ';' | ':'));
+
+    match (close, is_quote) {
+        (Some(close), _) if free => Some(Pairing::Insert(character, close)),
+        // A quote right after a word is an apostrophe or a string prefix's closing one.
+        (None, true) if free && !previous.is_some_and(is_word_character) => Some(Pairing::Insert(character, character)),
+        _ => None
+    }
+}
+
+/// Backspace at a `cursor` between the two halves of an empty pair deletes
+/// both, nothing happens to pairs when there's a `selection`.
+pub fn deleted(content: &Content, cursor: Position, selection: Option<(Position, Position)>, path: Option<&Path>) -> Option<Pairing> {
+    if selection.is_some() {
+        return None;
+    }
+
+    let line: Vec<char> = content.line(cursor.line).map(|line| line.chars().collect()).unwrap_or_default();
+
+    let previous = *line.get(cursor.column.checked_sub(1)?)?;
+    let next = *line.get(cursor.column)?;
+
+    let pair = BRACKETS.contains(&(previous, next)) || (previous == next && quotes(path).contains(&previous));
+
+    match pair {
+        true => Some(Pairing::Delete),
+        false => None
+    }
+}
+
+/// Carries out `pairing` on `content`.
+pub fn perform(content: &mut Content, pairing: Pairing) {
+    match pairing {
+        Pairing::Insert(open, close) => {
+            content.perform(Action::Edit(Edit::Insert(open)));
+            content.perform(Action::Edit(Edit::Insert(close)));
+            content.perform(Action::Move(Motion::Left));
+        },
+        Pairing::Wrap(open, close) => {
+            let Some((from, to)) = buffer::selection(content) else {
+                return;
+            };
+
+            let text = buffer::text_between(content, from, to);
+            buffer::replace(content, from, to, &format!("{open}{text}{close}"));
+
+            let end = match from.line == to.line {
+                true => Position::new(to.line, to.column + 1),
+                false => to
+            };
+
+            buffer::select(content, Position::new(from.line, from.column + 1), end);
+        },
+        Pairing::Skip => {
+            content.perform(Action::Move(Motion::Right));
+        },
+        Pairing::Delete => {
+            content.perform(Action::Edit(Edit::Delete));
+            content.perform(Action::Edit(Edit::Backspace));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(line: usize, column: usize) -> Position {
+        Position::new(line, column)
+    }
+
+    fn rust() -> Option<&'static Path> {
+        Some(Path::new("main.rs"))
+    }
+
+    #[test]
+    fn brackets_match_across_nested_pairs_and_lines() {
+        let content = Content::with_text("fn f(a: [u8; 2]) {\n    (a[0])\n}");
+
+        assert_eq!(matching(&content, at(0, 4)), Some((at(0, 4), at(0, 15))));
+        assert_eq!(matching(&content, at(0, 15)), Some((at(0, 15), at(0, 4))));
+        assert_eq!(matching(&content, at(0, 17)), Some((at(0, 17), at(2, 0))));
+        assert_eq!(matching(&content, at(2, 1)), Some((at(2, 0), at(0, 17))));
+        assert_eq!(matching(&content, at(1, 2)), None);
+    }
+
+    #[test]
+    fn unbalanced_brackets_have_no_match() {
+        let content = Content::with_text("((x)");
+
+        assert_eq!(matching(&content, at(0, 0)), None);
+        assert_eq!(matching(&content, at(0, 1)), Some((at(0, 1), at(0, 3))));
+    }
+
+    #[test]
+    fn brackets_pair_in_front_of_whitespace_and_closers() {
+        let content = Content::with_text("call(x) word");
+
+        assert_eq!(typed(&content, at(0, 12), None, '(', rust()), Some(Pairing::Insert('(', ')')));
+        assert_eq!(typed(&content, at(0, 6), None, '[', rust()), Some(Pairing::Insert('[', ']')));
+        assert_eq!(typed(&content, at(0, 8), None, '{', rust()), None);
+        assert_eq!(typed(&content, at(0, 6), None, ')', rust()), Some(Pairing::Skip));
+        assert_eq!(typed(&content, at(0, 5), None, ')', rust()), None);
+    }
+
+    #[test]
+    fn quotes_depend_on_the_language_and_what_comes_before() {
+        let content = Content::with_text("it ");
+
+        assert_eq!(typed(&content, at(0, 3), None, '"', rust()), Some(Pairing::Insert('"', '"')));
+        assert_eq!(typed(&content, at(0, 3), None, '\'', rust()), None);
+        assert_eq!(typed(&content, at(0, 3), None, '\'', Some(Path::new("main.py"))), Some(Pairing::Insert('\'', '\'')));
+        // After a word it's an apostrophe.
+        assert_eq!(typed(&content, at(0, 2), None, '\'', Some(Path::new("main.py"))), None);
+    }
+
+    #[test]
+    fn selections_get_wrapped() {
+        let content = Content::with_text("word");
+        let selection = Some((at(0, 0), at(0, 4)));
+
+        assert_eq!(typed(&content, at(0, 4), selection, '{', rust()), Some(Pairing::Wrap('{', '}')));
+        assert_eq!(typed(&content, at(0, 4), selection, '"', rust()), Some(Pairing::Wrap('"', '"')));
+        assert_eq!(typed(&content, at(0, 4), selection, 'x', rust()), None);
+    }
+
+    #[test]
+    fn empty_pairs_are_deleted_together() {
+        let content = Content::with_text("f() \"\" (x)");
+
+        assert_eq!(deleted(&content, at(0, 2), None, rust()), Some(Pairing::Delete));
+        assert_eq!(deleted(&content, at(0, 5), None, rust()), Some(Pairing::Delete));
+        assert_eq!(deleted(&content, at(0, 8), None, rust()), None);
+        assert_eq!(deleted(&content, at(0, 2), Some((at(0, 1), at(0, 2))), rust()), None);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,234 @@
+use std::sync::Arc;
+
+use iced::widget::text_editor::{Action, Content, Edit, Motion};
+
+/// A place in the document, the column is counted in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize) -> Self {
+        Self { line, column }
+    }
+}
+
+/// Position of the cursor.
+pub fn cursor(content: &Content) -> Position {
+    let (line, column) = content.cursor_position();
+
+    let column = content.line(line)
+        .map(|text| text[..column.min(text.len())].chars().count())
+        .unwrap_or(0);
+
+    Position::new(line, column)
+}
+
+/// Length of a line in characters.
+pub fn line_length(content: &Content, line: usize) -> usize {
+    content.line(line).map(|text| text.chars().count()).unwrap_or(0)
+}
+
+/// The exact text of the document, lines joined with `\n`.
+///
+/// Unlike `Content::text` this keeps an empty last line, so it round-trips
+/// through [`set_text`].
+pub fn text(content: &Content) -> String {
+    content.lines().map(|line| line.to_string()).collect::<Vec<_>>().join("\n")
+}
+
+/// Replaces the whole document, keeping the cursor as close to where it was as possible.
+pub fn set_text(content: &mut Content, text: &str) {
+    let position = cursor(content);
+
+    // cosmic-text swallows the final line break, so an empty last line needs one more.
+    *content = match text.ends_with('\n') {
+        true => Content::with_text(&format!("{text}\n")),
+        false => Content::with_text(text),
+    };
+
+    let line = position.line.min(content.line_count().saturating_sub(1));
+
+    move_to(content, Position::new(line, position.column));
+}
+
+/// Number of characters from the start of the document to `position`, line breaks count as one.
+pub fn offset(content: &Content, position: Position) -> usize {
+    content.lines()
+        .take(position.line)
+        .map(|line| line.chars().count() + 1)
+        .sum::<usize>() + position.column
+}
+
+/// The position `offset` characters into the document, the inverse of [`offset`].
+pub fn position_at(content: &Content, offset: usize) -> Position {
+    let mut remaining = offset;
+
+    for (index, line) in content.lines().enumerate() {
+        let length = line.chars().count();
+
+        if remaining <= length {
+            return Position::new(index, remaining);
+        }
+
+        remaining -= length + 1;
+    }
+
+    let last = content.line_count().saturating_sub(1);
+
+    Position::new(last, line_length(content, last))
+}
+
+/// Number of characters in the document.
+pub fn length(content: &Content) -> usize {
+    let last = content.line_count().saturating_sub(1);
+
+    offset(content, Position::new(last, line_length(content, last)))
+}
+
+/// Moves the cursor to `position`, dropping any selection.
+///
+/// `text_editor` only knows relative motions and `Motion::Down` walks
+/// visual rows, so we step down until we reach the right document line.
+pub fn move_to(content: &mut Content, position: Position) {
+    content.perform(Action::Move(Motion::DocumentStart));
+    walk(content, position, Action::Move);
+}
+
+/// Selects the text between `from` and `to`, leaving the cursor at the latter.
+///
+/// The selection is always built forwards, so `from` should come first.
+pub fn select(content: &mut Content, from: Position, to: Position) {
+    move_to(content, from);
+    walk(content, to, Action::Select);
+}
+
+/// Steps the cursor forwards to `position` using the given kind of motion.
+fn walk(content: &mut Content, position: Position, action: fn(Motion) -> Action) {
+    let start = cursor(content);
+    let column = position.column.min(line_length(content, position.line));
+
+    if start.line == position.line {
+        for _ in start.column..column {
+            content.perform(action(Motion::Right));
+        }
+
+        return;
+    }
+
+    while content.cursor_position().0 < position.line {
+        let before = content.cursor_position();
+
+        content.perform(action(Motion::Down));
+
+        if content.cursor_position() == before {
+            break;
+        }
+    }
+
+    content.perform(action(Motion::Home));
+
+    for _ in 0..column {
+        content.perform(action(Motion::Right));
+    }
+}
+
+/// Replaces the text between `from` and `to` with `text`, leaving the cursor after it.
+pub fn replace(content: &mut Content, from: Position, to: Position, text: &str) {
+    if from == to {
+        move_to(content, from);
+    } else {
+        select(content, from, to);
+    }
+
+    if !text.is_empty() {
+        content.perform(Action::Edit(Edit::Paste(Arc::new(text.to_owned()))));
+    } else if from != to {
+        content.perform(Action::Edit(Edit::Backspace));
+    }
+}
+
+/// Returns the text between two positions.
+pub fn text_between(content: &Content, from: Position, to: Position) -> String {
+    let mut text = String::new();
+
+    for line in from.line..=to.line {
+        let Some(contents) = content.line(line) else {
+            break;
+        };
+
+        let start = if line == from.line { from.column } else { 0 };
+        let end = if line == to.line { to.column } else { usize::MAX };
+
+        text.extend(contents.chars().skip(start).take(end.saturating_sub(start)));
+
+        if line != to.line {
+            text.push('\n');
+        }
+    }
+
+    text
+}
+
+/// Works out where the current selection starts and ends.
+///
+/// `Content` only hands out the selected text and the cursor, so we check
+/// whether the selection lies before or after the cursor in the document.
+pub fn selection(content: &Content) -> Option<(Position, Position)> {
+    let selected = content.selection().filter(|text| !text.is_empty())?;
+    let cursor = cursor(content);
+
+    let newlines = selected.matches('\n').count();
+    let first = selected.split('\n').next().unwrap_or("").chars().count();
+    let last = selected.rsplit('\n').next().unwrap_or("").chars().count();
+
+    if cursor.line >= newlines {
+        let start_line = cursor.line - newlines;
+
+        let start = if newlines == 0 {
+            Position::new(cursor.line, cursor.column.saturating_sub(last))
+        } else {
+            Position::new(start_line, line_length(content, start_line).saturating_sub(first))
+        };
+
+        if text_between(content, start, cursor) == selected {
+            return Some((start, cursor));
+        }
+    }
+
+    let end = if newlines == 0 {
+        Position::new(cursor.line, cursor.column + first)
+    } else {
+        Position::new(cursor.line + newlines, last)
+    };
+
+    Some((cursor, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_and_positions_are_inverses() {
+        let content = Content::with_text("ab\nçd\n\nxyz");
+
+        assert_eq!(offset(&content, Position::new(1, 1)), 4);
+        assert_eq!(position_at(&content, 4), Position::new(1, 1));
+        assert_eq!(position_at(&content, 6), Position::new(2, 0));
+        assert_eq!(position_at(&content, 100), Position::new(3, 3));
+        assert_eq!(length(&content), 10);
+    }
+
+    #[test]
+    fn text_between_spans_lines() {
+        let content = Content::with_text("hello\nwörld\nend");
+
+        assert_eq!(text_between(&content, Position::new(0, 3), Position::new(1, 2)), "lo\nwö");
+        assert_eq!(text_between(&content, Position::new(1, 1), Position::new(1, 4)), "örl");
+        assert_eq!(line_length(&content, 1), 5);
+        assert_eq!(text(&content), "hello\nwörld\nend");
+    }
+}
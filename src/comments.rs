@@ -0,0 +1,235 @@
+use std::path::Path;
+
+use iced::widget::text_editor::Content;
+
+use crate::{buffer::{self, Position}, config::file_type, lines::{self, LineEdit}};
+
+/// How a language writes its comments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tokens {
+    pub line: Option<&'static str>,
+    pub block: Option<(&'static str, &'static str)>,
+}
+
+const C_LIKE: Tokens = Tokens { line: Some("//"), block: Some(("/*", "*/")) };
+const HASH: Tokens = Tokens { line: Some("#"), block: None };
+const MARKUP: Tokens = Tokens { line: None, block: Some(("<!--", "-->")) };
+
+/// The comment tokens for the file at `path`, `None` when its type isn't known.
+pub fn tokens(path: Option<&Path>) -> Option<Tokens> {
+    let tokens = match file_type(path).as_str() {
+        "rs" | "c" | "h" | "cpp" | "cc" | "hpp" | "js" | "mjs" | "cjs" | "ts" | "tsx" | "jsx"
+        | "java" | "go" | "swift" | "kt" | "kts" | "cs" | "scala" | "dart" | "php" | "glsl" | "wgsl" => C_LIKE,
+        "py" | "sh" | "bash" | "zsh" | "fish" | "toml" | "yaml" | "yml" | "rb" | "pl" | "r" | "nix" | "conf" => HASH,
+        "html" | "htm" | "xml" | "svg" | "md" | "vue" => MARKUP,
+        "css" => Tokens { line: None, block: Some(("/*", "*/")) },
+        "zig" => Tokens { line: Some("//"), block: None },
+        "sql" => Tokens { line: Some("--"), block: Some(("/*", "*/")) },
+        "lua" => Tokens { line: Some("--"), block: Some(("--[[", "]]")) },
+        "hs" => Tokens { line: Some("--"), block: Some(("{-", "-}")) },
+        "ini" | "asm" | "el" | "lisp" | "clj" | "scm" => Tokens { line: Some(";"), block: None },
+        "tex" | "erl" => Tokens { line: Some("%"), block: None },
+        "vim" => Tokens { line: Some("\""), block: None },
+        _ => return None
+    };
+
+    Some(tokens)
+}
+
+/// Comments out the lines of `selection`, or the `cursor`'s line, at their
+/// shallowest indentation, or uncomments them when every one that isn't blank already is.
+///
+/// Languages without line comments get a block comment instead.
+pub fn toggle_line(content: &Content, cursor: Position, selection: Option<(Position, Position)>, tokens: Tokens) -> Option<LineEdit> {
+    let Some(token) = tokens.line else {
+        return tokens.block.and_then(|_| toggle_block(content, cursor, selection, tokens));
+    };
+
+    let (first, last) = lines::selected_lines(cursor, selection);
+    let original = lines::lines(content, first, last);
+    let filled = || original.iter().filter(|line| !line.trim().is_empty());
+
+    let commented = filled().all(|line| line.trim_start().starts_with(token));
+    let indentation = filled().map(|line| indentation(line)).min()?;
+
+    let changed: Vec<String> = original.iter()
+        .map(|line| match (line.trim().is_empty(), commented) {
+            (true, _) => line.clone(),
+            (false, true) => {
+                let start = line.len() - line.trim_start().len();
+                let rest = &line[start + token.len()..];
+
+                format!("{}{}", &line[..start], rest.strip_prefix(' ').unwrap_or(rest))
+            },
+            (false, false) => {
+                let split = line.char_indices().nth(indentation).map_or(line.len(), |(index, _)| index);
+
+                format!("{}{} {}", &line[..split], token, &line[split..])
+            }
+        })
+        .collect();
+
+    let selection = match selection {
+        Some(_) => (Position::new(first, 0), Position::new(last, changed[last - first].chars().count())),
+        None => {
+            let before = &original[cursor.line - first];
+            let after = &changed[cursor.line - first];
+
+            // Text after the comment token moves with it, the indentation stays put.
+            let pivot = match commented {
+                true => self::indentation(before),
+                false => indentation
+            };
+
+            let column = match cursor.column < pivot {
+                true => cursor.column,
+                false => (cursor.column + after.chars().count()).saturating_sub(before.chars().count()).max(pivot)
+            };
+
+            (Position::new(cursor.line, column), Position::new(cursor.line, column))
+        }
+    };
+
+    Some(LineEdit {
+        from: Position::new(first, 0),
+        to: lines::line_end(content, last),
+        text: changed.join("\n"),
+        selection,
+    })
+}
+
+/// Wraps `selection`, or the text of the `cursor`'s line, in a block comment,
+/// or unwraps it when it already is one.
+///
+/// Languages without block comments get line comments instead.
+pub fn toggle_block(content: &Content, cursor: Position, selection: Option<(Position, Position)>, tokens: Tokens) -> Option<LineEdit> {
+    let Some((open, close)) = tokens.block else {
+        return tokens.line.and_then(|_| toggle_line(content, cursor, selection, tokens));
+    };
+
+    let (from, to) = selection.unwrap_or_else(|| {
+        let line = content.line(cursor.line).map(|line| line.to_string()).unwrap_or_default();
+        (Position::new(cursor.line, indentation(&line)), lines::line_end(content, cursor.line))
+    });
+
+    let original = buffer::text_between(content, from, to);
+    let trimmed = original.trim();
+
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    let leading = &original[..original.len() - original.trim_start().len()];
+    let trailing = &original[original.trim_end().len()..];
+
+    let (text, shift) = match trimmed.strip_prefix(open).and_then(|inner| inner.strip_suffix(close)) {
+        Some(inner) => {
+            let opened = inner.strip_prefix(' ').unwrap_or(inner);
+            let removed = trimmed.len() - opened.len();
+
+            (format!("{leading}{}{trailing}", opened.strip_suffix(' ').unwrap_or(opened)), -(removed as isize))
+        },
+        None => (format!("{leading}{open} {trimmed} {close}{trailing}"), open.len() as isize + 1)
+    };
+
+    let selection = match selection {
+        Some(_) => (from, lines::end_of(from, &text)),
+        None => {
+            let end = from.column + text.chars().count();
+            let column = match cursor.column <= from.column {
+                true => cursor.column,
+                false => cursor.column.saturating_add_signed(shift).clamp(from.column, end)
+            };
+
+            (Position::new(cursor.line, column), Position::new(cursor.line, column))
+        }
+    };
+
+    Some(LineEdit { from, to, text, selection })
+}
+
+/// Width of the whitespace `line` starts with, in characters.
+fn indentation(line: &str) -> usize {
+    line.chars().take_while(|character| character.is_whitespace()).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(line: usize, column: usize) -> Position {
+        Position::new(line, column)
+    }
+
+    #[test]
+    fn tokens_follow_the_file_type() {
+        assert_eq!(tokens(Some(Path::new("main.rs"))), Some(C_LIKE));
+        assert_eq!(tokens(Some(Path::new("Cargo.TOML"))), Some(HASH));
+        assert_eq!(tokens(Some(Path::new("notes.txt"))), None);
+        assert_eq!(tokens(None), None);
+    }
+
+    #[test]
+    fn lines_are_commented_at_their_shallowest_indentation() {
+        let content = Content::with_text("fn main() {\n        deep();\n\n    shallow();\n}");
+
+        let edit = toggle_line(&content, at(3, 2), Some((at(1, 0), at(3, 2))), C_LIKE).unwrap();
+
+        assert_eq!((edit.from, edit.to), (at(1, 0), at(3, 14)));
+        assert_eq!(edit.text, "    //     deep();\n\n    // shallow();");
+        assert_eq!(edit.selection, (at(1, 0), at(3, 17)));
+    }
+
+    #[test]
+    fn commented_lines_are_uncommented() {
+        let content = Content::with_text("  # one\n  #two\n  three");
+
+        let edit = toggle_line(&content, at(1, 0), Some((at(0, 0), at(1, 5))), HASH).unwrap();
+        assert_eq!(edit.text, "  one\n  two");
+
+        // One line that isn't commented yet comments them all.
+        let edit = toggle_line(&content, at(2, 0), Some((at(0, 0), at(2, 1))), HASH).unwrap();
+        assert_eq!(edit.text, "  # # one\n  # #two\n  # three");
+    }
+
+    #[test]
+    fn the_cursor_moves_with_the_text_after_the_token() {
+        let content = Content::with_text("    call();");
+
+        let commented = toggle_line(&content, at(0, 6), None, C_LIKE).unwrap();
+        assert_eq!(commented.selection.0, at(0, 9));
+
+        let indented = toggle_line(&content, at(0, 2), None, C_LIKE).unwrap();
+        assert_eq!(indented.selection.0, at(0, 2));
+
+        let content = Content::with_text("    // call();");
+        let uncommented = toggle_line(&content, at(0, 9), None, C_LIKE).unwrap();
+        assert_eq!((uncommented.text.as_str(), uncommented.selection.0), ("    call();", at(0, 6)));
+    }
+
+    #[test]
+    fn block_comments_wrap_and_unwrap_the_selection() {
+        let content = Content::with_text("let x = value + 1;");
+
+        let wrapped = toggle_block(&content, at(0, 13), Some((at(0, 8), at(0, 13))), C_LIKE).unwrap();
+        assert_eq!(wrapped.text, "/* value */");
+        assert_eq!(wrapped.selection, (at(0, 8), at(0, 19)));
+
+        let content = Content::with_text("  /* both */  ");
+        let unwrapped = toggle_block(&content, at(0, 0), None, C_LIKE).unwrap();
+        assert_eq!((unwrapped.from, unwrapped.text.as_str()), (at(0, 2), "both  "));
+    }
+
+    #[test]
+    fn languages_fall_back_to_the_comments_they_have() {
+        let content = Content::with_text("<p>text</p>");
+
+        let edit = toggle_line(&content, at(0, 0), None, MARKUP).unwrap();
+        assert_eq!(edit.text, "<!-- <p>text</p> -->");
+
+        let edit = toggle_block(&Content::with_text("x = 1"), at(0, 0), None, HASH).unwrap();
+        assert_eq!(edit.text, "# x = 1");
+
+        assert!(toggle_block(&Content::with_text("   "), at(0, 0), None, C_LIKE).is_none());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,345 @@
+use std::collections::HashSet;
+
+use iced::widget::text_editor::Content;
+
+use crate::buffer::{self, Position};
+use crate::lsp;
+
+/// Where a completion came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Buffer,
+    LanguageServer,
+    /// The item's `insert` is a snippet body to expand.
+    Snippet,
+}
+
+impl Source {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Source::Buffer => "word",
+            Source::LanguageServer => "lsp",
+            Source::Snippet => "snippet",
+        }
+    }
+}
+
+/// Something the word at the cursor can be completed to.
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub label: String,
+    /// Text that replaces the word at the cursor.
+    pub insert: String,
+    pub detail: Option<String>,
+    pub source: Source,
+}
+
+impl Item {
+    pub fn from_lsp(item: lsp::CompletionItem) -> Self {
+        Self {
+            insert: item.insert_text.unwrap_or_else(|| item.label.clone()),
+            label: item.label,
+            detail: item.detail,
+            source: Source::LanguageServer,
+        }
+    }
+}
+
+/// Offers completions synchronously as the user types.
+///
+/// Sources that answer asynchronously, like language servers, hand their
+/// items to [`Completion::extend`] once they arrive instead.
+pub trait Provider {
+    fn complete(&self, content: &Content, prefix: &str) -> Vec<Item>;
+}
+
+/// Completes words that already appear in the buffer.
+pub struct BufferWords;
+
+impl Provider for BufferWords {
+    fn complete(&self, content: &Content, prefix: &str) -> Vec<Item> {
+        let mut seen = HashSet::new();
+        let mut items = Vec::new();
+
+        for line in content.lines() {
+            for word in line.split(|character: char| !is_word_character(character)) {
+                if word.chars().count() < 2 || word == prefix || !seen.insert(word.to_owned()) {
+                    continue;
+                }
+
+                items.push(Item {
+                    label: word.to_owned(),
+                    insert: word.to_owned(),
+                    detail: None,
+                    source: Source::Buffer,
+                });
+            }
+        }
+
+        items
+    }
+}
+
+/// An open completion popup.
+#[derive(Debug, Clone)]
+pub struct Completion {
+    /// Where the word being completed starts.
+    pub start: Position,
+    prefix: String,
+    items: Vec<Item>,
+    /// Indices into `items` that match the prefix, best match first.
+    matches: Vec<usize>,
+    selected: usize,
+}
+
+impl Completion {
+    pub fn new(start: Position, prefix: String, items: Vec<Item>) -> Self {
+        let mut completion = Self {
+            start,
+            prefix: String::new(),
+            items,
+            matches: Vec::new(),
+            selected: 0,
+        };
+
+        completion.filter(prefix);
+        completion
+    }
+
+    /// Adds more items, dropping ones whose label is already offered.
+    pub fn extend(&mut self, items: Vec<Item>) {
+        let labels: HashSet<String> = self.items.iter().map(|item| item.label.clone()).collect();
+
+        self.items.extend(items.into_iter().filter(|item| !labels.contains(&item.label)));
+        self.filter(self.prefix.clone());
+    }
+
+    /// Narrows the items down to the ones fuzzily matching `prefix`.
+    pub fn filter(&mut self, prefix: String) {
+        let mut matches: Vec<(i64, usize)> = self.items.iter()
+            .enumerate()
+            .filter_map(|(index, item)| fuzzy_score(&prefix, &item.label).map(|score| (score, index)))
+            .collect();
+
+        matches.sort_by(|(a, a_index), (b, b_index)| {
+            b.cmp(a).then_with(|| self.items[*a_index].label.len().cmp(&self.items[*b_index].label.len()))
+        });
+
+        self.matches = matches.into_iter().map(|(_, index)| index).collect();
+        self.selected = 0;
+        self.prefix = prefix;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.matches.is_empty()
+    }
+
+    /// The matching items in the order they're shown.
+    pub fn matches(&self) -> impl Iterator<Item = &Item> {
+        self.matches.iter().map(|index| &self.items[*index])
+    }
+
+    /// Position of the highlighted item among [`Completion::matches`].
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// The item at `index` among [`Completion::matches`].
+    pub fn get(&self, index: usize) -> Option<&Item> {
+        self.matches.get(index).map(|index| &self.items[*index])
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.matches.is_empty() {
+            self.selected = (self.selected + 1) % self.matches.len();
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        if !self.matches.is_empty() {
+            self.selected = (self.selected + self.matches.len() - 1) % self.matches.len();
+        }
+    }
+}
+
+pub fn is_word_character(character: char) -> bool {
+    character.is_alphanumeric() || character == '_'
+}
+
+/// Where the word that ends at `position` starts.
+pub fn word_start(content: &Content, position: Position) -> Position {
+    let length = content.line(position.line)
+        .map(|line| {
+            line.chars()
+                .take(position.column)
+                .collect::<Vec<_>>()
+                .into_iter()
+                .rev()
+                .take_while(|character| is_word_character(*character))
+                .count()
+        })
+        .unwrap_or(0);
+
+    Position::new(position.line, position.column - length)
+}
+
+/// The part of the word at the cursor that comes before it.
+pub fn prefix(content: &Content) -> (Position, String) {
+    let cursor = buffer::cursor(content);
+    let start = word_start(content, cursor);
+
+    (start, buffer::text_between(content, start, cursor))
+}
+
+/// Scores how well `candidate` matches `pattern` when its characters are
+/// typed in order with gaps allowed, `None` if it doesn't match at all.
+///
+/// Consecutive characters and ones at the start of a word count for more,
+/// so `gfe` ranks `get_file_encoding` above `huge_fence`.
+pub fn fuzzy_score(pattern: &str, candidate: &str) -> Option<i64> {
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+    let mut candidate_characters = candidate.char_indices().peekable();
+    let mut last_character = None;
+
+    for wanted in pattern.chars().flat_map(char::to_lowercase) {
+        loop {
+            let (index, character) = candidate_characters.next()?;
+            let position = candidate[..index].chars().count();
+            let before = last_character.replace(character);
+
+            if !character.to_lowercase().eq(std::iter::once(wanted)) {
+                continue;
+            }
+
+            score += 1;
+
+            if previous.is_some_and(|previous| previous + 1 == position) {
+                score += 5;
+            }
+
+            let word_start = match before {
+                None => true,
+                Some(before) => !is_word_character(before) || before == '_'
+                    || (before.is_lowercase() && character.is_uppercase()),
+            };
+
+            if word_start {
+                score += 3;
+            }
+
+            if position == 0 {
+                score += 5;
+            }
+
+            score -= position as i64 - previous.map(|previous| previous as i64 + 1).unwrap_or(0);
+            previous = Some(position);
+
+            break;
+        }
+    }
+
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(label: &str) -> Item {
+        Item {
+            label: label.to_owned(),
+            insert: label.to_owned(),
+            detail: None,
+            source: Source::Buffer,
+        }
+    }
+
+    fn labels(completion: &Completion) -> Vec<&str> {
+        completion.matches().map(|item| item.label.as_str()).collect()
+    }
+
+    #[test]
+    fn server_items_insert_their_label_unless_told_otherwise() {
+        let plain = Item::from_lsp(lsp::CompletionItem { label: String::from("len"), ..Default::default() });
+        let snippet = Item::from_lsp(lsp::CompletionItem {
+            label: String::from("push(…)"),
+            insert_text: Some(String::from("push")),
+            detail: Some(String::from("fn(&mut self, T)")),
+            ..Default::default()
+        });
+
+        assert_eq!((plain.label.as_str(), plain.insert.as_str()), ("len", "len"));
+        assert_eq!((snippet.label.as_str(), snippet.insert.as_str()), ("push(…)", "push"));
+        assert_eq!(snippet.detail.as_deref(), Some("fn(&mut self, T)"));
+        assert!(matches!(snippet.source, Source::LanguageServer));
+    }
+
+    #[test]
+    fn word_starts_score_higher() {
+        let word_starts = fuzzy_score("gfe", "get_file_encoding").unwrap();
+        let scattered = fuzzy_score("gfe", "huge_fence").unwrap();
+
+        assert!(word_starts > scattered);
+        assert!(fuzzy_score("gfe", "getFileEncoding").unwrap() > scattered);
+    }
+
+    #[test]
+    fn fuzzy_matching_ignores_case_but_not_order() {
+        assert!(fuzzy_score("FE", "file_encoding").is_some());
+        assert!(fuzzy_score("ef", "file").is_none());
+        assert!(fuzzy_score("files", "file").is_none());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn matches_are_ranked_then_shortest_first() {
+        let completion = Completion::new(
+            Position::new(0, 0),
+            String::from("ab"),
+            vec![item("xaxb"), item("abcdef"), item("ab_c"), item("nothing")]
+        );
+
+        assert_eq!(labels(&completion), ["ab_c", "abcdef", "xaxb"]);
+    }
+
+    #[test]
+    fn extending_skips_labels_already_offered() {
+        let mut completion = Completion::new(Position::new(0, 0), String::from("pr"), vec![item("print")]);
+
+        completion.extend(vec![item("print"), item("println")]);
+
+        assert_eq!(labels(&completion), ["print", "println"]);
+    }
+
+    #[test]
+    fn selection_wraps_around() {
+        let mut completion = Completion::new(Position::new(0, 0), String::new(), vec![item("one"), item("two")]);
+
+        completion.select_previous();
+        assert_eq!(completion.selected(), 1);
+
+        completion.select_next();
+        assert_eq!(completion.selected(), 0);
+
+        completion.filter(String::from("tw"));
+        assert_eq!(completion.get(completion.selected()).unwrap().label, "two");
+    }
+
+    #[test]
+    fn buffer_words_are_offered_once() {
+        let content = Content::with_text("let value = value_of(x);\nvalue + other");
+
+        let words: Vec<_> = BufferWords.complete(&content, "val").into_iter().map(|item| item.label).collect();
+
+        assert_eq!(words, ["let", "value", "value_of", "other"]);
+    }
+
+    #[test]
+    fn words_start_after_the_last_separator() {
+        let content = Content::with_text("call(some_arg");
+
+        assert_eq!(word_start(&content, Position::new(0, 13)), Position::new(0, 5));
+        assert_eq!(word_start(&content, Position::new(0, 5)), Position::new(0, 5));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,149 @@
+use std::{collections::HashMap, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::{encoding::LineEnding, keymap::Keymap, viewport::Wrap, GFEError};
+
+/// User preferences, stored as TOML in the platform's config directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Preferences keyed by file extension, `"*"` applies to every file.
+    pub file_types: HashMap<String, FileTypeConfig>,
+    /// Which keybindings the editor uses.
+    pub keymap: Keymap,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileTypeConfig {
+    pub wrap: Option<Wrap>,
+    pub wrap_column: Option<usize>,
+    /// Column to draw the ruler at, `0` hides it.
+    pub ruler: Option<usize>,
+    pub trim_trailing_whitespace: Option<bool>,
+    pub final_newline: Option<bool>,
+    pub line_ending: Option<LineEnding>,
+    /// Command and arguments of a formatter to pipe the buffer through on save.
+    pub formatter: Option<Vec<String>>,
+    /// Command and arguments that start a language server over stdio.
+    pub language_server: Option<Vec<String>>,
+    /// Closes brackets and quotes as they're typed, on unless set to `false`.
+    pub auto_pair: Option<bool>,
+}
+
+impl Config {
+    /// Returns the preferences for the file at `path`, falling back to the `"*"` entry.
+    pub fn for_file(&self, path: Option<&Path>) -> FileTypeConfig {
+        let fallback = self.file_types.get("*").cloned().unwrap_or_default();
+
+        match self.file_types.get(&file_type(path)) {
+            Some(config) => FileTypeConfig {
+                wrap: config.wrap.or(fallback.wrap),
+                wrap_column: config.wrap_column.or(fallback.wrap_column),
+                ruler: config.ruler.or(fallback.ruler),
+                trim_trailing_whitespace: config.trim_trailing_whitespace.or(fallback.trim_trailing_whitespace),
+                final_newline: config.final_newline.or(fallback.final_newline),
+                line_ending: config.line_ending.or(fallback.line_ending),
+                formatter: config.formatter.clone().or(fallback.formatter),
+                language_server: config.language_server.clone().or(fallback.language_server),
+                auto_pair: config.auto_pair.or(fallback.auto_pair),
+            },
+            None => fallback
+        }
+    }
+
+    /// Returns the entry for the file type of `path` so it can be changed.
+    pub fn for_file_mut(&mut self, path: Option<&Path>) -> &mut FileTypeConfig {
+        self.file_types.entry(file_type(path)).or_default()
+    }
+}
+
+/// Key used in [`Config::file_types`] for the file at `path`.
+pub fn file_type(path: Option<&Path>) -> String {
+    path.and_then(|path| path.extension())
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase())
+        .unwrap_or_else(|| String::from("*"))
+}
+
+/// Directory GFE keeps its configuration in.
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|path| path.join("gfe"))
+}
+
+pub async fn load_config() -> Result<Config, GFEError> {
+    let path = config_dir().ok_or(GFEError::NoConfigDir)?.join("config.toml");
+
+    let contents = match fs::read_to_string(&path).await {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(error) => return Err(GFEError::IO(error.kind()))
+    };
+
+    toml::from_str(&contents).map_err(|error| GFEError::InvalidConfig(error.message().to_owned()))
+}
+
+pub async fn save_config(config: Config) -> Result<(), GFEError> {
+    let directory = config_dir().ok_or(GFEError::NoConfigDir)?;
+
+    let contents = toml::to_string_pretty(&config)
+        .map_err(|error| GFEError::InvalidConfig(error.to_string()))?;
+
+    fs::create_dir_all(&directory)
+        .await
+        .map_err(|error| error.kind())
+        .map_err(GFEError::IO)?;
+
+    fs::write(directory.join("config.toml"), contents)
+        .await
+        .map_err(|error| error.kind())
+        .map_err(GFEError::IO)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_types_are_lowercase_extensions() {
+        assert_eq!(file_type(Some(Path::new("src/main.RS"))), "rs");
+        assert_eq!(file_type(Some(Path::new("Makefile"))), "*");
+        assert_eq!(file_type(None), "*");
+    }
+
+    #[test]
+    fn file_type_settings_fall_back_to_the_star_entry() {
+        let config: Config = toml::from_str(r#"
+            [file_types."*"]
+            wrap = "column"
+            wrap_column = 100
+            ruler = 80
+
+            [file_types.rs]
+            ruler = 100
+            wrap = "off"
+        "#).unwrap();
+
+        let rust = config.for_file(Some(Path::new("main.rs")));
+        assert_eq!(rust.wrap, Some(Wrap::Off));
+        assert_eq!(rust.wrap_column, Some(100));
+        assert_eq!(rust.ruler, Some(100));
+
+        let text = config.for_file(Some(Path::new("notes.txt")));
+        assert_eq!(text.wrap, Some(Wrap::Column));
+        assert_eq!(text.ruler, Some(80));
+    }
+
+    #[test]
+    fn changed_settings_round_trip_through_toml() {
+        let mut config = Config::default();
+        config.for_file_mut(Some(Path::new("a.py"))).wrap_column = Some(72);
+
+        let config: Config = toml::from_str(&toml::to_string_pretty(&config).unwrap()).unwrap();
+        assert_eq!(config.for_file(Some(Path::new("b.py"))).wrap_column, Some(72));
+    }
+}
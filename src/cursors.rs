@@ -0,0 +1,357 @@
+use std::{cmp::Reverse, sync::Arc};
+
+use iced::widget::text_editor::{Action, Content, Edit, Motion};
+use iced::Color;
+
+use crate::{buffer::{self, Position}, decorations::Decoration, viewport::{self, VisibleLine}};
+
+/// A cursor and the selection it drags along, as character offsets into the document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    /// Where the selection started, the same as `head` when nothing is selected.
+    pub anchor: usize,
+    pub head: usize,
+}
+
+impl Cursor {
+    pub fn new(anchor: usize, head: usize) -> Self {
+        Self { anchor, head }
+    }
+
+    fn start(&self) -> usize {
+        self.anchor.min(self.head)
+    }
+
+    fn end(&self) -> usize {
+        self.anchor.max(self.head)
+    }
+
+    /// The cursor and selection of the `text_editor` itself.
+    pub fn primary(content: &Content) -> Self {
+        let head = buffer::offset(content, buffer::cursor(content));
+
+        match buffer::selection(content) {
+            Some((from, to)) => {
+                let from = buffer::offset(content, from);
+                let to = buffer::offset(content, to);
+
+                Self::new(if head == to { from } else { to }, head)
+            },
+            None => Self::new(head, head)
+        }
+    }
+
+    /// Moves the cursor and selection of the `text_editor` here.
+    pub fn apply(&self, content: &mut Content) {
+        let anchor = buffer::position_at(content, self.anchor);
+
+        if self.head >= self.anchor {
+            buffer::select(content, anchor, buffer::position_at(content, self.head));
+            return;
+        }
+
+        // Backwards selections are built by walking left from the anchor.
+        buffer::move_to(content, anchor);
+
+        for _ in self.head..self.anchor {
+            content.perform(Action::Select(Motion::Left));
+        }
+    }
+}
+
+/// The cursors besides the one the `text_editor` keeps track of.
+///
+/// `Content` only knows a single cursor, so actions are performed once per
+/// cursor by moving its cursor to each of them in turn.
+#[derive(Debug, Clone, Default)]
+pub struct Cursors {
+    extra: Vec<Cursor>,
+}
+
+impl Cursors {
+    pub fn is_empty(&self) -> bool {
+        self.extra.is_empty()
+    }
+
+    /// How many cursors there are, counting the one of the `text_editor`.
+    pub fn count(&self) -> usize {
+        self.extra.len() + 1
+    }
+
+    pub fn clear(&mut self) {
+        self.extra.clear();
+    }
+
+    /// Replaces every cursor, the one at `primary` becomes the `text_editor`'s own.
+    pub fn set(&mut self, content: &mut Content, mut cursors: Vec<Cursor>, primary: usize) {
+        if primary >= cursors.len() {
+            return;
+        }
+
+        cursors.remove(primary).apply(content);
+        self.extra = cursors;
+    }
+
+    /// The selected text of every cursor in document order, one per line.
+    pub fn selected_text(&self, content: &Content) -> String {
+        let mut cursors: Vec<Cursor> = self.extra.iter().copied()
+            .chain(std::iter::once(Cursor::primary(content)))
+            .collect();
+
+        cursors.sort_by_key(Cursor::start);
+
+        cursors.iter()
+            .map(|cursor| {
+                buffer::text_between(
+                    content,
+                    buffer::position_at(content, cursor.start()),
+                    buffer::position_at(content, cursor.end())
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Adds a cursor on the line above the topmost cursor, or below the
+    /// bottommost one, in the same column where possible.
+    pub fn add_vertical(&mut self, content: &Content, up: bool) {
+        let all = self.extra.iter().copied().chain(std::iter::once(Cursor::primary(content)));
+
+        let head = match up {
+            true => all.map(|cursor| cursor.head).min(),
+            false => all.map(|cursor| cursor.head).max()
+        };
+
+        let Some(position) = head.map(|head| buffer::position_at(content, head)) else {
+            return;
+        };
+
+        let line = match up {
+            true => match position.line.checked_sub(1) {
+                Some(line) => line,
+                None => return
+            },
+            false => position.line + 1
+        };
+
+        if line >= content.line_count() {
+            return;
+        }
+
+        let column = position.column.min(buffer::line_length(content, line));
+        let offset = buffer::offset(content, Position::new(line, column));
+
+        self.add(Cursor::new(offset, offset), content);
+    }
+
+    /// Selects the word at the cursor or, if something is selected already,
+    /// adds a cursor selecting the next place the same text shows up.
+    pub fn select_next_occurrence(&mut self, content: &mut Content) {
+        let Some((from, to)) = buffer::selection(content) else {
+            content.perform(Action::SelectWord);
+            return;
+        };
+
+        let needle: Vec<char> = buffer::text_between(content, from, to).chars().collect();
+        let text: Vec<char> = buffer::text(content).chars().collect();
+
+        let primary = Cursor::primary(content);
+        let after = self.extra.iter().chain(std::iter::once(&primary)).map(Cursor::end).max().unwrap_or(0);
+
+        let taken = |index: usize| {
+            self.extra.iter().chain(std::iter::once(&primary)).any(|cursor| cursor.start() == index)
+        };
+
+        if let Some(index) = next_occurrence(&text, &needle, after, taken) {
+            self.extra.push(primary);
+            Cursor::new(index, index + needle.len()).apply(content);
+        }
+    }
+
+    /// Turns a selection over several lines into one cursor per line, each
+    /// selecting that line's part of it.
+    pub fn split_lines(&mut self, content: &mut Content) {
+        let Some((from, to)) = buffer::selection(content) else {
+            return;
+        };
+
+        // A selection ending at the start of a line doesn't really include that line.
+        let last = if to.line > from.line && to.column == 0 { to.line - 1 } else { to.line };
+
+        if last == from.line {
+            return;
+        }
+
+        let mut cursors: Vec<Cursor> = (from.line..=last)
+            .map(|line| {
+                let start = if line == from.line { from.column } else { 0 };
+                let end = if line == to.line { to.column } else { buffer::line_length(content, line) };
+
+                Cursor::new(
+                    buffer::offset(content, Position::new(line, start)),
+                    buffer::offset(content, Position::new(line, end))
+                )
+            })
+            .collect();
+
+        if let Some(primary) = cursors.pop() {
+            primary.apply(content);
+        }
+
+        self.extra.extend(cursors);
+    }
+
+    /// Performs `action` at every cursor.
+    ///
+    /// Pasting as many lines as there are cursors puts one line at each.
+    pub fn perform(&mut self, content: &mut Content, action: &Action) {
+        let lines: Option<Vec<String>> = match action {
+            Action::Edit(Edit::Paste(text)) => {
+                let lines: Vec<String> = text.strip_suffix('\n').unwrap_or(text).split('\n').map(str::to_owned).collect();
+
+                (lines.len() == self.count()).then_some(lines)
+            },
+            _ => None
+        };
+
+        self.perform_with(content, |index| match &lines {
+            Some(lines) => Action::Edit(Edit::Paste(Arc::new(lines[index].clone()))),
+            None => action.clone()
+        });
+    }
+
+    /// Performs the action `action_at` returns for each cursor, numbered in document order.
+    ///
+    /// Cursors are visited from the end of the document backwards so an edit
+    /// never moves the ones still to come, the ones already handled are
+    /// shifted by however much the document grew or shrank.
+    fn perform_with(&mut self, content: &mut Content, action_at: impl Fn(usize) -> Action) {
+        let mut cursors: Vec<(Cursor, bool)> = self.extra.iter()
+            .map(|cursor| (*cursor, false))
+            .chain(std::iter::once((Cursor::primary(content), true)))
+            .collect();
+
+        cursors.sort_by_key(|(cursor, _)| Reverse(cursor.start()));
+
+        let count = cursors.len();
+        let mut moved: Vec<(Cursor, bool)> = Vec::with_capacity(count);
+
+        for (index, (cursor, primary)) in cursors.into_iter().enumerate() {
+            let length = buffer::length(content);
+
+            cursor.apply(content);
+            content.perform(action_at(count - 1 - index));
+
+            let delta = buffer::length(content) as isize - length as isize;
+            let shift = |offset: usize| (offset as isize + delta).max(0) as usize;
+
+            for (later, _) in &mut moved {
+                later.anchor = shift(later.anchor);
+                later.head = shift(later.head);
+            }
+
+            moved.push((Cursor::primary(content), primary));
+        }
+
+        // Cursors that ran into each other become one, keeping the primary one.
+        moved.sort_by_key(|(cursor, primary)| (cursor.head, !primary));
+        moved.dedup_by_key(|(cursor, _)| cursor.head);
+
+        self.extra.clear();
+
+        for (cursor, primary) in moved {
+            match primary {
+                true => cursor.apply(content),
+                false => self.extra.push(cursor)
+            }
+        }
+    }
+
+    /// Cursors and selections to draw over the text area, the `text_editor`
+    /// draws its own cursor. Selections are left out when `selection` is `None`.
+    pub fn decorations(&self, content: &Content, visible: &[VisibleLine], columns: usize, selection: Option<Color>) -> Vec<Decoration> {
+        let mut decorations = Vec::new();
+
+        for cursor in &self.extra {
+            let from = buffer::position_at(content, cursor.start());
+            let to = buffer::position_at(content, cursor.end());
+
+            if let Some(color) = selection {
+                for (row, from, to) in viewport::spans(content, visible, from, to, columns) {
+                    decorations.push(Decoration::Selection { row, from, to, color });
+                }
+            }
+
+            if let Some((row, column)) = viewport::locate(visible, buffer::position_at(content, cursor.head), columns) {
+                decorations.push(Decoration::Cursor { row, column });
+            }
+        }
+
+        decorations
+    }
+
+    fn add(&mut self, cursor: Cursor, content: &Content) {
+        let primary = Cursor::primary(content);
+
+        if primary.head != cursor.head && !self.extra.iter().any(|extra| extra.head == cursor.head) {
+            self.extra.push(cursor);
+        }
+    }
+}
+
+/// Where `needle` next shows up in `text` from `after` on, wrapping around to
+/// the top once. Places that `taken` says already have a cursor are skipped.
+fn next_occurrence(text: &[char], needle: &[char], after: usize, taken: impl Fn(usize) -> bool) -> Option<usize> {
+    let after = after.min(text.len());
+
+    (after..text.len())
+        .chain(0..after)
+        .find(|index| text[*index..].starts_with(needle) && !taken(*index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn occurrence(text: &str, needle: &str, after: usize, taken: &[usize]) -> Option<usize> {
+        let text: Vec<char> = text.chars().collect();
+        let needle: Vec<char> = needle.chars().collect();
+
+        next_occurrence(&text, &needle, after, |index| taken.contains(&index))
+    }
+
+    #[test]
+    fn occurrences_are_searched_after_the_cursors() {
+        assert_eq!(occurrence("foo bar foo baz foo", "foo", 3, &[0]), Some(8));
+        assert_eq!(occurrence("foo bar foo baz foo", "foo", 11, &[0, 8]), Some(16));
+    }
+
+    #[test]
+    fn the_search_wraps_around_once() {
+        assert_eq!(occurrence("foo bar foo baz", "foo", 11, &[8]), Some(0));
+        assert_eq!(occurrence("foo bar", "foo", 3, &[0]), None);
+        assert_eq!(occurrence("foo bar", "qux", 0, &[]), None);
+    }
+
+    #[test]
+    fn occurrences_that_have_a_cursor_are_skipped() {
+        // The match right after the cursors already has one, the search goes on past it.
+        assert_eq!(occurrence("ab ab ab ab", "ab", 5, &[0, 3, 6]), Some(9));
+        assert_eq!(occurrence("ab ab ab ab", "ab", 11, &[3, 6, 9]), Some(0));
+        assert_eq!(occurrence("ab ab ab", "ab", 2, &[0, 3, 6]), None);
+    }
+
+    #[test]
+    fn cursors_order_their_ends() {
+        let backwards = Cursor::new(7, 2);
+
+        assert_eq!((backwards.start(), backwards.end()), (2, 7));
+    }
+
+    #[test]
+    fn the_primary_cursor_comes_from_the_editor() {
+        let content = Content::with_text("one\ntwo");
+
+        assert_eq!(Cursor::primary(&content), Cursor::new(0, 0));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,412 @@
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::overlay;
+use iced::advanced::renderer;
+use iced::advanced::text::{self, LineHeight, Shaping};
+use iced::advanced::widget::{self, Widget};
+use iced::advanced::{self, Clipboard, Shell};
+use iced::event;
+use iced::mouse;
+use iced::alignment::{Horizontal, Vertical};
+use iced::{Border, Color, Element, Event, Font, Length, Pixels, Point, Rectangle, Size, Vector};
+
+use crate::viewport::{CHAR_WIDTH, EDITOR_PADDING, LINE_HEIGHT, TEXT_SIZE};
+
+/// Something drawn on top of the text area of the editor.
+///
+/// Rows are visual rows relative to the top of the viewport.
+#[derive(Debug, Clone)]
+pub enum Decoration {
+    /// Tints whole rows, used for the line the cursor is on.
+    Line { row: isize, rows: usize },
+    /// A vertical guide line at the given column.
+    Ruler { column: usize },
+    /// A squiggle under the columns `from..to` of a row, hovering it shows `message`.
+    Underline { row: isize, from: usize, to: usize, color: Color, message: String },
+    /// A cursor other than the one the `text_editor` draws itself.
+    Cursor { row: isize, column: usize },
+    /// Selected columns `from..to` of a row that belong to such a cursor.
+    Selection { row: isize, from: usize, to: usize, color: Color },
+    /// Shown after the end of a line that has lines folded under it.
+    Folded { row: isize, column: usize },
+    /// Dimmed text from the given column on, like who last changed the cursor line.
+    Annotation { row: isize, column: usize, text: String },
+}
+
+/// A widget that draws [`Decoration`]s over the `text_editor` it wraps
+pub struct Decorations<'a, Message, Theme, Renderer> {
+    content: Element<'a, Message, Theme, Renderer>,
+    decorations: Vec<Decoration>,
+}
+
+impl<'a, Message, Theme, Renderer> Decorations<'a, Message, Theme, Renderer> {
+    /// Returns a new [`Decorations`]
+    pub fn new(
+        content: impl Into<Element<'a, Message, Theme, Renderer>>,
+        decorations: Vec<Decoration>,
+    ) -> Self {
+        Self {
+            content: content.into(),
+            decorations,
+        }
+    }
+}
+
+impl<'a, Message, Theme, Renderer> Widget<Message, Theme, Renderer>
+    for Decorations<'a, Message, Theme, Renderer>
+where
+    Renderer: text::Renderer<Font = Font>,
+{
+    fn children(&self) -> Vec<widget::Tree> {
+        vec![widget::Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut widget::Tree) {
+        tree.diff_children(&[&self.content]);
+    }
+
+    fn size(&self) -> Size<Length> {
+        self.content.as_widget().size()
+    }
+
+    fn layout(
+        &self,
+        tree: &mut widget::Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        self.content.as_widget().layout(
+            &mut tree.children[0],
+            renderer,
+            limits,
+        )
+    }
+
+    fn on_event(
+        &mut self,
+        state: &mut widget::Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        self.content.as_widget_mut().on_event(
+            &mut state.children[0],
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        )
+    }
+
+    fn draw(
+        &self,
+        state: &widget::Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &state.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            viewport,
+        );
+
+        let bounds = layout.bounds();
+
+        let text_area = Rectangle {
+            x: bounds.x + EDITOR_PADDING,
+            y: bounds.y + EDITOR_PADDING,
+            width: bounds.width - EDITOR_PADDING * 2.0,
+            height: bounds.height - EDITOR_PADDING * 2.0,
+        };
+
+        let mut tooltip = None;
+
+        for decoration in &self.decorations {
+            match decoration {
+                &Decoration::Line { row, rows } => {
+                    let area = Rectangle {
+                        x: bounds.x,
+                        y: text_area.y + row as f32 * LINE_HEIGHT,
+                        width: bounds.width,
+                        height: rows as f32 * LINE_HEIGHT,
+                    };
+
+                    fill(renderer, area, text_area, Color { a: 0.05, ..Color::WHITE });
+                },
+                &Decoration::Ruler { column } => {
+                    let area = Rectangle {
+                        x: text_area.x + column as f32 * CHAR_WIDTH,
+                        y: bounds.y,
+                        width: 1.0,
+                        height: bounds.height,
+                    };
+
+                    if area.x < bounds.x + bounds.width {
+                        fill(renderer, area, bounds, Color { a: 0.15, ..Color::WHITE });
+                    }
+                },
+                &Decoration::Cursor { row, column } => {
+                    let area = Rectangle {
+                        x: text_area.x + column as f32 * CHAR_WIDTH,
+                        y: text_area.y + row as f32 * LINE_HEIGHT,
+                        width: 1.0,
+                        height: LINE_HEIGHT,
+                    };
+
+                    fill(renderer, area, text_area, Color::WHITE);
+                },
+                &Decoration::Selection { row, from, to, color } => {
+                    let area = Rectangle {
+                        x: text_area.x + from as f32 * CHAR_WIDTH,
+                        y: text_area.y + row as f32 * LINE_HEIGHT,
+                        width: (to - from) as f32 * CHAR_WIDTH,
+                        height: LINE_HEIGHT,
+                    };
+
+                    fill(renderer, area, text_area, color);
+                },
+                &Decoration::Folded { row, column } => {
+                    let area = Rectangle {
+                        x: text_area.x + (column + 1) as f32 * CHAR_WIDTH,
+                        y: text_area.y + row as f32 * LINE_HEIGHT + 2.0,
+                        width: CHAR_WIDTH * 3.0,
+                        height: LINE_HEIGHT - 4.0,
+                    };
+
+                    if text_area.contains(area.center()) {
+                        renderer.fill_quad(
+                            renderer::Quad {
+                                bounds: area,
+                                border: Border::with_radius(3.0),
+                                ..renderer::Quad::default()
+                            },
+                            Color { a: 0.15, ..Color::WHITE },
+                        );
+
+                        renderer.fill_text(
+                            text::Text {
+                                content: "\u{22EF}",
+                                bounds: area.size(),
+                                size: Pixels(TEXT_SIZE),
+                                line_height: LineHeight::default(),
+                                font: Font::MONOSPACE,
+                                horizontal_alignment: Horizontal::Center,
+                                vertical_alignment: Vertical::Center,
+                                shaping: Shaping::Advanced,
+                            },
+                            area.center(),
+                            Color { a: 0.6, ..Color::WHITE },
+                            area,
+                        );
+                    }
+                },
+                Decoration::Annotation { row, column, text } => {
+                    let position = Point::new(
+                        text_area.x + *column as f32 * CHAR_WIDTH,
+                        text_area.y + *row as f32 * LINE_HEIGHT
+                    );
+
+                    if text_area.contains(position) {
+                        renderer.fill_text(
+                            text::Text {
+                                content: text,
+                                bounds: Size::new(text_area.x + text_area.width - position.x, LINE_HEIGHT),
+                                size: Pixels(TEXT_SIZE),
+                                line_height: LineHeight::default(),
+                                font: Font::MONOSPACE,
+                                horizontal_alignment: Horizontal::Left,
+                                vertical_alignment: Vertical::Top,
+                                shaping: Shaping::Advanced,
+                            },
+                            position,
+                            Color { a: 0.3, ..Color::WHITE },
+                            text_area,
+                        );
+                    }
+                },
+                Decoration::Underline { row, from, to, color, message } => {
+                    let area = Rectangle {
+                        x: text_area.x + *from as f32 * CHAR_WIDTH,
+                        y: text_area.y + *row as f32 * LINE_HEIGHT,
+                        width: (to - from) as f32 * CHAR_WIDTH,
+                        height: LINE_HEIGHT,
+                    };
+
+                    squiggle(renderer, area, text_area, *color);
+
+                    if cursor.is_over(area) && text_area.contains(area.center()) {
+                        tooltip = Some((area, message));
+                    }
+                }
+            }
+        }
+
+        if let Some((area, message)) = tooltip {
+            draw_tooltip(renderer, area, message, *viewport);
+        }
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        state: &'b mut widget::Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        translation: Vector,
+    ) -> Option<overlay::Element<'b, Message, Theme, Renderer>> {
+        self.content.as_widget_mut().overlay(
+            &mut state.children[0],
+            layout,
+            renderer,
+            translation,
+        )
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &widget::Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            &state.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn operate(
+        &self,
+        state: &mut widget::Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn widget::Operation<Message>,
+    ) {
+        self.content.as_widget().operate(
+            &mut state.children[0],
+            layout,
+            renderer,
+            operation,
+        );
+    }
+}
+
+/// Draws a zigzag line along the bottom of `area`.
+fn squiggle<Renderer: advanced::Renderer>(renderer: &mut Renderer, area: Rectangle, clip: Rectangle, color: Color) {
+    const STEP: f32 = 2.0;
+
+    let bottom = area.y + area.height - 3.0;
+    let mut x = area.x;
+    let mut up = false;
+
+    while x < area.x + area.width {
+        let segment = Rectangle {
+            x,
+            y: if up { bottom - 1.0 } else { bottom },
+            width: STEP.min(area.x + area.width - x),
+            height: 1.0,
+        };
+
+        fill(renderer, segment, clip, color);
+
+        x += STEP;
+        up = !up;
+    }
+}
+
+/// Draws `message` in a box right below `area`, on a layer above the text.
+fn draw_tooltip<Renderer: text::Renderer<Font = Font>>(renderer: &mut Renderer, area: Rectangle, message: &str, viewport: Rectangle) {
+    const PADDING: f32 = 6.0;
+    const SIZE: f32 = TEXT_SIZE * 0.85;
+
+    let lines = message.lines().count().max(1);
+    let longest = message.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+
+    let size = Size::new(
+        longest as f32 * SIZE * 0.6 + PADDING * 2.0,
+        lines as f32 * SIZE * 1.3 + PADDING * 2.0,
+    );
+
+    // Flip above the squiggle when there's no room below it.
+    let y = match area.y + area.height + size.height > viewport.y + viewport.height {
+        true => area.y - size.height,
+        false => area.y + area.height,
+    };
+
+    let bounds = Rectangle::new(Point::new(area.x.min(viewport.x + viewport.width - size.width).max(viewport.x), y), size);
+
+    renderer.with_layer(viewport, |renderer| {
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds,
+                border: Border {
+                    color: Color { a: 0.2, ..Color::WHITE },
+                    width: 1.0,
+                    radius: 4.0.into(),
+                },
+                ..renderer::Quad::default()
+            },
+            Color::from_rgb(0.15, 0.15, 0.17),
+        );
+
+        renderer.fill_text(
+            text::Text {
+                content: message,
+                bounds: Size::new(size.width - PADDING * 2.0, size.height - PADDING * 2.0),
+                size: Pixels(SIZE),
+                line_height: LineHeight::default(),
+                font: Font::MONOSPACE,
+                horizontal_alignment: Horizontal::Left,
+                vertical_alignment: Vertical::Top,
+                shaping: Shaping::Advanced,
+            },
+            Point::new(bounds.x + PADDING, bounds.y + PADDING),
+            Color::WHITE,
+            bounds,
+        );
+    });
+}
+
+/// Fills the part of `area` that lies inside of `clip`.
+fn fill<Renderer: advanced::Renderer>(renderer: &mut Renderer, area: Rectangle, clip: Rectangle, color: Color) {
+    let vertical = Rectangle { x: area.x, width: area.width, ..clip };
+
+    if let Some(bounds) = area.intersection(&vertical) {
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds,
+                ..renderer::Quad::default()
+            },
+            color,
+        );
+    }
+}
+
+impl<'a, Message, Theme, Renderer> From<Decorations<'a, Message, Theme, Renderer>>
+    for Element<'a, Message, Theme, Renderer>
+where
+    Theme: 'a,
+    Message: 'a,
+    Renderer: 'a + text::Renderer<Font = Font>,
+{
+    fn from(decorations: Decorations<'a, Message, Theme, Renderer>) -> Self {
+        Element::new(decorations)
+    }
+}
\ No newline at end of file
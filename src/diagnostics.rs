@@ -0,0 +1,211 @@
+use iced::widget::text_editor::Content;
+use iced::Color;
+
+use crate::{buffer::Position, decorations::Decoration, lsp, viewport::{self, VisibleLine}};
+
+/// How bad a [`Diagnostic`] is, ordered from worst to mildest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+impl Severity {
+    pub fn color(&self) -> Color {
+        match self {
+            Severity::Error => Color::from_rgb(1.0, 0.4, 0.4),
+            Severity::Warning => Color::from_rgb(1.0, 0.8, 0.3),
+            Severity::Information => Color::from_rgb(0.4, 0.7, 1.0),
+            Severity::Hint => Color::from_rgb(0.6, 0.6, 0.6),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Information => "info",
+            Severity::Hint => "hint",
+        }
+    }
+}
+
+/// A problem reported for a range of the buffer.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub start: Position,
+    pub end: Position,
+    pub severity: Severity,
+    pub message: String,
+    /// What reported it, e.g. `rustc` or `clippy`.
+    pub source: Option<String>,
+}
+
+impl Diagnostic {
+    /// Converts a diagnostic from a language server, whose columns count UTF-16 units.
+    pub fn from_lsp(content: &Content, diagnostic: lsp::Diagnostic) -> Self {
+        let severity = match diagnostic.severity {
+            Some(lsp::DiagnosticSeverity::WARNING) => Severity::Warning,
+            Some(lsp::DiagnosticSeverity::INFORMATION) => Severity::Information,
+            Some(lsp::DiagnosticSeverity::HINT) => Severity::Hint,
+            _ => Severity::Error,
+        };
+
+        Self {
+            start: lsp::from_lsp_position(content, diagnostic.range.start),
+            end: lsp::from_lsp_position(content, diagnostic.range.end),
+            severity,
+            message: diagnostic.message,
+            source: diagnostic.source,
+        }
+    }
+}
+
+/// The diagnostics of a buffer, kept sorted by where they start.
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    items: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    /// Replaces every diagnostic, sources like language servers always send the full set.
+    pub fn set(&mut self, mut items: Vec<Diagnostic>) {
+        items.sort_by_key(|diagnostic| (diagnostic.start, diagnostic.severity));
+
+        self.items = items;
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.items.iter()
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.items.iter().filter(|diagnostic| diagnostic.severity == severity).count()
+    }
+
+    /// The worst severity of the diagnostics touching `line`.
+    pub fn line_severity(&self, line: usize) -> Option<Severity> {
+        self.items.iter()
+            .filter(|diagnostic| diagnostic.start.line <= line && line <= diagnostic.end.line)
+            .map(|diagnostic| diagnostic.severity)
+            .min()
+    }
+
+    /// The first diagnostic starting after `position`, wrapping around to the top.
+    pub fn next(&self, position: Position) -> Option<&Diagnostic> {
+        self.items.iter()
+            .find(|diagnostic| diagnostic.start > position)
+            .or(self.items.first())
+    }
+
+    /// The last diagnostic starting before `position`, wrapping around to the bottom.
+    pub fn previous(&self, position: Position) -> Option<&Diagnostic> {
+        self.items.iter()
+            .rev()
+            .find(|diagnostic| diagnostic.start < position)
+            .or(self.items.last())
+    }
+
+    /// Squiggles under every diagnostic on screen, split up where lines wrap.
+    ///
+    /// `columns` is how many characters fit on a visual row.
+    pub fn underlines(&self, content: &Content, visible: &[VisibleLine], columns: usize) -> Vec<Decoration> {
+        let mut underlines = Vec::new();
+
+        for diagnostic in &self.items {
+            // Empty ranges still get a character worth of squiggle so they can be seen.
+            let end = match diagnostic.start == diagnostic.end {
+                true => Position::new(diagnostic.end.line, diagnostic.end.column + 1),
+                false => diagnostic.end
+            };
+
+            for (row, from, to) in viewport::spans(content, visible, diagnostic.start, end, columns) {
+                underlines.push(Decoration::Underline {
+                    row,
+                    from,
+                    to,
+                    color: diagnostic.severity.color(),
+                    message: diagnostic.message.clone(),
+                });
+            }
+        }
+
+        underlines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(line: usize, column: usize, severity: Severity) -> Diagnostic {
+        Diagnostic {
+            start: Position::new(line, column),
+            end: Position::new(line, column + 1),
+            severity,
+            message: format!("{} at {line}:{column}", severity.label()),
+            source: None,
+        }
+    }
+
+    fn diagnostics() -> Diagnostics {
+        let mut diagnostics = Diagnostics::default();
+
+        diagnostics.set(vec![
+            diagnostic(4, 0, Severity::Hint),
+            diagnostic(1, 2, Severity::Warning),
+            diagnostic(1, 2, Severity::Error),
+        ]);
+
+        diagnostics
+    }
+
+    #[test]
+    fn diagnostics_are_sorted_by_position_then_severity() {
+        let messages: Vec<_> = diagnostics().iter().map(|diagnostic| diagnostic.message.clone()).collect();
+
+        assert_eq!(messages, ["error at 1:2", "warning at 1:2", "hint at 4:0"]);
+    }
+
+    #[test]
+    fn lines_take_the_worst_severity() {
+        let diagnostics = diagnostics();
+
+        assert_eq!(diagnostics.line_severity(1), Some(Severity::Error));
+        assert_eq!(diagnostics.line_severity(4), Some(Severity::Hint));
+        assert_eq!(diagnostics.line_severity(2), None);
+        assert_eq!(diagnostics.count(Severity::Warning), 1);
+    }
+
+    #[test]
+    fn next_and_previous_wrap_around() {
+        let diagnostics = diagnostics();
+
+        assert_eq!(diagnostics.next(Position::new(2, 0)).unwrap().start, Position::new(4, 0));
+        assert_eq!(diagnostics.next(Position::new(4, 0)).unwrap().start, Position::new(1, 2));
+        assert_eq!(diagnostics.previous(Position::new(4, 0)).unwrap().start, Position::new(1, 2));
+        assert_eq!(diagnostics.previous(Position::new(0, 0)).unwrap().start, Position::new(4, 0));
+        assert!(Diagnostics::default().next(Position::new(0, 0)).is_none());
+    }
+
+    #[test]
+    fn server_columns_are_converted_from_utf16() {
+        let content = Content::with_text("let 😀 = é;");
+        let range = lsp_types::Range::new(lsp_types::Position::new(0, 4), lsp_types::Position::new(0, 6));
+
+        let diagnostic = Diagnostic::from_lsp(&content, lsp::Diagnostic {
+            severity: Some(lsp::DiagnosticSeverity::WARNING),
+            ..lsp::Diagnostic::new_simple(range, String::from("odd name"))
+        });
+
+        assert_eq!(diagnostic.start, Position::new(0, 4));
+        assert_eq!(diagnostic.end, Position::new(0, 5));
+        assert_eq!(diagnostic.severity, Severity::Warning);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,495 @@
+use std::ops::Range;
+
+use iced::alignment::{Alignment, Horizontal};
+use iced::widget::{self, text_editor::Content};
+use iced::{theme, Color, Element, Font, Length};
+use similar::{Algorithm, DiffOp};
+
+use crate::buffer::{self, Position};
+use crate::lines::LineEdit;
+
+/// Unchanged lines shown around each hunk.
+const CONTEXT: usize = 3;
+
+/// A run of lines that differ between two versions of a text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// Lines of the old text, empty when lines were only added.
+    pub old: Range<usize>,
+    /// Lines of the new text, empty when lines were only removed.
+    pub new: Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Modified,
+    Deleted,
+}
+
+impl Hunk {
+    pub fn change(&self) -> Change {
+        match (self.old.is_empty(), self.new.is_empty()) {
+            (true, _) => Change::Added,
+            (false, true) => Change::Deleted,
+            (false, false) => Change::Modified
+        }
+    }
+
+    /// "@@ -1,2 +1,3 @@" as in a unified diff.
+    pub fn header(&self) -> String {
+        let range = |lines: &Range<usize>| match lines.is_empty() {
+            true => format!("{},0", lines.start),
+            false => format!("{},{}", lines.start + 1, lines.len())
+        };
+
+        format!("@@ -{} +{} @@", range(&self.old), range(&self.new))
+    }
+}
+
+/// The text a file has on disk next to the one in the editor, and where they differ.
+pub struct Comparison {
+    pub saved: String,
+    pub current: String,
+    pub hunks: Vec<Hunk>,
+}
+
+impl Comparison {
+    pub fn new(saved: String, current: String) -> Self {
+        let mut comparison = Self { saved, current: String::new(), hunks: Vec::new() };
+        comparison.update(current);
+        comparison
+    }
+
+    /// Compares the saved text with what's in the editor now.
+    pub fn update(&mut self, current: String) {
+        self.current = current;
+        self.hunks = hunks(&self.saved_lines(), &self.current_lines());
+    }
+
+    pub fn saved_lines(&self) -> Vec<&str> {
+        lines(&self.saved)
+    }
+
+    /// The editor's text doesn't have the line break the file ends with.
+    pub fn current_lines(&self) -> Vec<&str> {
+        self.current.split('\n').collect()
+    }
+}
+
+/// A line of a hunk as it's shown, with where it is in the old and new text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Row<'a> {
+    Same { old: usize, new: usize, text: &'a str },
+    Removed { old: usize, text: &'a str },
+    Added { new: usize, text: &'a str },
+}
+
+/// The lines of a text the way the buffer has them, without the line break
+/// files end with.
+pub fn lines(text: &str) -> Vec<&str> {
+    text.strip_suffix('\n').unwrap_or(text).split('\n').collect()
+}
+
+/// Where `new` differs from `old`, line by line.
+pub fn hunks(old: &[&str], new: &[&str]) -> Vec<Hunk> {
+    let ops = similar::capture_diff_slices(Algorithm::Myers, old, new);
+    let mut hunks: Vec<Hunk> = Vec::new();
+
+    for op in ops {
+        if let DiffOp::Equal { .. } = op {
+            continue;
+        }
+
+        let (old, new) = (op.old_range(), op.new_range());
+
+        // A removal right before an insertion is one changed run of lines.
+        match hunks.last_mut() {
+            Some(last) if last.old.end == old.start && last.new.end == new.start => {
+                last.old.end = old.end;
+                last.new.end = new.end;
+            },
+            _ => hunks.push(Hunk { old, new })
+        }
+    }
+
+    hunks
+}
+
+/// Where a line of the new text was in the old one, `None` when it's part of a hunk.
+pub fn old_line(hunks: &[Hunk], line: usize) -> Option<usize> {
+    let mut offset = 0isize;
+
+    for hunk in hunks {
+        if hunk.new.contains(&line) {
+            return None;
+        }
+
+        if hunk.new.start > line {
+            break;
+        }
+
+        offset += hunk.old.len() as isize - hunk.new.len() as isize;
+    }
+
+    Some((line as isize + offset) as usize)
+}
+
+/// The hunk that changed a line of the new text, or removed lines where [`changes`] marks them.
+pub fn hunk_at(hunks: &[Hunk], line: usize, line_count: usize) -> Option<usize> {
+    hunks.iter().position(|hunk| match hunk.change() {
+        Change::Deleted => hunk.new.start.min(line_count.saturating_sub(1)) == line,
+        _ => hunk.new.contains(&line)
+    })
+}
+
+/// The change to mark each line of the new text with, deleted lines marked on the line
+/// that came after them, or the last line when they were at the end.
+pub fn changes(hunks: &[Hunk], line_count: usize) -> Vec<(usize, Change)> {
+    hunks.iter()
+        .flat_map(|hunk| match hunk.change() {
+            Change::Deleted => vec![(hunk.new.start.min(line_count.saturating_sub(1)), Change::Deleted)],
+            change => hunk.new.clone().map(|line| (line, change)).collect()
+        })
+        .collect()
+}
+
+/// The lines of a hunk one after the other, with some unchanged ones around it.
+pub fn inline<'a>(old: &[&'a str], new: &[&'a str], hunks: &[Hunk], index: usize) -> Vec<Row<'a>> {
+    let hunk = &hunks[index];
+    let (before, after) = context(old, hunks, index);
+    let mut rows = Vec::new();
+
+    rows.extend(before.map(|line| same(old, line, hunk)));
+    rows.extend(hunk.old.clone().map(|line| Row::Removed { old: line, text: old[line] }));
+    rows.extend(hunk.new.clone().map(|line| Row::Added { new: line, text: new[line] }));
+    rows.extend(after.map(|line| same(old, line, hunk)));
+
+    rows
+}
+
+/// The lines of a hunk as the old and new text have them next to each other,
+/// the shorter side padded with `None` so unchanged lines stay level.
+pub fn side_by_side<'a>(old: &[&'a str], new: &[&'a str], hunks: &[Hunk], index: usize) -> Vec<(Option<Row<'a>>, Option<Row<'a>>)> {
+    let hunk = &hunks[index];
+    let (before, after) = context(old, hunks, index);
+    let mut rows = Vec::new();
+
+    rows.extend(before.map(|line| (Some(same(old, line, hunk)), Some(same(old, line, hunk)))));
+
+    for index in 0..hunk.old.len().max(hunk.new.len()) {
+        let removed = hunk.old.clone().nth(index).map(|line| Row::Removed { old: line, text: old[line] });
+        let added = hunk.new.clone().nth(index).map(|line| Row::Added { new: line, text: new[line] });
+
+        rows.push((removed, added));
+    }
+
+    rows.extend(after.map(|line| (Some(same(old, line, hunk)), Some(same(old, line, hunk)))));
+
+    rows
+}
+
+/// Unchanged lines of the old text just before and after a hunk, up to the hunks next to it.
+fn context(old: &[&str], hunks: &[Hunk], index: usize) -> (Range<usize>, Range<usize>) {
+    let hunk = &hunks[index];
+    let previous_end = index.checked_sub(1).map_or(0, |previous| hunks[previous].old.end);
+    let next_start = hunks.get(index + 1).map_or(old.len(), |next| next.old.start);
+
+    (
+        hunk.old.start.saturating_sub(CONTEXT).max(previous_end)..hunk.old.start,
+        hunk.old.end..(hunk.old.end + CONTEXT).min(next_start)
+    )
+}
+
+/// An unchanged line of the old text, which is as far down in the new one as `hunk` moved it.
+fn same<'a>(old: &[&'a str], line: usize, hunk: &Hunk) -> Row<'a> {
+    let new = match line < hunk.old.start {
+        true => (line + hunk.new.start).saturating_sub(hunk.old.start),
+        false => (line + hunk.new.end).saturating_sub(hunk.old.end)
+    };
+
+    Row::Same { old: line, new, text: old[line] }
+}
+
+/// Puts the old lines of `hunk` back in `content`, which has to hold the new
+/// text with every line in it.
+pub fn revert(content: &Content, old: &[&str], hunk: &Hunk) -> LineEdit {
+    let last_line = content.line_count().saturating_sub(1);
+    let old_text = old[hunk.old.clone()].join("\n");
+    let line_end = |line: usize| Position::new(line, buffer::line_length(content, line));
+
+    let (from, to, text) = match hunk.change() {
+        Change::Modified => (Position::new(hunk.new.start, 0), line_end(hunk.new.end - 1), old_text),
+        // Added lines go with the line break after them, or before them at the end of the text.
+        Change::Added if hunk.new.end <= last_line => {
+            (Position::new(hunk.new.start, 0), Position::new(hunk.new.end, 0), String::new())
+        },
+        Change::Added if hunk.new.start > 0 => (line_end(hunk.new.start - 1), line_end(last_line), String::new()),
+        Change::Added => (Position::new(0, 0), line_end(last_line), String::new()),
+        Change::Deleted if hunk.new.start <= last_line => {
+            let start = Position::new(hunk.new.start, 0);
+            (start, start, old_text + "\n")
+        },
+        Change::Deleted => (line_end(last_line), line_end(last_line), format!("\n{old_text}"))
+    };
+
+    let cursor = Position::new(from.line, 0);
+
+    LineEdit { from, to, text, selection: (cursor, cursor) }
+}
+
+/// The changes since the file was saved hunk by hunk, each of which can be reverted.
+pub fn view<'a, Message: Clone + 'a>(
+    comparison: &'a Comparison,
+    side_by_side: bool,
+    on_revert: impl Fn(usize) -> Message,
+    on_toggle_layout: Message,
+) -> Element<'a, Message> {
+    let (saved, current) = (comparison.saved_lines(), comparison.current_lines());
+
+    let hunks = comparison.hunks.iter().enumerate().map(|(index, hunk)| {
+        let header = widget::row![
+            widget::text(hunk.header()).size(12).style(theme::Text::Color(Color { a: 0.5, ..Color::WHITE })),
+            widget::horizontal_space(),
+            widget::button(widget::text("Revert").size(12)).padding([2, 8]).on_press(on_revert(index))
+        ].align_items(Alignment::Center);
+
+        let rows: Vec<Element<'_, Message>> = match side_by_side {
+            true => self::side_by_side(&saved, &current, &comparison.hunks, index).into_iter()
+                .map(|(old, new)| widget::row![cell(old), cell(new)].spacing(10).into())
+                .collect(),
+            false => inline(&saved, &current, &comparison.hunks, index).into_iter()
+                .map(|row| inline_row(row).into())
+                .collect()
+        };
+
+        widget::column![header, widget::column(rows)].spacing(5).into()
+    });
+
+    let list: Element<'_, Message> = match comparison.hunks.is_empty() {
+        true => widget::text("No changes since the file was saved").size(12).into(),
+        false => widget::scrollable(widget::column(hunks).spacing(15)).into()
+    };
+
+    widget::container(
+        widget::column![
+            widget::row![
+                widget::text("Changes since saved"),
+                widget::horizontal_space(),
+                widget::button(widget::text(match side_by_side {
+                    true => "Inline",
+                    false => "Side by Side"
+                }).size(12))
+                .padding([2, 8])
+                .on_press(on_toggle_layout)
+            ].align_items(Alignment::Center),
+            list,
+        ].spacing(10)
+    )
+    .width(Length::Fixed(900.0))
+    .max_height(600.0)
+    .padding(10)
+    .into()
+}
+
+/// A line of the inline diff, with its line numbers in the saved and current text.
+fn inline_row<Message>(row: Row<'_>) -> widget::Row<'static, Message> {
+    let number = |line: Option<usize>| widget::text(line.map(|line| (line + 1).to_string()).unwrap_or_default())
+        .size(12)
+        .width(Length::Fixed(40.0))
+        .horizontal_alignment(Horizontal::Right);
+
+    let (old, new, marker, text) = match row {
+        Row::Same { old, new, text } => (Some(old), Some(new), ' ', text),
+        Row::Removed { old, text } => (Some(old), None, '-', text),
+        Row::Added { new, text } => (None, Some(new), '+', text)
+    };
+
+    widget::row![number(old), number(new), line_text(marker, text)].spacing(10)
+}
+
+/// One side of the side by side diff, empty where the other side has more lines.
+fn cell<'a, Message: 'a>(row: Option<Row<'_>>) -> Element<'a, Message> {
+    let (line, marker, text) = match row {
+        Some(Row::Same { old, text, .. }) => (old, ' ', text),
+        Some(Row::Removed { old, text }) => (old, '-', text),
+        Some(Row::Added { new, text }) => (new, '+', text),
+        None => return widget::horizontal_space().into()
+    };
+
+    widget::row![
+        widget::text(line + 1).size(12).width(Length::Fixed(40.0)).horizontal_alignment(Horizontal::Right),
+        line_text(marker, text)
+    ]
+    .spacing(10)
+    .width(Length::Fill)
+    .into()
+}
+
+/// A line of a diff after its marker, removed lines are red and added ones green.
+pub fn line_text(marker: char, text: &str) -> widget::Text<'static> {
+    let color = match marker {
+        '-' => Color::from_rgb(1.0, 0.4, 0.4),
+        '+' => Color::from_rgb(0.4, 0.8, 0.4),
+        _ => Color { a: 0.7, ..Color::WHITE }
+    };
+
+    widget::text(format!("{marker} {text}"))
+        .font(Font::MONOSPACE)
+        .size(14)
+        .style(theme::Text::Color(color))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hunk(old: Range<usize>, new: Range<usize>) -> Hunk {
+        Hunk { old, new }
+    }
+
+    #[test]
+    fn a_removal_before_an_insertion_is_one_hunk() {
+        assert_eq!(hunks(&["a", "b", "c"], &["a", "x", "c"]), vec![hunk(1..2, 1..2)]);
+        assert_eq!(hunks(&["a", "c"], &["a", "b", "c"]), vec![hunk(1..1, 1..2)]);
+        assert_eq!(hunks(&["a", "b", "c", "d"], &["a", "d"]), vec![hunk(1..3, 1..1)]);
+        assert_eq!(hunks(&["a", "b"], &["a", "b"]), Vec::new());
+    }
+
+    #[test]
+    fn tells_what_a_hunk_did() {
+        assert_eq!(hunk(1..1, 1..3).change(), Change::Added);
+        assert_eq!(hunk(1..3, 1..1).change(), Change::Deleted);
+        assert_eq!(hunk(1..2, 1..3).change(), Change::Modified);
+    }
+
+    #[test]
+    fn headers_count_from_one_like_unified_diffs() {
+        assert_eq!(hunk(1..3, 1..4).header(), "@@ -2,2 +2,3 @@");
+        assert_eq!(hunk(2..2, 2..3).header(), "@@ -2,0 +3,1 @@");
+    }
+
+    #[test]
+    fn lines_drop_the_final_line_break() {
+        assert_eq!(lines("a\nb\n"), vec!["a", "b"]);
+        assert_eq!(lines("a\nb"), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn maps_lines_back_to_the_old_text() {
+        let hunks = vec![hunk(1..1, 1..3), hunk(4..6, 6..6)];
+
+        assert_eq!(old_line(&hunks, 0), Some(0));
+        assert_eq!(old_line(&hunks, 1), None);
+        assert_eq!(old_line(&hunks, 3), Some(1));
+        assert_eq!(old_line(&hunks, 6), Some(6));
+    }
+
+    #[test]
+    fn deleted_lines_are_marked_on_the_line_after_them() {
+        let hunks = vec![hunk(0..1, 0..2), hunk(3..5, 4..4), hunk(7..8, 6..6)];
+
+        assert_eq!(changes(&hunks, 7), vec![
+            (0, Change::Modified),
+            (1, Change::Modified),
+            (4, Change::Deleted),
+            (6, Change::Deleted),
+        ]);
+
+        assert_eq!(hunk_at(&hunks, 4, 7), Some(1));
+        assert_eq!(hunk_at(&hunks, 6, 6), None);
+        assert_eq!(hunk_at(&hunks, 5, 6), Some(2));
+        assert_eq!(hunk_at(&hunks, 2, 7), None);
+    }
+
+    #[test]
+    fn inline_rows_have_context_around_the_hunk() {
+        let old = ["a", "b", "c", "d", "e", "f", "g", "h", "i"];
+        let new = ["a", "b", "c", "d", "x", "f", "g", "h", "i"];
+        let hunks = hunks(&old, &new);
+
+        assert_eq!(inline(&old, &new, &hunks, 0), vec![
+            Row::Same { old: 1, new: 1, text: "b" },
+            Row::Same { old: 2, new: 2, text: "c" },
+            Row::Same { old: 3, new: 3, text: "d" },
+            Row::Removed { old: 4, text: "e" },
+            Row::Added { new: 4, text: "x" },
+            Row::Same { old: 5, new: 5, text: "f" },
+            Row::Same { old: 6, new: 6, text: "g" },
+            Row::Same { old: 7, new: 7, text: "h" },
+        ]);
+    }
+
+    #[test]
+    fn context_stops_at_the_next_hunk() {
+        let old = ["a", "b", "c", "d"];
+        let new = ["x", "b", "c", "y"];
+        let hunks = hunks(&old, &new);
+
+        assert_eq!(inline(&old, &new, &hunks, 1), vec![
+            Row::Same { old: 1, new: 1, text: "b" },
+            Row::Same { old: 2, new: 2, text: "c" },
+            Row::Removed { old: 3, text: "d" },
+            Row::Added { new: 3, text: "y" },
+        ]);
+    }
+
+    #[test]
+    fn side_by_side_pads_the_shorter_side() {
+        let old = ["a", "b", "c"];
+        let new = ["a", "x", "y", "c"];
+        let hunks = hunks(&old, &new);
+
+        assert_eq!(side_by_side(&old, &new, &hunks, 0), vec![
+            (Some(Row::Same { old: 0, new: 0, text: "a" }), Some(Row::Same { old: 0, new: 0, text: "a" })),
+            (Some(Row::Removed { old: 1, text: "b" }), Some(Row::Added { new: 1, text: "x" })),
+            (None, Some(Row::Added { new: 2, text: "y" })),
+            (Some(Row::Same { old: 2, new: 3, text: "c" }), Some(Row::Same { old: 2, new: 3, text: "c" })),
+        ]);
+    }
+
+    #[test]
+    fn reverting_puts_the_old_lines_back() {
+        let content = Content::with_text("a\nx\nc");
+        let edit = revert(&content, &["a", "b", "c"], &hunk(1..2, 1..2));
+
+        assert_eq!((edit.from, edit.to, edit.text.as_str()), (Position::new(1, 0), Position::new(1, 1), "b"));
+    }
+
+    #[test]
+    fn reverting_an_addition_takes_its_line_break_too() {
+        let content = Content::with_text("a\nnew\nb");
+        let edit = revert(&content, &["a", "b"], &hunk(1..1, 1..2));
+
+        assert_eq!((edit.from, edit.to, edit.text.as_str()), (Position::new(1, 0), Position::new(2, 0), ""));
+
+        let content = Content::with_text("a\nnew");
+        let edit = revert(&content, &["a"], &hunk(1..1, 1..2));
+
+        assert_eq!((edit.from, edit.to, edit.text.as_str()), (Position::new(0, 1), Position::new(1, 3), ""));
+    }
+
+    #[test]
+    fn reverting_a_deletion_inserts_the_lines() {
+        let content = Content::with_text("a\nc");
+        let edit = revert(&content, &["a", "b", "c"], &hunk(1..2, 1..1));
+
+        assert_eq!((edit.from, edit.to, edit.text.as_str()), (Position::new(1, 0), Position::new(1, 0), "b\n"));
+
+        let content = Content::with_text("a");
+        let edit = revert(&content, &["a", "b"], &hunk(1..2, 1..1));
+
+        assert_eq!((edit.from, edit.to, edit.text.as_str()), (Position::new(0, 1), Position::new(0, 1), "\nb"));
+    }
+
+    #[test]
+    fn comparisons_follow_the_editor() {
+        let mut comparison = Comparison::new(String::from("a\nb\n"), String::from("a\nb"));
+
+        assert!(comparison.hunks.is_empty());
+
+        comparison.update(String::from("a\nb\nc"));
+
+        assert_eq!(comparison.hunks, vec![hunk(2..2, 2..3)]);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,109 @@
+use std::path::Path;
+
+use ec4rs::property;
+
+use crate::{encoding::{Charset, LineEnding}, indent::{IndentStyle, Indentation}};
+
+/// The `.editorconfig` properties GFE understands, resolved for a single file.
+#[derive(Debug, Clone, Default)]
+pub struct EditorConfig {
+    pub indent_style: Option<IndentStyle>,
+    pub indent_size: Option<usize>,
+    pub charset: Option<Charset>,
+    pub end_of_line: Option<LineEnding>,
+    pub insert_final_newline: Option<bool>,
+    pub trim_trailing_whitespace: Option<bool>,
+}
+
+impl EditorConfig {
+    /// Resolves the sections of every `.editorconfig` from the directory of `path` upwards.
+    ///
+    /// Files that fail to parse are treated as if they weren't there.
+    pub fn resolve(path: &Path) -> Self {
+        let Ok(mut properties) = ec4rs::properties_of(path) else {
+            return Self::default();
+        };
+
+        properties.use_fallbacks();
+
+        let indent_style = match properties.get::<property::IndentStyle>() {
+            Ok(property::IndentStyle::Tabs) => Some(IndentStyle::Tabs),
+            Ok(property::IndentStyle::Spaces) => Some(IndentStyle::Spaces),
+            Err(_) => None
+        };
+
+        let indent_size = match properties.get::<property::IndentSize>() {
+            Ok(property::IndentSize::Value(size)) if size > 0 => Some(size),
+            _ => match properties.get::<property::TabWidth>() {
+                Ok(property::TabWidth::Value(width)) if width > 0 => Some(width),
+                _ => None
+            }
+        };
+
+        let charset = match properties.get::<property::Charset>() {
+            Ok(property::Charset::Utf8) => Some(Charset::Utf8),
+            Ok(property::Charset::Utf8Bom) => Some(Charset::Utf8Bom),
+            Ok(property::Charset::Latin1) => Some(Charset::Latin1),
+            Ok(property::Charset::Utf16Le) => Some(Charset::Utf16Le),
+            Ok(property::Charset::Utf16Be) => Some(Charset::Utf16Be),
+            Err(_) => None
+        };
+
+        let end_of_line = match properties.get::<property::EndOfLine>() {
+            Ok(property::EndOfLine::Lf) => Some(LineEnding::Lf),
+            Ok(property::EndOfLine::CrLf) => Some(LineEnding::CrLf),
+            Ok(property::EndOfLine::Cr) => Some(LineEnding::Cr),
+            Err(_) => None
+        };
+
+        let insert_final_newline = match properties.get::<property::FinalNewline>() {
+            Ok(property::FinalNewline::Value(value)) => Some(value),
+            Err(_) => None
+        };
+
+        let trim_trailing_whitespace = match properties.get::<property::TrimTrailingWs>() {
+            Ok(property::TrimTrailingWs::Value(value)) => Some(value),
+            Err(_) => None
+        };
+
+        Self {
+            indent_style,
+            indent_size,
+            charset,
+            end_of_line,
+            insert_final_newline,
+            trim_trailing_whitespace,
+        }
+    }
+
+    /// Applies the indentation properties on top of `indentation`.
+    pub fn indentation(&self, indentation: Indentation) -> Indentation {
+        Indentation {
+            style: self.indent_style.unwrap_or(indentation.style),
+            width: self.indent_size.unwrap_or(indentation.width),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_matching_the_file_apply() {
+        let directory = std::env::temp_dir().join(format!("gfe-editorconfig-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join(".editorconfig"), "root = true\n\n[*]\nindent_style = tab\nend_of_line = crlf\n\n[*.py]\nindent_style = space\nindent_size = 2\ninsert_final_newline = false\n").unwrap();
+
+        let python = EditorConfig::resolve(&directory.join("a.py"));
+        let other = EditorConfig::resolve(&directory.join("b.txt"));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(python.indentation(Indentation::default()), Indentation { style: IndentStyle::Spaces, width: 2 });
+        assert_eq!(python.end_of_line, Some(LineEnding::CrLf));
+        assert_eq!(python.insert_final_newline, Some(false));
+        assert_eq!(other.indent_style, Some(IndentStyle::Tabs));
+        assert_eq!(other.insert_final_newline, None);
+    }
+}
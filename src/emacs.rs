@@ -0,0 +1,700 @@
+use std::collections::VecDeque;
+
+use iced::keyboard::{self, key::Named, Modifiers};
+use iced::widget::text_editor::{Action, Content, Edit, Motion};
+use serde::{Deserialize, Serialize};
+
+use crate::{buffer::{self, Position}, keymap::Effect};
+
+/// How many kills the kill ring remembers.
+const KILL_RING_SIZE: usize = 60;
+
+/// A key press as the Emacs layer sees it, `C-` and `M-` included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Key {
+    pub control: bool,
+    pub meta: bool,
+    pub name: Name,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Name {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+}
+
+impl Key {
+    /// Translates a key press, `None` for modifiers on their own and keys
+    /// the rest of the editor handles, like Tab.
+    pub fn from_keyboard(key: keyboard::Key<&str>, modifiers: Modifiers) -> Option<Self> {
+        let name = match key {
+            keyboard::Key::Character(text) => Name::Char(text.chars().next()?),
+            keyboard::Key::Named(named) => match named {
+                Named::Space => Name::Char(' '),
+                Named::Enter => Name::Enter,
+                Named::Backspace => Name::Backspace,
+                Named::Delete => Name::Delete,
+                Named::Escape => Name::Escape,
+                Named::ArrowUp => Name::Up,
+                Named::ArrowDown => Name::Down,
+                Named::ArrowLeft => Name::Left,
+                Named::ArrowRight => Name::Right,
+                Named::Home => Name::Home,
+                Named::End => Name::End,
+                Named::PageUp => Name::PageUp,
+                Named::PageDown => Name::PageDown,
+                _ => return None
+            },
+            _ => return None
+        };
+
+        Some(Self {
+            control: modifiers.control(),
+            meta: modifiers.alt(),
+            name,
+        })
+    }
+}
+
+/// What the previous command was, kills in a row grow the same entry and
+/// `M-y` only follows a yank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Last {
+    #[default]
+    Other,
+    Kill,
+    Yank,
+}
+
+/// The text the last yank inserted, in characters from the start of the document.
+#[derive(Debug, Clone, Copy)]
+struct Yank {
+    start: usize,
+    length: usize,
+    /// Which kill ring entry it was.
+    index: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Search {
+    query: String,
+    forward: bool,
+    /// Where the cursor was when the search started.
+    origin: Position,
+    /// Start and end of the current match.
+    found: Option<(usize, usize)>,
+    failing: bool,
+}
+
+/// Emacs keybindings on top of the `text_editor`.
+///
+/// Like the [`Vim`](crate::vim::Vim) layer it turns keys into [`Effect`]s for
+/// the editor to carry out.
+#[derive(Debug, Clone, Default)]
+pub struct Emacs {
+    /// Where the region starts, motions select from it while it's set.
+    mark: Option<Position>,
+    kill_ring: VecDeque<String>,
+    last: Last,
+    yank: Option<Yank>,
+    /// Whether `C-x` was pressed and waits for the rest of the command.
+    prefix: bool,
+    search: Option<Search>,
+    last_query: String,
+    message: Option<String>,
+}
+
+impl Emacs {
+    /// Says the window stays open because the buffer has unsaved changes.
+    pub fn refuse_quit(&mut self) {
+        self.message = Some(String::from("Buffer modified, save it first with C-x C-s"));
+    }
+
+    /// Forgets the region, e.g. when the mouse moved the cursor.
+    pub fn deactivate_mark(&mut self) {
+        self.mark = None;
+    }
+
+    /// What the bottom panel shows: the search, a pending prefix or a message.
+    pub fn status(&self) -> String {
+        if let Some(search) = &self.search {
+            let direction = match search.forward {
+                true => "I-search",
+                false => "I-search backward"
+            };
+
+            return match search.failing {
+                true => format!("Failing {direction}: {}", search.query),
+                false => format!("{direction}: {}", search.query)
+            };
+        }
+
+        if self.prefix {
+            return String::from("C-x-");
+        }
+
+        self.message.clone().unwrap_or_default()
+    }
+
+    /// Handles a key press.
+    pub fn key(&mut self, key: Key, content: &Content) -> Vec<Effect> {
+        self.message = None;
+
+        if self.search.is_some() {
+            if let Some(effects) = self.search_key(key, content) {
+                return effects;
+            }
+        }
+
+        let last = std::mem::take(&mut self.last);
+
+        if self.prefix {
+            self.prefix = false;
+            return self.prefixed(key);
+        }
+
+        let cursor = buffer::cursor(content);
+
+        match (key.control, key.meta, key.name) {
+            (true, false, Name::Char('f')) | (false, false, Name::Right) => self.motion(Motion::Right),
+            (true, false, Name::Char('b')) | (false, false, Name::Left) => self.motion(Motion::Left),
+            (true, false, Name::Char('n')) | (false, false, Name::Down) => self.motion(Motion::Down),
+            (true, false, Name::Char('p')) | (false, false, Name::Up) => self.motion(Motion::Up),
+            (true, false, Name::Char('a')) | (false, false, Name::Home) => self.motion(Motion::Home),
+            (true, false, Name::Char('e')) | (false, false, Name::End) => self.motion(Motion::End),
+            (true, false, Name::Char('v')) | (false, false, Name::PageDown) => self.motion(Motion::PageDown),
+            (false, true, Name::Char('v')) | (false, false, Name::PageUp) => self.motion(Motion::PageUp),
+            (false, true, Name::Char('f')) => self.motion(Motion::WordRight),
+            (false, true, Name::Char('b')) => self.motion(Motion::WordLeft),
+            (false, true, Name::Char('<')) => self.motion(Motion::DocumentStart),
+            (false, true, Name::Char('>')) => self.motion(Motion::DocumentEnd),
+            (true, false, Name::Char('d')) | (false, false, Name::Delete) => self.edit(Edit::Delete),
+            (false, false, Name::Backspace) => self.edit(Edit::Backspace),
+            (false, false, Name::Enter) => self.edit(Edit::Enter),
+            (false, false, Name::Char(character)) => self.edit(Edit::Insert(character)),
+            (true, false, Name::Char('k')) => {
+                let length = buffer::line_length(content, cursor.line);
+
+                let to = match cursor.column < length {
+                    true => Position::new(cursor.line, length),
+                    false if cursor.line + 1 < content.line_count() => Position::new(cursor.line + 1, 0),
+                    false => return Vec::new()
+                };
+
+                self.kill(content, cursor, to, true, last)
+            },
+            (false, true, Name::Char('d')) => {
+                let text: Vec<char> = buffer::text(content).chars().collect();
+                let to = forward_word(&text, buffer::offset(content, cursor));
+
+                self.kill(content, cursor, buffer::position_at(content, to), true, last)
+            },
+            (false, true, Name::Backspace) => {
+                let text: Vec<char> = buffer::text(content).chars().collect();
+                let from = backward_word(&text, buffer::offset(content, cursor));
+
+                self.kill(content, buffer::position_at(content, from), cursor, false, last)
+            },
+            (true, false, Name::Char('w')) => match self.region(content) {
+                Some((from, to)) => self.kill(content, from, to, true, last),
+                None => Vec::new()
+            },
+            (false, true, Name::Char('w')) => match self.region(content) {
+                Some((from, to)) => {
+                    self.push_kill(buffer::text_between(content, from, to), Last::Other);
+                    self.mark = None;
+                    self.message = Some(String::from("Saved text from region"));
+
+                    vec![Effect::MoveTo(cursor)]
+                },
+                None => Vec::new()
+            },
+            (true, false, Name::Char('y')) => self.yank(0, buffer::offset(content, cursor)),
+            (false, true, Name::Char('y')) => match (last, self.yank) {
+                (Last::Yank, Some(yank)) => {
+                    let index = (yank.index + 1) % self.kill_ring.len().max(1);
+                    let anchor = buffer::position_at(content, yank.start);
+                    let head = buffer::position_at(content, yank.start + yank.length);
+
+                    let mut effects = vec![Effect::Select { anchor, head }];
+                    effects.extend(self.yank(index, yank.start));
+
+                    effects
+                },
+                _ => {
+                    self.message = Some(String::from("Previous command was not a yank"));
+                    Vec::new()
+                }
+            },
+            (true, false, Name::Char(' ' | '@')) => {
+                self.mark = Some(cursor);
+                self.message = Some(String::from("Mark set"));
+
+                vec![Effect::MoveTo(cursor)]
+            },
+            (true, false, Name::Char('g')) | (false, false, Name::Escape) => {
+                self.mark = None;
+                self.message = Some(String::from("Quit"));
+
+                vec![Effect::MoveTo(cursor)]
+            },
+            (true, false, Name::Char('/' | '_')) => vec![Effect::Undo],
+            (true, false, Name::Char(direction @ ('s' | 'r'))) => {
+                self.mark = None;
+                self.search = Some(Search {
+                    query: String::new(),
+                    forward: direction == 's',
+                    origin: cursor,
+                    found: None,
+                    failing: false,
+                });
+
+                Vec::new()
+            },
+            (true, false, Name::Char('x')) => {
+                self.prefix = true;
+                Vec::new()
+            },
+            (false, true, Name::Char('x')) => vec![Effect::ShowPalette],
+            _ => Vec::new()
+        }
+    }
+
+    /// The second key of a `C-x` command.
+    fn prefixed(&mut self, key: Key) -> Vec<Effect> {
+        match (key.control, key.name) {
+            (true, Name::Char('s')) => vec![Effect::Save],
+            (true, Name::Char('f')) => vec![Effect::Open(None)],
+            (true, Name::Char('c')) => vec![Effect::Quit { force: false }],
+            (false, Name::Char('u')) => vec![Effect::Undo],
+            (false, Name::Char('h')) => {
+                self.mark = Some(Position::default());
+
+                vec![
+                    Effect::MoveTo(Position::default()),
+                    Effect::Perform(Action::Select(Motion::DocumentEnd)),
+                ]
+            },
+            (true, Name::Char('g')) | (_, Name::Escape) => {
+                self.message = Some(String::from("Quit"));
+                Vec::new()
+            },
+            (control, name) => {
+                let key = match name {
+                    Name::Char(character) => character.to_string(),
+                    other => format!("{other:?}")
+                };
+
+                self.message = Some(match control {
+                    true => format!("C-x C-{key} is undefined"),
+                    false => format!("C-x {key} is undefined")
+                });
+
+                Vec::new()
+            }
+        }
+    }
+
+    /// A motion, which extends the region while the mark is set.
+    fn motion(&self, motion: Motion) -> Vec<Effect> {
+        let action = match self.mark {
+            Some(_) => Action::Select(motion),
+            None => Action::Move(motion)
+        };
+
+        vec![Effect::Perform(action)]
+    }
+
+    fn edit(&mut self, edit: Edit) -> Vec<Effect> {
+        self.mark = None;
+
+        vec![Effect::Perform(Action::Edit(edit))]
+    }
+
+    /// The text between the mark and the cursor, start first.
+    fn region(&mut self, content: &Content) -> Option<(Position, Position)> {
+        let Some(mark) = self.mark else {
+            self.message = Some(String::from("The mark is not set now, so there is no region"));
+            return None;
+        };
+
+        let cursor = buffer::cursor(content);
+
+        Some((mark.min(cursor), mark.max(cursor)))
+    }
+
+    /// Deletes the text between `from` and `to` into the kill ring.
+    fn kill(&mut self, content: &Content, from: Position, to: Position, forward: bool, last: Last) -> Vec<Effect> {
+        self.mark = None;
+
+        if from == to {
+            return Vec::new();
+        }
+
+        let text = buffer::text_between(content, from, to);
+
+        match (last, self.kill_ring.front_mut()) {
+            (Last::Kill, Some(entry)) if forward => entry.push_str(&text),
+            (Last::Kill, Some(entry)) => entry.insert_str(0, &text),
+            _ => self.push_kill(text, Last::Kill)
+        }
+
+        self.last = Last::Kill;
+
+        vec![
+            Effect::Select { anchor: from, head: to },
+            Effect::Perform(Action::Edit(Edit::Delete)),
+        ]
+    }
+
+    fn push_kill(&mut self, text: String, last: Last) {
+        self.kill_ring.push_front(text);
+        self.kill_ring.truncate(KILL_RING_SIZE);
+        self.last = last;
+    }
+
+    /// Inserts the kill ring entry at `index`, which ends up starting at `start`.
+    ///
+    /// A selection is replaced, that's how `M-y` swaps out the last yank.
+    fn yank(&mut self, index: usize, start: usize) -> Vec<Effect> {
+        let Some(text) = self.kill_ring.get(index).cloned() else {
+            self.message = Some(String::from("Kill ring is empty"));
+            return Vec::new();
+        };
+
+        self.mark = None;
+        self.last = Last::Yank;
+
+        self.yank = Some(Yank {
+            start,
+            length: text.chars().count(),
+            index,
+        });
+
+        vec![Effect::Perform(Action::Edit(Edit::Paste(text.into())))]
+    }
+
+    /// Handles a key during an incremental search, `None` when the key ends
+    /// the search and should then run as usual.
+    fn search_key(&mut self, key: Key, content: &Content) -> Option<Vec<Effect>> {
+        let search = self.search.as_mut()?;
+
+        match (key.control, key.meta, key.name) {
+            (true, false, Name::Char(direction @ ('s' | 'r'))) => {
+                let forward = direction == 's';
+
+                if search.query.is_empty() {
+                    search.query = self.last_query.clone();
+                }
+
+                // Pressing it again moves on to the next match.
+                let from = match (search.found, forward) {
+                    (Some((start, _)), true) => Some(start + 1),
+                    (Some((start, _)), false) => start.checked_sub(1),
+                    (None, _) => Some(buffer::offset(content, search.origin))
+                };
+
+                search.forward = forward;
+
+                Some(match from {
+                    Some(from) => self.find(content, from),
+                    None => {
+                        search.failing = true;
+                        Vec::new()
+                    }
+                })
+            },
+            (false, false, Name::Char(character)) => {
+                search.query.push(character);
+
+                let from = match search.found {
+                    Some((start, _)) => start,
+                    None => buffer::offset(content, search.origin)
+                };
+
+                Some(self.find(content, from))
+            },
+            (false, false, Name::Backspace) => {
+                search.query.pop();
+                search.found = None;
+
+                let origin = search.origin;
+
+                Some(match search.query.is_empty() {
+                    true => vec![Effect::MoveTo(origin)],
+                    false => self.find(content, buffer::offset(content, origin))
+                })
+            },
+            (true, false, Name::Char('g')) | (false, false, Name::Escape) => {
+                let origin = search.origin;
+
+                self.search = None;
+
+                Some(vec![Effect::MoveTo(origin)])
+            },
+            (false, false, Name::Enter) => {
+                self.finish_search();
+
+                Some(vec![Effect::MoveTo(buffer::cursor(content))])
+            },
+            _ => {
+                self.finish_search();
+                None
+            }
+        }
+    }
+
+    fn finish_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            if !search.query.is_empty() {
+                self.last_query = search.query;
+            }
+        }
+    }
+
+    /// Looks for the query starting at `from`, forwards or backwards, and
+    /// selects the match.
+    fn find(&mut self, content: &Content, from: usize) -> Vec<Effect> {
+        let Some(search) = self.search.as_mut() else {
+            return Vec::new();
+        };
+
+        if search.query.is_empty() {
+            return Vec::new();
+        }
+
+        // Like Emacs the search ignores case until the query has an uppercase letter.
+        let ignore_case = !search.query.chars().any(char::is_uppercase);
+        let fold = |character: char| match ignore_case {
+            true => character.to_lowercase().next().unwrap_or(character),
+            false => character
+        };
+
+        let text: Vec<char> = buffer::text(content).chars().map(fold).collect();
+        let query: Vec<char> = search.query.chars().map(fold).collect();
+
+        let Some(last) = text.len().checked_sub(query.len()) else {
+            search.failing = true;
+            return Vec::new();
+        };
+
+        let matches = |start: &usize| text[*start..*start + query.len()] == query[..];
+
+        let found = match search.forward {
+            true => (from.min(last + 1)..=last).find(matches),
+            false => (0..=from.min(last)).rev().find(matches)
+        };
+
+        let Some(start) = found else {
+            search.failing = true;
+            return Vec::new();
+        };
+
+        let end = start + query.len();
+
+        search.found = Some((start, end));
+        search.failing = false;
+
+        let (start, end) = (buffer::position_at(content, start), buffer::position_at(content, end));
+
+        vec![match search.forward {
+            true => Effect::Select { anchor: start, head: end },
+            false => Effect::Select { anchor: end, head: start }
+        }]
+    }
+}
+
+fn is_word(character: char) -> bool {
+    character.is_alphanumeric()
+}
+
+/// Where `M-f` stops: after the end of the next word.
+fn forward_word(text: &[char], offset: usize) -> usize {
+    let mut index = offset;
+
+    while text.get(index).is_some_and(|&character| !is_word(character)) {
+        index += 1;
+    }
+
+    while text.get(index).is_some_and(|&character| is_word(character)) {
+        index += 1;
+    }
+
+    index.min(text.len())
+}
+
+/// Where `M-b` stops: at the start of the previous word.
+fn backward_word(text: &[char], offset: usize) -> usize {
+    let mut index = offset.min(text.len());
+
+    while index > 0 && !is_word(text[index - 1]) {
+        index -= 1;
+    }
+
+    while index > 0 && is_word(text[index - 1]) {
+        index -= 1;
+    }
+
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: Name) -> Key {
+        Key { control: false, meta: false, name }
+    }
+
+    fn control(character: char) -> Key {
+        Key { control: true, ..key(Name::Char(character)) }
+    }
+
+    fn meta(character: char) -> Key {
+        Key { meta: true, ..key(Name::Char(character)) }
+    }
+
+    fn selected(effects: &[Effect]) -> Option<(Position, Position)> {
+        match effects {
+            [Effect::Select { anchor, head }, ..] => Some((*anchor, *head)),
+            _ => None
+        }
+    }
+
+    #[test]
+    fn refusing_to_quit_shows_until_the_next_key() {
+        let mut emacs = Emacs::default();
+        let content = Content::with_text("hello");
+
+        emacs.refuse_quit();
+        assert_eq!(emacs.status(), "Buffer modified, save it first with C-x C-s");
+
+        emacs.key(control('f'), &content);
+        assert_eq!(emacs.status(), "");
+    }
+
+    #[test]
+    fn kills_in_a_row_grow_one_entry() {
+        let mut emacs = Emacs::default();
+
+        let effects = emacs.key(meta('d'), &Content::with_text("hello world"));
+        assert_eq!(selected(&effects), Some((Position::new(0, 0), Position::new(0, 5))));
+        assert!(matches!(effects[1], Effect::Perform(Action::Edit(Edit::Delete))));
+
+        emacs.key(meta('d'), &Content::with_text(" world"));
+        assert_eq!(emacs.kill_ring, ["hello world"]);
+
+        emacs.key(control('f'), &Content::with_text(""));
+        emacs.key(control('k'), &Content::with_text("again"));
+        assert_eq!(emacs.kill_ring, ["again", "hello world"]);
+    }
+
+    #[test]
+    fn yanking_again_cycles_through_the_kill_ring() {
+        let content = Content::with_text("one\ntwo");
+        let mut emacs = Emacs::default();
+
+        emacs.push_kill(String::from("first"), Last::Other);
+        emacs.push_kill(String::from("second"), Last::Other);
+
+        let effects = emacs.key(control('y'), &content);
+        assert!(matches!(&effects[..], [Effect::Perform(Action::Edit(Edit::Paste(text)))] if text.as_str() == "second"));
+
+        // What the editor looks like once the yank was pasted.
+        let effects = emacs.key(meta('y'), &Content::with_text("second\none\ntwo"));
+        assert_eq!(selected(&effects), Some((Position::new(0, 0), Position::new(0, 6))));
+        assert!(matches!(&effects[1], Effect::Perform(Action::Edit(Edit::Paste(text))) if text.as_str() == "first"));
+
+        emacs.key(control('f'), &content);
+        assert!(emacs.key(meta('y'), &content).is_empty());
+        assert_eq!(emacs.status(), "Previous command was not a yank");
+    }
+
+    #[test]
+    fn motions_select_while_the_mark_is_set() {
+        let content = Content::with_text("text");
+        let mut emacs = Emacs::default();
+
+        assert!(matches!(emacs.key(control('f'), &content)[..], [Effect::Perform(Action::Move(Motion::Right))]));
+
+        emacs.key(control(' '), &content);
+        assert_eq!(emacs.status(), "Mark set");
+        assert!(matches!(emacs.key(control('f'), &content)[..], [Effect::Perform(Action::Select(Motion::Right))]));
+
+        emacs.key(control('g'), &content);
+        assert!(matches!(emacs.key(control('f'), &content)[..], [Effect::Perform(Action::Move(Motion::Right))]));
+    }
+
+    #[test]
+    fn prefixed_commands_wait_for_their_second_key() {
+        let content = Content::with_text("");
+        let mut emacs = Emacs::default();
+
+        assert!(emacs.key(control('x'), &content).is_empty());
+        assert_eq!(emacs.status(), "C-x-");
+        assert!(matches!(emacs.key(control('s'), &content)[..], [Effect::Save]));
+
+        emacs.key(control('x'), &content);
+        emacs.key(key(Name::Char('z')), &content);
+        assert_eq!(emacs.status(), "C-x z is undefined");
+    }
+
+    #[test]
+    fn incremental_search_moves_from_match_to_match() {
+        let content = Content::with_text("hello world\nwow");
+        let mut emacs = Emacs::default();
+
+        emacs.key(control('s'), &content);
+        emacs.key(key(Name::Char('w')), &content);
+
+        let effects = emacs.key(key(Name::Char('o')), &content);
+        assert_eq!(selected(&effects), Some((Position::new(0, 6), Position::new(0, 8))));
+        assert_eq!(emacs.status(), "I-search: wo");
+
+        let effects = emacs.key(control('s'), &content);
+        assert_eq!(selected(&effects), Some((Position::new(1, 0), Position::new(1, 2))));
+
+        assert!(emacs.key(control('s'), &content).is_empty());
+        assert_eq!(emacs.status(), "Failing I-search: wo");
+
+        assert!(matches!(emacs.key(key(Name::Escape), &content)[..], [Effect::MoveTo(Position { line: 0, column: 0 })]));
+        assert_eq!(emacs.status(), "");
+    }
+
+    #[test]
+    fn searching_cares_about_case_once_the_query_does() {
+        let content = Content::with_text("Word word");
+        let mut emacs = Emacs::default();
+
+        emacs.key(control('s'), &content);
+        let effects = emacs.key(key(Name::Char('w')), &content);
+        assert_eq!(selected(&effects), Some((Position::new(0, 0), Position::new(0, 1))));
+
+        emacs.key(key(Name::Backspace), &content);
+        let effects = emacs.key(key(Name::Char('W')), &content);
+        assert_eq!(selected(&effects), Some((Position::new(0, 0), Position::new(0, 1))));
+
+        assert!(emacs.key(control('s'), &content).is_empty());
+    }
+
+    #[test]
+    fn word_motions_skip_punctuation() {
+        let text: Vec<char> = "foo, bar".chars().collect();
+
+        assert_eq!(forward_word(&text, 0), 3);
+        assert_eq!(forward_word(&text, 3), 8);
+        assert_eq!(backward_word(&text, 8), 5);
+        assert_eq!(backward_word(&text, 5), 0);
+    }
+}
\ No newline at end of file
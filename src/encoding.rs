@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+
+/// How the characters of a file are stored on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Charset {
+    #[default]
+    Utf8,
+    Utf8Bom,
+    Latin1,
+    Utf16Le,
+    Utf16Be,
+}
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+impl Charset {
+    /// Picks the charset from the byte order mark of a file, if it has one.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(UTF8_BOM) {
+            Some(Charset::Utf8Bom)
+        } else if bytes.starts_with(&[0xFF, 0xFE]) {
+            Some(Charset::Utf16Le)
+        } else if bytes.starts_with(&[0xFE, 0xFF]) {
+            Some(Charset::Utf16Be)
+        } else {
+            None
+        }
+    }
+
+    /// Decodes a file, `None` if the bytes aren't valid in this charset.
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        match self {
+            Charset::Utf8 => String::from_utf8(bytes.to_vec()).ok(),
+            Charset::Utf8Bom => String::from_utf8(bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes).to_vec()).ok(),
+            Charset::Latin1 => Some(bytes.iter().map(|byte| *byte as char).collect()),
+            Charset::Utf16Le | Charset::Utf16Be => {
+                if !bytes.len().is_multiple_of(2) {
+                    return None;
+                }
+
+                let units = bytes.chunks_exact(2).map(|pair| match self {
+                    Charset::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                    _ => u16::from_be_bytes([pair[0], pair[1]]),
+                });
+
+                let text = char::decode_utf16(units).collect::<Result<String, _>>().ok()?;
+
+                Some(text.strip_prefix('\u{FEFF}').map(str::to_owned).unwrap_or(text))
+            }
+        }
+    }
+
+    /// Encodes text for writing to disk, characters latin1 can't hold become `?`.
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        match self {
+            Charset::Utf8 => text.as_bytes().to_vec(),
+            Charset::Utf8Bom => [UTF8_BOM, text.as_bytes()].concat(),
+            Charset::Latin1 => text.chars()
+                .map(|character| u8::try_from(u32::from(character)).unwrap_or(b'?'))
+                .collect(),
+            Charset::Utf16Le => std::iter::once(0xFEFF).chain(text.encode_utf16())
+                .flat_map(u16::to_le_bytes)
+                .collect(),
+            Charset::Utf16Be => std::iter::once(0xFEFF).chain(text.encode_utf16())
+                .flat_map(u16::to_be_bytes)
+                .collect(),
+        }
+    }
+}
+
+/// The character sequence that ends a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+    Cr,
+}
+
+impl LineEnding {
+    /// Picks whichever line ending shows up first in `text`.
+    pub fn detect(text: &str) -> Option<Self> {
+        let index = text.find(['\r', '\n'])?;
+
+        match &text[index..] {
+            rest if rest.starts_with("\r\n") => Some(LineEnding::CrLf),
+            rest if rest.starts_with('\r') => Some(LineEnding::Cr),
+            _ => Some(LineEnding::Lf),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Cr => "\r",
+        }
+    }
+
+    /// Turns `text` with `\n` line endings into text using this line ending.
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            LineEnding::Lf => text.to_owned(),
+            _ => text.replace('\n', self.as_str()),
+        }
+    }
+}
+
+/// Turns any line endings in `text` into `\n`, which is what the editor works with.
+pub fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn charsets_round_trip() {
+        let text = "héllo\nwörld €";
+
+        for charset in [Charset::Utf8, Charset::Utf8Bom, Charset::Utf16Le, Charset::Utf16Be] {
+            let bytes = charset.encode(text);
+
+            assert_eq!(Charset::detect(&bytes).unwrap_or_default(), charset);
+            assert_eq!(charset.decode(&bytes).as_deref(), Some(text));
+        }
+    }
+
+    #[test]
+    fn latin1_replaces_what_it_cannot_hold() {
+        assert_eq!(Charset::Latin1.encode("é€"), [0xE9, b'?']);
+        assert_eq!(Charset::Latin1.decode(&[0xE9]).as_deref(), Some("é"));
+        assert_eq!(Charset::Utf16Le.decode(&[0xFF, 0xFE, 0x41]), None);
+    }
+
+    #[test]
+    fn line_endings_are_detected_and_applied() {
+        assert_eq!(LineEnding::detect("a\r\nb\n"), Some(LineEnding::CrLf));
+        assert_eq!(LineEnding::detect("a\rb"), Some(LineEnding::Cr));
+        assert_eq!(LineEnding::detect("a\nb\r\n"), Some(LineEnding::Lf));
+        assert_eq!(LineEnding::detect("a"), None);
+
+        assert_eq!(normalize_line_endings("a\r\nb\rc\n"), "a\nb\nc\n");
+        assert_eq!(LineEnding::CrLf.apply("a\nb\n"), "a\r\nb\r\n");
+    }
+}
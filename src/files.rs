@@ -1,11 +1,19 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{io::ErrorKind, path::PathBuf, sync::Arc};
 
 use tokio::fs;
 use rfd::AsyncFileDialog;
 
-use crate::GFEError;
+use crate::{editorconfig::EditorConfig, encoding::{self, Charset, LineEnding}, GFEError};
 
-pub async fn pick_file() -> Result<(PathBuf, Arc<String>), GFEError> {
+/// How a file is stored on disk, so saving writes it back the same way.
+#[derive(Debug, Clone, Default)]
+pub struct FileFormat {
+    pub charset: Charset,
+    pub line_ending: LineEnding,
+    pub editorconfig: EditorConfig,
+}
+
+pub async fn pick_file() -> Result<(PathBuf, Arc<String>, FileFormat), GFEError> {
     let file_handle = AsyncFileDialog::new().set_title("Choose a text file...")
         .pick_file()
         .await
@@ -14,21 +22,78 @@ pub async fn pick_file() -> Result<(PathBuf, Arc<String>), GFEError> {
     load_file(file_handle.path().to_owned()).await
 }
 
-pub async fn load_file(path: PathBuf) -> Result<(PathBuf, Arc<String>), GFEError> {
-    let contents = fs::read_to_string(&path)
+pub async fn load_file(path: PathBuf) -> Result<(PathBuf, Arc<String>, FileFormat), GFEError> {
+    let bytes = fs::read(&path)
         .await
-        .map(Arc::new)
         .map_err(|error| error.kind())
         .map_err(GFEError::IO)?;
 
-    Ok((path, contents))
+    let editorconfig = EditorConfig::resolve(&path);
+
+    let charset = Charset::detect(&bytes)
+        .or(editorconfig.charset)
+        .unwrap_or_default();
+
+    let contents = charset.decode(&bytes).ok_or(GFEError::IO(ErrorKind::InvalidData))?;
+
+    let line_ending = editorconfig.end_of_line
+        .or(LineEnding::detect(&contents))
+        .unwrap_or_default();
+
+    let format = FileFormat {
+        charset: editorconfig.charset.unwrap_or(charset),
+        line_ending,
+        editorconfig,
+    };
+
+    Ok((path, Arc::new(encoding::normalize_line_endings(&contents)), format))
 }
 
-pub async fn save_file(path: PathBuf, contents: String) -> Result<(), GFEError> {
-    fs::write(&path, contents)
+pub async fn save_file(path: PathBuf, contents: String, format: FileFormat) -> Result<(), GFEError> {
+    let bytes = format.charset.encode(&format.line_ending.apply(&contents));
+
+    fs::write(&path, bytes)
         .await
         .map_err(|error| error.kind())
         .map_err(GFEError::IO)?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `bytes` to a fresh file with `.editorconfig` next to it, then opens and saves it again.
+    async fn round_trip(name: &str, editorconfig: &str, bytes: &[u8]) -> Vec<u8> {
+        let directory = std::env::temp_dir().join(format!("gfe-files-{name}-{}", std::process::id()));
+        let path = directory.join("file.txt");
+
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join(".editorconfig"), format!("root = true\n\n[*]\n{editorconfig}")).unwrap();
+        std::fs::write(&path, bytes).unwrap();
+
+        let (path, contents, format) = load_file(path).await.unwrap();
+        save_file(path.clone(), contents.to_string(), format).await.unwrap();
+
+        let saved = std::fs::read(&path).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        saved
+    }
+
+    // Trimming and final newlines are up to the save pipeline, not even `.editorconfig` changes the text here.
+    #[tokio::test]
+    async fn save_file_writes_the_text_as_given() {
+        let saved = round_trip("newline", "trim_trailing_whitespace = true\n", b"a  \nb").await;
+
+        assert_eq!(saved, b"a  \nb");
+    }
+
+    #[tokio::test]
+    async fn saving_keeps_the_charset_and_line_endings() {
+        let bytes = Charset::Utf16Le.encode("one\r\ntwo\r\n");
+
+        assert_eq!(round_trip("format", "", &bytes).await, bytes);
+    }
+}
@@ -0,0 +1,559 @@
+use iced::widget::text_editor::{Content, Edit};
+use serde::{Deserialize, Serialize};
+
+use crate::buffer::{self, Position};
+use crate::decorations::Decoration;
+use crate::viewport::{self, VisibleLine};
+
+/// Lines taken out of the buffer and hidden under the line above them.
+///
+/// The `text_editor` can't skip lines while drawing, so a fold removes its
+/// lines from the [`Content`] and keeps them here until it's opened again.
+#[derive(Debug, Clone, PartialEq)]
+struct Fold {
+    /// Line of the buffer the fold hangs off, it stays visible.
+    line: usize,
+    /// The hidden lines as they were in the buffer.
+    hidden: Vec<String>,
+    /// Folds that were closed among the hidden lines, counted from the first of them.
+    nested: Vec<Fold>,
+}
+
+impl Fold {
+    /// How many lines of the document the fold hides.
+    fn size(&self) -> usize {
+        self.hidden.len() + self.nested.iter().map(Fold::size).sum::<usize>()
+    }
+
+    /// The hidden lines with the nested folds opened.
+    fn document_lines(&self) -> Vec<String> {
+        expand(&self.hidden, &self.nested)
+    }
+}
+
+/// A folded range in lines of the document, how folds are remembered between runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FoldRange {
+    /// Line the fold hangs off.
+    pub start: usize,
+    /// Last line it hides.
+    pub end: usize,
+}
+
+/// The closed folds of a buffer, sorted by the line they hang off.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Folds {
+    folds: Vec<Fold>,
+}
+
+impl Folds {
+    pub fn is_empty(&self) -> bool {
+        self.folds.is_empty()
+    }
+
+    /// Whether lines are folded under `line` of the buffer.
+    pub fn is_folded(&self, line: usize) -> bool {
+        self.folds.iter().any(|fold| fold.line == line)
+    }
+
+    /// The line of the document that's on `line` of the buffer.
+    pub fn document_line(&self, line: usize) -> usize {
+        line + self.folds.iter()
+            .take_while(|fold| fold.line < line)
+            .map(Fold::size)
+            .sum::<usize>()
+    }
+
+    /// The line of the buffer showing `line` of the document, and whether
+    /// it's hidden under that one.
+    pub fn buffer_line(&self, line: usize) -> (usize, bool) {
+        let mut hidden = 0;
+
+        for fold in &self.folds {
+            let start = fold.line + hidden;
+
+            if line <= start {
+                break;
+            }
+
+            if line <= start + fold.size() {
+                return (fold.line, true);
+            }
+
+            hidden += fold.size();
+        }
+
+        (line - hidden, false)
+    }
+
+    /// The text of the whole document, the folded lines put back in.
+    pub fn document_text(&self, content: &Content) -> String {
+        let lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
+
+        expand(&lines, &self.folds).join("\n")
+    }
+
+    /// Folds the lines indented deeper than `line` below it.
+    pub fn fold(&mut self, content: &mut Content, line: usize) -> bool {
+        match region_end(content, line) {
+            Some(end) if !self.is_folded(line) => self.fold_range(content, line, end),
+            _ => false
+        }
+    }
+
+    /// Opens the fold under `line`.
+    pub fn unfold(&mut self, content: &mut Content, line: usize) -> bool {
+        let Some(index) = self.folds.iter().position(|fold| fold.line == line) else {
+            return false;
+        };
+
+        let fold = self.folds.remove(index);
+        let count = fold.hidden.len();
+
+        let (anchor, head) = selection(content);
+        let shift = |position: Position| match position.line > line {
+            true => Position::new(position.line + count, position.column),
+            false => position
+        };
+
+        let end = Position::new(line, buffer::line_length(content, line));
+        buffer::replace(content, end, end, &format!("\n{}", fold.hidden.join("\n")));
+        restore_selection(content, shift(anchor), shift(head));
+
+        self.shift(line, count as isize);
+
+        self.folds.extend(fold.nested.into_iter().map(|nested| Fold { line: nested.line + line + 1, ..nested }));
+        self.folds.sort_by_key(|fold| fold.line);
+
+        true
+    }
+
+    pub fn toggle(&mut self, content: &mut Content, line: usize) -> bool {
+        match self.is_folded(line) {
+            true => self.unfold(content, line),
+            false => self.fold(content, line)
+        }
+    }
+
+    /// The line to open or close for the cursor on `line` of the buffer: the
+    /// fold hanging off it, or the region it's in.
+    pub fn toggle_target(&self, content: &Content, line: usize) -> Option<usize> {
+        match self.is_folded(line) {
+            true => Some(line),
+            false => enclosing_region(content, line)
+        }
+    }
+
+    /// Folds every region, inner ones first so they stay folded when the outer one is opened.
+    pub fn fold_all(&mut self, content: &mut Content) {
+        for line in (0..content.line_count()).rev() {
+            self.fold(content, line);
+        }
+    }
+
+    pub fn unfold_all(&mut self, content: &mut Content) {
+        // From the bottom up, so opening one doesn't move the lines of the next.
+        while let Some(line) = self.folds.last().map(|fold| fold.line) {
+            self.unfold(content, line);
+        }
+    }
+
+    /// The marks after the visible lines with lines folded under them.
+    pub fn decorations(&self, content: &Content, visible: &[VisibleLine], columns: usize) -> Vec<Decoration> {
+        visible.iter()
+            .filter(|line| self.is_folded(line.line))
+            .filter_map(|line| {
+                let end = Position::new(line.line, buffer::line_length(content, line.line));
+                viewport::locate(visible, end, columns)
+            })
+            .map(|(row, column)| Decoration::Folded { row, column })
+            .collect()
+    }
+
+    /// The visible lines the gutter can fold or open, and whether they're folded.
+    pub fn toggles(&self, content: &Content, visible: &[VisibleLine]) -> Vec<(usize, bool)> {
+        visible.iter()
+            .filter(|line| self.is_folded(line.line) || is_foldable(content, line.line))
+            .map(|line| (line.line, self.is_folded(line.line)))
+            .collect()
+    }
+
+    /// The line numbers of the document to show for the visible lines, none
+    /// while nothing is folded and they're the buffer's.
+    pub fn numbers(&self, visible: &[VisibleLine]) -> Vec<usize> {
+        match self.is_empty() {
+            true => Vec::new(),
+            false => visible.iter().map(|line| self.document_line(line.line)).collect()
+        }
+    }
+
+    /// Opens the folds `line` of the document is hidden in, returns the line of the buffer it ends up on.
+    pub fn reveal(&mut self, content: &mut Content, line: usize) -> usize {
+        loop {
+            match self.buffer_line(line) {
+                (line, true) => self.unfold(content, line),
+                (line, false) => return line
+            };
+        }
+    }
+
+    /// Opens the folds hanging off the lines `first..=last` of the buffer.
+    pub fn unfold_lines(&mut self, content: &mut Content, first: usize, last: usize) {
+        let lines: Vec<usize> = self.folds.iter()
+            .map(|fold| fold.line)
+            .filter(|line| (first..=last).contains(line))
+            .collect();
+
+        for line in lines.into_iter().rev() {
+            self.unfold(content, line);
+        }
+    }
+
+    /// Opens the folds hanging off the selected lines, and `margin` lines around them.
+    pub fn unfold_selection(&mut self, content: &mut Content, margin: usize) {
+        loop {
+            let (from, to) = selection(content);
+            let (first, last) = (from.line.saturating_sub(margin), to.line + margin);
+
+            // Opening one can bring back nested folds that are selected too.
+            if !self.folds.iter().any(|fold| (first..=last).contains(&fold.line)) {
+                return;
+            }
+
+            self.unfold_lines(content, first, last);
+        }
+    }
+
+    /// Opens the folds an edit at the cursor would join or split lines of,
+    /// returns the lines it touches when it can add or remove some.
+    pub fn unfold_for_edit(&mut self, content: &mut Content, edit: &Edit) -> Option<(usize, usize)> {
+        loop {
+            let (first, last) = touched_lines(content, edit)?;
+
+            if !self.folds.iter().any(|fold| (first..=last).contains(&fold.line)) {
+                return Some((first, last));
+            }
+
+            self.unfold_lines(content, first, last);
+        }
+    }
+
+    /// Moves the folds below `line` along with lines added or removed above them.
+    pub fn shift(&mut self, line: usize, lines: isize) {
+        for fold in self.folds.iter_mut().filter(|fold| fold.line > line) {
+            fold.line = fold.line.saturating_add_signed(lines);
+        }
+    }
+
+    /// Where the folds are in the document, nested ones included.
+    pub fn ranges(&self) -> Vec<FoldRange> {
+        let mut ranges = Vec::new();
+        let mut hidden = 0;
+
+        for fold in &self.folds {
+            let start = fold.line + hidden;
+
+            collect_ranges(fold, start, &mut ranges);
+            hidden += fold.size();
+        }
+
+        ranges
+    }
+
+    /// Closes the folds of `ranges` again, e.g. when the file is reopened.
+    pub fn restore(&mut self, content: &mut Content, ranges: &[FoldRange]) {
+        let mut ranges = ranges.to_vec();
+
+        // Inner folds first, the outer ones take them in.
+        ranges.sort_by_key(|range| range.end - range.start);
+
+        for range in ranges {
+            let line_count = content.line_count() + self.folds.iter().map(Fold::size).sum::<usize>();
+
+            let (start, start_hidden) = self.buffer_line(range.start);
+            let (end, _) = self.buffer_line(range.end);
+
+            if !start_hidden && range.end < line_count && end > start {
+                self.fold_range(content, start, end);
+            }
+        }
+    }
+
+    /// Hides the lines after `line` up to and including `end`.
+    fn fold_range(&mut self, content: &mut Content, line: usize, end: usize) -> bool {
+        if end <= line || end >= content.line_count() {
+            return false;
+        }
+
+        let hidden: Vec<String> = (line + 1..=end)
+            .map(|line| content.line(line).map(|line| line.to_string()).unwrap_or_default())
+            .collect();
+
+        let count = hidden.len();
+
+        // Folds among the hidden lines go with them.
+        let (nested, folds): (Vec<Fold>, Vec<Fold>) = std::mem::take(&mut self.folds)
+            .into_iter()
+            .partition(|fold| fold.line > line && fold.line <= end);
+
+        self.folds = folds;
+
+        let nested = nested.into_iter().map(|fold| Fold { line: fold.line - line - 1, ..fold }).collect();
+
+        let (anchor, head) = selection(content);
+        let header_end = Position::new(line, buffer::line_length(content, line));
+
+        // Whatever was selected in the hidden lines ends up at the end of the one they hang off.
+        let shift = |position: Position| match position.line {
+            row if row > end => Position::new(row - count, position.column),
+            row if row > line => header_end,
+            _ => position
+        };
+
+        buffer::replace(content, header_end, Position::new(end, buffer::line_length(content, end)), "");
+        restore_selection(content, shift(anchor), shift(head));
+
+        self.shift(end, -(count as isize));
+        self.folds.push(Fold { line, hidden, nested });
+        self.folds.sort_by_key(|fold| fold.line);
+
+        true
+    }
+}
+
+/// `lines` with the lines of `folds` put back under them.
+fn expand(lines: &[String], folds: &[Fold]) -> Vec<String> {
+    let mut expanded = Vec::with_capacity(lines.len());
+    let mut folds = folds.iter().peekable();
+
+    for (index, line) in lines.iter().enumerate() {
+        expanded.push(line.clone());
+
+        while let Some(fold) = folds.next_if(|fold| fold.line == index) {
+            expanded.extend(fold.document_lines());
+        }
+    }
+
+    expanded
+}
+
+fn collect_ranges(fold: &Fold, start: usize, ranges: &mut Vec<FoldRange>) {
+    ranges.push(FoldRange { start, end: start + fold.size() });
+
+    let mut hidden = 0;
+
+    for nested in &fold.nested {
+        let nested_start = start + 1 + nested.line + hidden;
+
+        collect_ranges(nested, nested_start, ranges);
+        hidden += nested.size();
+    }
+}
+
+/// The selection of `content` as anchor and head, both the cursor when nothing is selected.
+fn selection(content: &Content) -> (Position, Position) {
+    let cursor = buffer::cursor(content);
+
+    buffer::selection(content).unwrap_or((cursor, cursor))
+}
+
+fn restore_selection(content: &mut Content, anchor: Position, head: Position) {
+    match anchor == head {
+        true => buffer::move_to(content, head),
+        false => buffer::select(content, anchor, head)
+    }
+}
+
+/// How far `line` is indented, `None` when it's blank.
+fn line_indentation(content: &Content, line: usize) -> Option<usize> {
+    let text = content.line(line)?;
+
+    match text.trim().is_empty() {
+        true => None,
+        false => Some(text.chars().take_while(|character| character.is_whitespace()).count())
+    }
+}
+
+/// Whether the lines after `line` are indented deeper, so it can be folded.
+pub fn is_foldable(content: &Content, line: usize) -> bool {
+    let Some(base) = line_indentation(content, line) else {
+        return false;
+    };
+
+    (line + 1..content.line_count())
+        .find_map(|next| line_indentation(content, next))
+        .is_some_and(|next| next > base)
+}
+
+/// The line whose fold `line` is in: itself when it can be folded, or the
+/// closest one above that's indented less.
+pub fn enclosing_region(content: &Content, line: usize) -> Option<usize> {
+    if is_foldable(content, line) {
+        return Some(line);
+    }
+
+    let own = (line..content.line_count()).find_map(|next| line_indentation(content, next))?;
+
+    (0..line).rev()
+        .find(|above| line_indentation(content, *above).is_some_and(|indentation| indentation < own))
+        .filter(|above| is_foldable(content, *above))
+}
+
+/// The last line of the region that folds under `line`: everything indented
+/// deeper than it, without the blank lines at the end.
+fn region_end(content: &Content, line: usize) -> Option<usize> {
+    let base = line_indentation(content, line)?;
+    let mut end = None;
+
+    for next in line + 1..content.line_count() {
+        match line_indentation(content, next) {
+            Some(indentation) if indentation > base => end = Some(next),
+            Some(_) => break,
+            None => {}
+        }
+    }
+
+    end
+}
+
+/// The lines an edit at the cursor could join or split, `None` when it
+/// stays within a single line and can't disturb the folds.
+fn touched_lines(content: &Content, edit: &Edit) -> Option<(usize, usize)> {
+    let (from, to) = selection(content);
+
+    match edit {
+        _ if from.line != to.line => Some((from.line, to.line)),
+        Edit::Enter => Some((from.line, from.line)),
+        Edit::Paste(text) if text.contains('\n') => Some((from.line, from.line)),
+        Edit::Backspace if from == to && from.column == 0 && from.line > 0 => Some((from.line - 1, from.line)),
+        Edit::Delete if from == to && from.column == buffer::line_length(content, from.line) => Some((from.line, from.line + 1)),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    /// Ten lines of document, 2 to 5 folded under 1 with 3 and 4 folded
+    /// again under 2, and 8 folded under 7.
+    fn folds() -> Folds {
+        Folds {
+            folds: vec![
+                Fold {
+                    line: 1,
+                    hidden: lines(&["2", "5"]),
+                    nested: vec![Fold { line: 0, hidden: lines(&["3", "4"]), nested: Vec::new() }],
+                },
+                Fold { line: 3, hidden: lines(&["8"]), nested: Vec::new() },
+            ]
+        }
+    }
+
+    #[test]
+    fn buffer_lines_skip_the_folded_ones() {
+        let folds = folds();
+
+        let document: Vec<usize> = (0..5).map(|line| folds.document_line(line)).collect();
+
+        assert_eq!(document, [0, 1, 6, 7, 9]);
+    }
+
+    fn visible(count: usize) -> Vec<VisibleLine> {
+        (0..count).map(|line| VisibleLine { line, row: line as isize, rows: 1 }).collect()
+    }
+
+    #[test]
+    fn the_gutter_numbers_lines_of_the_document() {
+        assert_eq!(folds().numbers(&visible(5)), [0, 1, 6, 7, 9]);
+        assert!(Folds::default().numbers(&visible(5)).is_empty());
+    }
+
+    #[test]
+    fn toggles_are_on_folded_and_foldable_lines() {
+        let content = Content::with_text("fn a() {\n    x\n}\nfn b() {\n    y\n}");
+
+        assert_eq!(Folds::default().toggles(&content, &visible(6)), [(0, false), (3, false)]);
+        assert_eq!(folds().toggles(&content, &visible(6)), [(0, false), (1, true), (3, true)]);
+    }
+
+    #[test]
+    fn the_cursor_toggles_its_fold_or_region() {
+        let content = Content::with_text("fn a() {\n    x\n}\nfn b() {\n    y\n}");
+
+        assert_eq!(Folds::default().toggle_target(&content, 4), Some(3));
+        assert_eq!(Folds::default().toggle_target(&content, 0), Some(0));
+        assert_eq!(folds().toggle_target(&content, 1), Some(1));
+        assert_eq!(Folds::default().toggle_target(&content, 2), None);
+    }
+
+    #[test]
+    fn folded_lines_are_hidden_under_the_line_they_hang_off() {
+        let folds = folds();
+
+        let buffer: Vec<(usize, bool)> = (0..10).map(|line| folds.buffer_line(line)).collect();
+
+        assert_eq!(buffer, [
+            (0, false), (1, false), (1, true), (1, true), (1, true),
+            (1, true), (2, false), (3, false), (3, true), (4, false)
+        ]);
+    }
+
+    #[test]
+    fn the_document_has_the_folded_lines_put_back() {
+        let content = Content::with_text("0\n1\n6\n7\n9");
+
+        assert_eq!(folds().document_text(&content), "0\n1\n2\n3\n4\n5\n6\n7\n8\n9");
+    }
+
+    #[test]
+    fn ranges_are_in_document_lines_nested_ones_included() {
+        assert_eq!(folds().ranges(), [
+            FoldRange { start: 1, end: 5 },
+            FoldRange { start: 2, end: 4 },
+            FoldRange { start: 7, end: 8 },
+        ]);
+    }
+
+    #[test]
+    fn folds_below_an_edit_move_with_it() {
+        let mut folds = folds();
+
+        folds.shift(2, 3);
+
+        assert!(folds.is_folded(1));
+        assert!(folds.is_folded(6));
+        assert!(!folds.is_folded(3));
+    }
+
+    #[test]
+    fn regions_are_the_lines_indented_deeper() {
+        let content = Content::with_text("fn main() {\n    if x {\n        y();\n\n    }\n\n}\nlast");
+
+        assert!(is_foldable(&content, 0));
+        assert!(is_foldable(&content, 1));
+        assert!(!is_foldable(&content, 2));
+        assert!(!is_foldable(&content, 3));
+
+        assert_eq!(region_end(&content, 0), Some(4));
+        assert_eq!(region_end(&content, 1), Some(2));
+        assert_eq!(region_end(&content, 7), None);
+
+        assert_eq!(enclosing_region(&content, 2), Some(1));
+        assert_eq!(enclosing_region(&content, 4), Some(0));
+        assert_eq!(enclosing_region(&content, 7), None);
+    }
+
+    #[test]
+    fn edits_within_a_line_leave_the_folds_alone() {
+        let content = Content::with_text("one\ntwo");
+
+        assert_eq!(touched_lines(&content, &Edit::Insert('x')), None);
+        assert_eq!(touched_lines(&content, &Edit::Backspace), None);
+        assert_eq!(touched_lines(&content, &Edit::Enter), Some((0, 0)));
+    }
+}
\ No newline at end of file
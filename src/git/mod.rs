@@ -0,0 +1,607 @@
+use std::{collections::HashMap, io::ErrorKind, path::{Path, PathBuf}, process::Stdio};
+
+use chrono::{Local, TimeZone};
+use tokio::{io::AsyncWriteExt, process};
+
+use crate::{diff::{self, Hunk}, encoding, GFEError};
+
+mod panel;
+
+pub use panel::{Action, SourceControl};
+
+/// What the local git repository knows about the open file.
+#[derive(Debug, Clone)]
+pub struct Tracked {
+    /// The checked out branch, or the commit when there's none.
+    pub branch: String,
+    /// The file as of the last commit, `None` when it hasn't been committed.
+    pub head: Option<String>,
+    /// Who last changed each line of `head`.
+    pub blame: Vec<Blame>,
+}
+
+impl Tracked {
+    /// Where `text` differs from the last commit, nothing before the file's first one.
+    pub fn hunks(&self, text: &str) -> Vec<Hunk> {
+        match &self.head {
+            Some(head) => diff::hunks(&diff::lines(head), &text.split('\n').collect::<Vec<_>>()),
+            None => Vec::new()
+        }
+    }
+
+    /// Who last changed a line of the text `hunks` came from, if it's been committed.
+    pub fn blame(&self, hunks: &[Hunk], line: usize) -> Option<String> {
+        self.head.as_ref()?;
+
+        match diff::old_line(hunks, line) {
+            Some(line) => self.blame.get(line).map(Blame::label),
+            None => Some(String::from("Not committed yet"))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Blame {
+    /// Short hash of the commit.
+    pub commit: String,
+    pub author: String,
+    /// Seconds since the epoch.
+    pub time: i64,
+    pub summary: String,
+}
+
+impl Blame {
+    /// "Author, 2024-05-01 · Summary", shown after the cursor line.
+    pub fn label(&self) -> String {
+        let date = Local.timestamp_opt(self.time, 0)
+            .single()
+            .map(|time| time.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+
+        format!("{}, {} \u{00B7} {}", self.author, date, self.summary)
+    }
+}
+
+/// The files that changed in the repository, in the index and in the working tree.
+#[derive(Debug, Clone)]
+pub struct Changes {
+    /// Top directory of the repository, the files' paths are relative to it.
+    pub root: PathBuf,
+    pub staged: Vec<FileChange>,
+    pub unstaged: Vec<FileChange>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub path: String,
+    /// The letter `git status` shows, '?' for files git doesn't know about yet.
+    pub status: char,
+    /// What changed in the file, untracked and binary files have none.
+    pub patch: Option<Patch>,
+}
+
+/// A file's part of a `git diff`, split into hunks that can be applied on their own.
+#[derive(Debug, Clone, Default)]
+pub struct Patch {
+    /// The lines naming the file before the first hunk.
+    header: String,
+    pub hunks: Vec<PatchHunk>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PatchHunk {
+    /// The "@@ -1,2 +1,3 @@" line.
+    pub header: String,
+    /// The removed, added and "\ No newline at end of file" lines, each with its marker.
+    pub lines: Vec<String>,
+}
+
+impl Patch {
+    /// A patch with only one of the hunks, for `git apply`.
+    pub fn hunk(&self, index: usize) -> Option<String> {
+        let hunk = self.hunks.get(index)?;
+
+        Some(format!("{}{}\n{}\n", self.header, hunk.header, hunk.lines.join("\n")))
+    }
+}
+
+/// Asks git about the file at `path`, `None` when it isn't in a repository or git isn't installed.
+pub async fn track(path: PathBuf) -> Result<Option<Tracked>, GFEError> {
+    let (Some(directory), Some(name)) = (path.parent(), path.file_name().and_then(|name| name.to_str())) else {
+        return Ok(None);
+    };
+
+    if git(directory, &["rev-parse", "--is-inside-work-tree"]).await?.is_none() {
+        return Ok(None);
+    }
+
+    // A branch without commits yet still has a name, a detached HEAD only has its commit.
+    let branch = match git(directory, &["symbolic-ref", "--short", "HEAD"]).await? {
+        Some(branch) => branch,
+        None => git(directory, &["rev-parse", "--short", "HEAD"]).await?.unwrap_or_default()
+    };
+
+    let head = git(directory, &["show", &format!("HEAD:./{name}")]).await?
+        .map(|text| encoding::normalize_line_endings(&text));
+
+    let blame = match head {
+        Some(_) => git(directory, &["blame", "--porcelain", "HEAD", "--", name]).await?
+            .map(|output| parse_blame(&output))
+            .unwrap_or_default(),
+        None => Vec::new()
+    };
+
+    Ok(Some(Tracked { branch: branch.trim().to_string(), head, blame }))
+}
+
+/// Lists what changed in the repository `directory` is in, `None` when it isn't in one.
+pub async fn changes(directory: PathBuf) -> Result<Option<Changes>, GFEError> {
+    let Some(root) = git(&directory, &["rev-parse", "--show-toplevel"]).await? else {
+        return Ok(None);
+    };
+
+    let root = PathBuf::from(root.trim());
+
+    let status = run(&root, &["status", "--porcelain=v1", "-z", "--untracked-files=all"], None).await?;
+
+    // No context lines, so every change is a hunk of its own.
+    let diff = ["-c", "core.quotePath=false", "diff", "-U0", "--no-color", "--no-ext-diff"];
+    let mut unstaged_patches = parse_diff(&run(&root, &diff, None).await?);
+    let mut staged_patches = parse_diff(&run(&root, &[&diff[..], &["--cached"]].concat(), None).await?);
+
+    let (mut staged, mut unstaged) = (Vec::new(), Vec::new());
+    let mut entries = status.split('\0');
+
+    while let Some(entry) = entries.next() {
+        let mut letters = entry.chars();
+
+        let (Some(index), Some(worktree), Some(path)) = (letters.next(), letters.next(), entry.get(3..)) else {
+            continue;
+        };
+
+        // Renames and copies are followed by where the file came from.
+        if matches!(index, 'R' | 'C') {
+            entries.next();
+        }
+
+        if !matches!(index, ' ' | '?') {
+            staged.push(FileChange { path: path.to_string(), status: index, patch: staged_patches.remove(path) });
+        }
+
+        if worktree != ' ' {
+            unstaged.push(FileChange { path: path.to_string(), status: worktree, patch: unstaged_patches.remove(path) });
+        }
+    }
+
+    Ok(Some(Changes { root, staged, unstaged }))
+}
+
+/// Adds the whole file to the index.
+pub async fn stage(root: PathBuf, path: String) -> Result<(), GFEError> {
+    run(&root, &["add", "--", &path], None).await.map(|_| ())
+}
+
+/// Puts the file in the index back the way the last commit has it, before
+/// the first commit there's nothing to go back to so it leaves the index.
+pub async fn unstage(root: PathBuf, path: String) -> Result<(), GFEError> {
+    let args = match git(&root, &["rev-parse", "--verify", "-q", "HEAD"]).await? {
+        Some(_) => vec!["reset", "-q", "--", &path],
+        None => vec!["rm", "--cached", "-q", "--", &path]
+    };
+
+    run(&root, &args, None).await.map(|_| ())
+}
+
+/// Applies a patch from [`Patch::hunk`] to the index, or takes it out again when `reverse` is set.
+pub async fn apply(root: PathBuf, patch: String, reverse: bool) -> Result<(), GFEError> {
+    let mut args = vec!["apply", "--cached", "--unidiff-zero"];
+
+    if reverse {
+        args.push("--reverse");
+    }
+
+    args.push("-");
+
+    run(&root, &args, Some(&patch)).await.map(|_| ())
+}
+
+/// Commits what's in the index.
+pub async fn commit(root: PathBuf, message: String) -> Result<(), GFEError> {
+    run(&root, &["commit", "-F", "-"], Some(&message)).await.map(|_| ())
+}
+
+/// Runs git in `directory`, failing with what it printed when it does.
+async fn run(directory: &Path, args: &[&str], input: Option<&str>) -> Result<String, GFEError> {
+    let mut child = process::Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| GFEError::Git(format!("Couldn't start git: {error}")))?;
+
+    // Dropping stdin closes it, git would wait for more otherwise. When git
+    // stops before reading it all, what it printed says more than the broken pipe.
+    let written = match (child.stdin.take(), input) {
+        (Some(mut stdin), Some(input)) => stdin.write_all(input.as_bytes()).await,
+        _ => Ok(())
+    };
+
+    let output = child.wait_with_output()
+        .await
+        .map_err(|error| GFEError::Git(error.to_string()))?;
+
+    if !output.status.success() {
+        // Some failures, like there being nothing to commit, only end up on stdout.
+        let message = match output.stderr.is_empty() {
+            true => String::from_utf8_lossy(&output.stdout).into_owned(),
+            false => String::from_utf8_lossy(&output.stderr).into_owned()
+        };
+
+        // The last line has what went wrong, the ones before explain it at length.
+        let last = message.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or_default();
+
+        return Err(GFEError::Git(last.to_string()));
+    }
+
+    written.map_err(|error| GFEError::Git(error.to_string()))?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Splits `git diff` output into the patch of each file, by path.
+fn parse_diff(output: &str) -> HashMap<String, Patch> {
+    let mut patches = HashMap::new();
+    let mut current: Option<(String, Patch)> = None;
+
+    let Some(output) = output.strip_suffix('\n') else {
+        return patches;
+    };
+
+    for line in output.split('\n') {
+        if line.starts_with("diff --git ") {
+            patches.extend(current.take());
+            current = Some((String::new(), Patch { header: format!("{line}\n"), hunks: Vec::new() }));
+            continue;
+        }
+
+        let Some((path, patch)) = current.as_mut() else {
+            continue;
+        };
+
+        if line.starts_with("@@") {
+            patch.hunks.push(PatchHunk { header: line.to_string(), lines: Vec::new() });
+            continue;
+        }
+
+        match patch.hunks.last_mut() {
+            Some(hunk) => hunk.lines.push(line.to_string()),
+            None => {
+                // Deleted files only have the old name, new ones only the new one.
+                if let Some(name) = line.strip_prefix("+++ b/") {
+                    *path = name.to_string();
+                } else if let (Some(name), true) = (line.strip_prefix("--- a/"), path.is_empty()) {
+                    *path = name.to_string();
+                }
+
+                patch.header.push_str(line);
+                patch.header.push('\n');
+            }
+        }
+    }
+
+    patches.extend(current);
+    patches
+}
+
+/// Runs git in `directory`, `None` when it fails or isn't installed.
+async fn git(directory: &Path, args: &[&str]) -> Result<Option<String>, GFEError> {
+    let output = process::Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(args)
+        .output()
+        .await;
+
+    match output {
+        Ok(output) if output.status.success() => Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned())),
+        Ok(_) => Ok(None),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(GFEError::IO(error.kind()))
+    }
+}
+
+/// Reads `git blame --porcelain`, which describes a commit only the first time one of its lines comes up.
+fn parse_blame(output: &str) -> Vec<Blame> {
+    let mut commits: HashMap<&str, Blame> = HashMap::new();
+    let mut lines = Vec::new();
+    let mut current: Option<(&str, usize)> = None;
+
+    for line in output.lines() {
+        // The line's text ends what's said about it.
+        if line.starts_with('\t') {
+            if let Some((hash, number)) = current.take() {
+                if lines.len() < number {
+                    lines.resize(number, Blame::default());
+                }
+
+                lines[number - 1] = commits.get(hash).cloned().unwrap_or_default();
+            }
+
+            continue;
+        }
+
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+
+        let Some((hash, _)) = current else {
+            // "<hash> <line in the commit> <line in the file> [<lines in the group>]"
+            let number = value.split(' ').nth(1).and_then(|number| number.parse().ok()).unwrap_or(0);
+
+            if number > 0 {
+                current = Some((key, number));
+
+                commits.entry(key).or_insert_with(|| Blame {
+                    commit: key.chars().take(8).collect(),
+                    ..Blame::default()
+                });
+            }
+
+            continue;
+        };
+
+        if let Some(blame) = commits.get_mut(hash) {
+            match key {
+                "author" => blame.author = value.to_string(),
+                "author-time" => blame.time = value.parse().unwrap_or(0),
+                "summary" => blame.summary = value.to_string(),
+                _ => {}
+            }
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLAME: &str = "\
+1234567890abcdef1234567890abcdef12345678 1 1 2
+author Ada
+author-mail <ada@example.com>
+author-time 1714521600
+author-tz +0000
+summary Write the first lines
+filename notes.txt
+\tfirst
+1234567890abcdef1234567890abcdef12345678 2 2
+\tsecond
+fedcba0987654321fedcba0987654321fedcba09 3 3 1
+author Grace
+author-time 1714608000
+summary Add a third line
+previous 1234567890abcdef1234567890abcdef12345678 notes.txt
+filename notes.txt
+\tthird
+";
+
+    #[test]
+    fn reads_who_changed_each_line() {
+        let blame = parse_blame(BLAME);
+
+        assert_eq!(blame.len(), 3);
+        assert_eq!(blame[0], Blame {
+            commit: String::from("12345678"),
+            author: String::from("Ada"),
+            time: 1714521600,
+            summary: String::from("Write the first lines"),
+        });
+        assert_eq!(blame[2].author, "Grace");
+        assert_eq!(blame[2].summary, "Add a third line");
+    }
+
+    #[test]
+    fn commits_are_described_once() {
+        let blame = parse_blame(BLAME);
+
+        assert_eq!(blame[1], blame[0]);
+    }
+
+    #[test]
+    fn lines_can_come_out_of_order() {
+        let output = "\
+aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa 2 2 1
+author Ada
+author-time 0
+summary Later
+\tsecond
+bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb 1 1 1
+author Grace
+author-time 0
+summary Earlier
+\tfirst
+";
+        let blame = parse_blame(output);
+
+        assert_eq!(blame.iter().map(|blame| blame.author.as_str()).collect::<Vec<_>>(), vec!["Grace", "Ada"]);
+    }
+
+    fn tracked(head: Option<&str>) -> Tracked {
+        Tracked {
+            branch: String::from("main"),
+            head: head.map(String::from),
+            blame: parse_blame(BLAME),
+        }
+    }
+
+    #[test]
+    fn hunks_are_against_the_last_commit() {
+        let tracked = tracked(Some("first\nsecond\nthird\n"));
+
+        assert!(tracked.hunks("first\nsecond\nthird").is_empty());
+        assert_eq!(tracked.hunks("first\nchanged\nthird"), vec![Hunk { old: 1..2, new: 1..2 }]);
+        assert!(self::tracked(None).hunks("anything").is_empty());
+    }
+
+    #[test]
+    fn blames_lines_where_they_were_committed() {
+        let tracked = tracked(Some("first\nsecond\nthird\n"));
+        let hunks = tracked.hunks("new\nfirst\nsecond\nthird");
+
+        assert_eq!(tracked.blame(&hunks, 0).as_deref(), Some("Not committed yet"));
+        assert!(tracked.blame(&hunks, 3).unwrap().starts_with("Grace, "));
+        assert_eq!(self::tracked(None).blame(&[], 0), None);
+    }
+
+    #[test]
+    fn labels_have_the_author_date_and_summary() {
+        let blame = Blame { commit: String::from("12345678"), author: String::from("Ada"), time: 0, summary: String::from("Start") };
+        let label = blame.label();
+
+        assert!(label.starts_with("Ada, "));
+        assert!(label.ends_with(" \u{00B7} Start"));
+    }
+
+    const DIFF: &str = "\
+diff --git a/src/main.rs b/src/main.rs
+index 1111111..2222222 100644
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -2 +2 @@ fn main() {
+-    old();
++    new();
+@@ -10,0 +11,2 @@ fn other() {
++    one();
++    two();
+diff --git a/gone.txt b/gone.txt
+deleted file mode 100644
+index 3333333..0000000
+--- a/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+\\ No newline at end of file
+";
+
+    #[test]
+    fn splits_a_diff_by_file_and_hunk() {
+        let patches = parse_diff(DIFF);
+
+        assert_eq!(patches.len(), 2);
+
+        let main = &patches["src/main.rs"];
+
+        assert_eq!(main.hunks.len(), 2);
+        assert_eq!(main.hunks[0].header, "@@ -2 +2 @@ fn main() {");
+        assert_eq!(main.hunks[0].lines, vec!["-    old();", "+    new();"]);
+        assert_eq!(main.hunks[1].lines, vec!["+    one();", "+    two();"]);
+    }
+
+    #[test]
+    fn deleted_files_go_by_their_old_name() {
+        let patches = parse_diff(DIFF);
+        let gone = &patches["gone.txt"];
+
+        assert_eq!(gone.hunks[0].lines, vec!["-bye", "\\ No newline at end of file"]);
+    }
+
+    #[test]
+    fn an_empty_diff_has_no_patches() {
+        assert!(parse_diff("").is_empty());
+    }
+
+    #[test]
+    fn a_hunk_is_a_patch_of_its_own() {
+        let patches = parse_diff(DIFF);
+
+        assert_eq!(patches["src/main.rs"].hunk(1).unwrap(), "\
+diff --git a/src/main.rs b/src/main.rs
+index 1111111..2222222 100644
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -10,0 +11,2 @@ fn other() {
++    one();
++    two();
+");
+        assert_eq!(patches["src/main.rs"].hunk(2), None);
+    }
+
+    /// A repository of its own in the temporary directory, `None` without git.
+    async fn repository(name: &str) -> Option<PathBuf> {
+        let root = std::env::temp_dir().join(format!("gfe-git-{name}-{}", std::process::id()));
+
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).ok()?;
+
+        git(&root, &["init", "-q"]).await.ok()??;
+        git(&root, &["config", "user.name", "Test"]).await.ok()??;
+        git(&root, &["config", "user.email", "test@example.com"]).await.ok()??;
+
+        Some(root)
+    }
+
+    async fn staged(root: &Path) -> String {
+        run(root, &["diff", "--cached", "--name-only"], None).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn unstages_a_file_before_the_first_commit() {
+        let Some(root) = repository("unborn").await else {
+            return;
+        };
+
+        std::fs::write(root.join("new.txt"), "new\n").unwrap();
+        stage(root.clone(), String::from("new.txt")).await.unwrap();
+
+        assert_eq!(staged(&root).await, "new.txt\n");
+
+        unstage(root.clone(), String::from("new.txt")).await.unwrap();
+
+        assert_eq!(staged(&root).await, "");
+        assert!(root.join("new.txt").exists());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn unstages_a_file_back_to_the_last_commit() {
+        let Some(root) = repository("committed").await else {
+            return;
+        };
+
+        std::fs::write(root.join("file.txt"), "one\n").unwrap();
+        stage(root.clone(), String::from("file.txt")).await.unwrap();
+        commit(root.clone(), String::from("First")).await.unwrap();
+
+        std::fs::write(root.join("file.txt"), "two\n").unwrap();
+        stage(root.clone(), String::from("file.txt")).await.unwrap();
+        unstage(root.clone(), String::from("file.txt")).await.unwrap();
+
+        assert_eq!(staged(&root).await, "");
+        assert_eq!(std::fs::read_to_string(root.join("file.txt")).unwrap(), "two\n");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+    #[tokio::test]
+    async fn lists_the_changes_of_the_repository_a_directory_is_in() {
+        let Some(root) = repository("workspace").await else {
+            return;
+        };
+
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(root.join("notes.txt"), "notes\n").unwrap();
+        stage(root.clone(), String::from("notes.txt")).await.unwrap();
+
+        let changes = changes(root.join("src")).await.unwrap().unwrap();
+        let paths = |files: &[FileChange]| files.iter().map(|file| file.path.clone()).collect::<Vec<_>>();
+
+        assert_eq!(paths(&changes.staged), ["notes.txt"]);
+        assert_eq!(paths(&changes.unstaged), ["src/main.rs"]);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,276 @@
+use std::collections::HashSet;
+use std::env;
+use std::path::Path;
+
+use iced::{theme, widget, Alignment, Color, Command, Element, Font, Length};
+
+use super::{Changes, FileChange};
+use crate::{diff, GFEError};
+
+/// Something done in the source control panel.
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Shows or hides the hunks of a staged, or unstaged, file.
+    ToggleExpanded(bool, String),
+    Stage(String),
+    Unstage(String),
+    StageHunk(String, usize),
+    UnstageHunk(String, usize),
+    MessageChanged(String),
+    Commit,
+}
+
+/// The changed files of the repository while the panel is shown, and the
+/// commit being written.
+#[derive(Default)]
+pub struct SourceControl {
+    shown: bool,
+    changes: Option<Changes>,
+    /// Files whose hunks are shown, by whether they're staged and their path.
+    expanded: HashSet<(bool, String)>,
+    message: String,
+}
+
+impl SourceControl {
+    pub fn is_shown(&self) -> bool {
+        self.shown
+    }
+
+    /// Shows or hides the panel, the commit message takes the keyboard when it's shown.
+    pub fn toggle<Message: 'static>(&mut self) -> Command<Message> {
+        self.shown = !self.shown;
+
+        match self.shown {
+            true => widget::text_input::focus(message_input()),
+            false => Command::none()
+        }
+    }
+
+    pub fn hide(&mut self) {
+        self.shown = false;
+    }
+
+    pub fn loaded(&mut self, changes: Option<Changes>) {
+        self.changes = changes;
+    }
+
+    /// Lists the changed files of the workspace again while they're shown: the
+    /// repository the file at `path` is in, or the one of the current directory
+    /// with no file open.
+    pub fn refresh<Message: 'static>(
+        &self,
+        path: Option<&Path>,
+        on_loaded: impl FnOnce(Result<Option<Changes>, GFEError>) -> Message + Send + 'static,
+    ) -> Command<Message> {
+        if !self.shown {
+            return Command::none();
+        }
+
+        let directory = match path.and_then(Path::parent) {
+            Some(directory) => directory.to_path_buf(),
+            None => env::current_dir().unwrap_or_default()
+        };
+
+        Command::perform(super::changes(directory), on_loaded)
+    }
+
+    /// Runs what was done in the panel, git's answer comes back through `on_done`.
+    pub fn update<Message: 'static>(
+        &mut self,
+        action: Action,
+        on_done: impl FnOnce(Result<(), GFEError>) -> Message + Send + 'static,
+    ) -> Result<Command<Message>, GFEError> {
+        let root = match (&action, &self.changes) {
+            (Action::ToggleExpanded(staged, path), _) => {
+                let key = (*staged, path.clone());
+
+                if !self.expanded.remove(&key) {
+                    self.expanded.insert(key);
+                }
+
+                return Ok(Command::none());
+            },
+            (Action::MessageChanged(message), _) => {
+                self.message = message.clone();
+                return Ok(Command::none());
+            },
+            (_, None) => return Ok(Command::none()),
+            (_, Some(changes)) => changes.root.clone()
+        };
+
+        let command = match action {
+            Action::Stage(path) => Command::perform(super::stage(root, path), on_done),
+            Action::Unstage(path) => Command::perform(super::unstage(root, path), on_done),
+            Action::StageHunk(path, index) => self.apply_hunk(false, &path, index, on_done),
+            Action::UnstageHunk(path, index) => self.apply_hunk(true, &path, index, on_done),
+            Action::Commit if self.message.trim().is_empty() => {
+                return Err(GFEError::Git(String::from("The commit message is empty")));
+            },
+            Action::Commit => Command::perform(super::commit(root, self.message.clone()), on_done),
+            Action::ToggleExpanded(..) | Action::MessageChanged(_) => Command::none()
+        };
+
+        Ok(command)
+    }
+
+    /// Starts the next commit message from scratch once one went through.
+    pub fn committed(&mut self) {
+        self.message.clear();
+    }
+
+    /// Stages one hunk of a file, or takes it out of the index when `staged` is set.
+    fn apply_hunk<Message: 'static>(
+        &self,
+        staged: bool,
+        path: &str,
+        index: usize,
+        on_done: impl FnOnce(Result<(), GFEError>) -> Message + Send + 'static,
+    ) -> Command<Message> {
+        let Some(changes) = &self.changes else {
+            return Command::none();
+        };
+
+        let files = match staged {
+            true => &changes.staged,
+            false => &changes.unstaged
+        };
+
+        let patch = files.iter()
+            .find(|file| file.path == path)
+            .and_then(|file| file.patch.as_ref())
+            .and_then(|patch| patch.hunk(index));
+
+        match patch {
+            Some(patch) => Command::perform(super::apply(changes.root.clone(), patch, staged), on_done),
+            None => Command::none()
+        }
+    }
+
+    /// The changed files of the repository, staged ones first, with a box to commit them.
+    pub fn view<'a, Message: Clone + 'a>(
+        &'a self,
+        branch: &'a str,
+        on_action: impl Fn(Action) -> Message + Clone + 'a,
+    ) -> Element<'a, Message> {
+        let Some(changes) = &self.changes else {
+            return widget::container(widget::text("Not in a git repository").size(12))
+                .padding(10)
+                .into();
+        };
+
+        let on_input = on_action.clone();
+
+        let commit = widget::row![
+            widget::text_input("Commit message", &self.message)
+                .id(message_input())
+                .on_input(move |message| on_input(Action::MessageChanged(message)))
+                .on_submit(on_action(Action::Commit)),
+            widget::button(widget::text("Commit")).padding([5, 10]).on_press(on_action(Action::Commit))
+        ].spacing(10);
+
+        let list = widget::column![
+            widget::text(format!("Staged Changes ({})", changes.staged.len())).size(12),
+            self.change_list(&changes.staged, true, &on_action),
+            widget::text(format!("Changes ({})", changes.unstaged.len())).size(12),
+            self.change_list(&changes.unstaged, false, &on_action),
+        ].spacing(10);
+
+        widget::container(
+            widget::column![
+                widget::row![
+                    widget::text("Source Control"),
+                    widget::horizontal_space(),
+                    widget::text(branch).size(12).style(theme::Text::Color(Color { a: 0.6, ..Color::WHITE }))
+                ].align_items(Alignment::Center),
+                commit,
+                widget::scrollable(list),
+            ].spacing(10)
+        )
+        .width(Length::Fixed(700.0))
+        .max_height(600.0)
+        .padding(10)
+        .into()
+    }
+
+    /// Staged or unstaged files with a button to move them to the other side, expanded ones with their hunks.
+    fn change_list<'a, Message: Clone + 'a>(
+        &self,
+        files: &'a [FileChange],
+        staged: bool,
+        on_action: &impl Fn(Action) -> Message,
+    ) -> widget::Column<'a, Message> {
+        let label = match staged {
+            true => "Unstage",
+            false => "Stage"
+        };
+
+        let items = files.iter().map(|file| {
+            let expanded = self.expanded.contains(&(staged, file.path.clone()));
+            let hunks = file.patch.as_ref().map(|patch| patch.hunks.as_slice()).unwrap_or_default();
+
+            let toggle: Element<'_, Message> = match hunks.is_empty() {
+                true => widget::horizontal_space().width(Length::Fixed(20.0)).into(),
+                false => widget::button(widget::text(match expanded {
+                        true => "\u{25BE}",
+                        false => "\u{25B8}"
+                    }))
+                    .style(theme::Button::Text)
+                    .padding(0)
+                    .width(Length::Fixed(20.0))
+                    .on_press(on_action(Action::ToggleExpanded(staged, file.path.clone())))
+                    .into()
+            };
+
+            let (letter, color) = match file.status {
+                'M' => ('M', Color::from_rgb(0.4, 0.6, 1.0)),
+                'A' => ('A', Color::from_rgb(0.4, 0.8, 0.4)),
+                '?' => ('U', Color::from_rgb(0.4, 0.8, 0.4)),
+                'D' => ('D', Color::from_rgb(1.0, 0.4, 0.4)),
+                status => (status, Color { a: 0.7, ..Color::WHITE })
+            };
+
+            let row = widget::row![
+                toggle,
+                widget::text(letter).font(Font::MONOSPACE).style(theme::Text::Color(color)),
+                widget::text(&file.path),
+                widget::horizontal_space(),
+                widget::button(widget::text(label).size(12)).padding([2, 8]).on_press(on_action(match staged {
+                    true => Action::Unstage(file.path.clone()),
+                    false => Action::Stage(file.path.clone())
+                }))
+            ].spacing(10).align_items(Alignment::Center);
+
+            if !expanded {
+                return row.into();
+            }
+
+            let hunks = hunks.iter().enumerate().map(|(index, hunk)| {
+                let lines = hunk.lines.iter().map(|line| {
+                    let mut characters = line.chars();
+                    diff::line_text(characters.next().unwrap_or(' '), characters.as_str()).into()
+                });
+
+                widget::column![
+                    widget::row![
+                        widget::text(&hunk.header).size(12).style(theme::Text::Color(Color { a: 0.5, ..Color::WHITE })),
+                        widget::horizontal_space(),
+                        widget::button(widget::text(label).size(12)).padding([2, 8]).on_press(on_action(match staged {
+                            true => Action::UnstageHunk(file.path.clone(), index),
+                            false => Action::StageHunk(file.path.clone(), index)
+                        }))
+                    ].align_items(Alignment::Center),
+                    widget::column(lines),
+                ].spacing(5).into()
+            });
+
+            widget::column![row, widget::column(hunks).spacing(10).padding([0, 0, 0, 30])].spacing(5).into()
+        });
+
+        widget::column(items).spacing(5)
+    }
+}
+
+/// Id of the commit message, so it can take the keyboard when the panel opens.
+fn message_input() -> widget::text_input::Id {
+    widget::text_input::Id::new("commit")
+}
\ No newline at end of file
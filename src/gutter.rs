@@ -0,0 +1,323 @@
+use iced::alignment::{Horizontal, Vertical};
+use iced::mouse;
+use iced::{Border, Color, Element, Font, Length, Pixels, Point, Rectangle, Size};
+use iced_wgpu::core::text::{self, LineHeight, Shaping};
+use iced_wgpu::core::{event, layout, renderer, widget, Clipboard, Event, Layout, Shell, Widget};
+
+use crate::diff::Change;
+use crate::viewport::{VisibleLine, EDITOR_PADDING, LINE_HEIGHT, TEXT_SIZE, CHAR_WIDTH};
+
+const GUTTER_PADDING: f32 = 8.0;
+/// Room for the fold toggles between the numbers and the text.
+const FOLD_WIDTH: f32 = CHAR_WIDTH * 1.5;
+
+/// Line numbers drawn next to the `text_editor`, kept in sync with the [`Viewport`](crate::viewport::Viewport).
+pub struct Gutter<'a, Message> {
+    lines: Vec<VisibleLine>,
+    cursor_line: usize,
+    relative: bool,
+    width: f32,
+    /// Lines that get a coloured dot, e.g. because they have diagnostics.
+    markers: Vec<(usize, Color)>,
+    /// Lines changed since the last commit.
+    changes: Vec<(usize, Change)>,
+    /// Numbers to show for `lines` when folds hide lines of the document between them.
+    numbers: Vec<usize>,
+    /// Lines that can be folded, and whether they are.
+    folds: Vec<(usize, bool)>,
+    on_fold: Option<Box<dyn Fn(usize) -> Message + 'a>>,
+}
+
+impl<'a, Message> Gutter<'a, Message> {
+    pub fn new(lines: Vec<VisibleLine>, cursor_line: usize, line_count: usize) -> Self {
+        Self {
+            lines,
+            cursor_line,
+            relative: false,
+            width: Self::width(line_count),
+            markers: Vec::new(),
+            changes: Vec::new(),
+            numbers: Vec::new(),
+            folds: Vec::new(),
+            on_fold: None,
+        }
+    }
+
+    /// Width the gutter needs to fit the numbers of a document with `line_count` lines.
+    pub fn width(line_count: usize) -> f32 {
+        let digits = line_count.max(1).to_string().len().max(2);
+
+        digits as f32 * CHAR_WIDTH + GUTTER_PADDING * 2.0 + FOLD_WIDTH
+    }
+
+    /// Shows line numbers relative to the cursor line.
+    pub fn relative(self, relative: bool) -> Self {
+        Self {
+            relative,
+            ..self
+        }
+    }
+
+    /// Marks lines with a coloured dot in front of their number.
+    pub fn markers(self, markers: Vec<(usize, Color)>) -> Self {
+        Self {
+            markers,
+            ..self
+        }
+    }
+
+    /// Marks added and modified lines with a bar next to the text, and where lines were deleted.
+    pub fn changes(self, changes: Vec<(usize, Change)>) -> Self {
+        Self {
+            changes,
+            ..self
+        }
+    }
+
+    /// Shows `numbers` instead of the lines' own, one for each visible line.
+    pub fn numbers(self, numbers: Vec<usize>) -> Self {
+        Self {
+            numbers,
+            ..self
+        }
+    }
+
+    /// Shows a toggle next to the lines that fold, clicking it sends `on_fold` with the line.
+    pub fn folds(self, folds: Vec<(usize, bool)>, on_fold: impl Fn(usize) -> Message + 'a) -> Self {
+        Self {
+            folds,
+            on_fold: Some(Box::new(on_fold)),
+            ..self
+        }
+    }
+
+    /// The area of the fold toggle of `visible`, if the gutter shows one.
+    fn fold_toggle(&self, bounds: Rectangle, visible: &VisibleLine) -> Option<Rectangle> {
+        if visible.row < 0 || !self.folds.iter().any(|(line, _)| *line == visible.line) {
+            return None;
+        }
+
+        Some(Rectangle::new(
+            Point::new(bounds.x + bounds.width - FOLD_WIDTH, bounds.y + EDITOR_PADDING + visible.row as f32 * LINE_HEIGHT),
+            Size::new(FOLD_WIDTH, LINE_HEIGHT)
+        ))
+    }
+}
+
+pub fn gutter<'a, Message>(lines: Vec<VisibleLine>, cursor_line: usize, line_count: usize) -> Gutter<'a, Message> {
+    Gutter::new(lines, cursor_line, line_count)
+}
+
+impl<'a, Message, Theme, Renderer> Widget<Message, Theme, Renderer> for Gutter<'a, Message>
+where
+    Renderer: text::Renderer<Font = Font>,
+{
+    fn size(&self) -> Size<Length> {
+        Size {
+            width: Length::Fixed(self.width),
+            height: Length::Fill,
+        }
+    }
+
+    fn layout(
+        &self,
+        _tree: &mut widget::Tree,
+        _renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        layout::Node::new(limits.width(self.width).height(Length::Fill).max())
+    }
+
+    fn on_event(
+        &mut self,
+        _state: &mut widget::Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        _viewport: &Rectangle,
+    ) -> event::Status {
+        let (Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)), Some(on_fold)) = (event, &self.on_fold) else {
+            return event::Status::Ignored;
+        };
+
+        let bounds = layout.bounds();
+
+        let clicked = self.lines.iter().find(|visible| {
+            self.fold_toggle(bounds, visible).is_some_and(|toggle| cursor.is_over(toggle))
+        });
+
+        match clicked {
+            Some(visible) => {
+                shell.publish(on_fold(visible.line));
+                event::Status::Captured
+            },
+            None => event::Status::Ignored
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        _state: &widget::Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let bounds = layout.bounds();
+
+        match self.lines.iter().any(|visible| self.fold_toggle(bounds, visible).is_some_and(|toggle| cursor.is_over(toggle))) {
+            true => mouse::Interaction::Pointer,
+            false => mouse::Interaction::default()
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &widget::Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+
+        for (index, visible) in self.lines.iter().enumerate() {
+            if visible.row < 0 {
+                continue;
+            }
+
+            let y = bounds.y + EDITOR_PADDING + visible.row as f32 * LINE_HEIGHT;
+
+            if y + LINE_HEIGHT > bounds.y + bounds.height {
+                break;
+            }
+
+            let is_current = visible.line == self.cursor_line;
+
+            if is_current {
+                renderer.fill_quad(
+                    renderer::Quad {
+                        bounds: Rectangle::new(
+                            Point::new(bounds.x, y),
+                            Size::new(bounds.width, LINE_HEIGHT * visible.rows as f32)
+                        ),
+                        ..renderer::Quad::default()
+                    },
+                    Color { a: 0.05, ..Color::WHITE },
+                );
+            }
+
+            if let Some((_, color)) = self.markers.iter().find(|(line, _)| *line == visible.line) {
+                let diameter = 6.0;
+
+                renderer.fill_quad(
+                    renderer::Quad {
+                        bounds: Rectangle::new(
+                            Point::new(bounds.x + 1.0, y + (LINE_HEIGHT - diameter) / 2.0),
+                            Size::new(diameter, diameter)
+                        ),
+                        border: Border::with_radius(diameter / 2.0),
+                        ..renderer::Quad::default()
+                    },
+                    *color,
+                );
+            }
+
+            if let Some((_, change)) = self.changes.iter().find(|(line, _)| *line == visible.line) {
+                // Deleted lines were above this one, there's nothing left of them to mark but the gap.
+                let (area, color) = match change {
+                    Change::Added => (Size::new(3.0, LINE_HEIGHT * visible.rows as f32), Color::from_rgb(0.4, 0.8, 0.4)),
+                    Change::Modified => (Size::new(3.0, LINE_HEIGHT * visible.rows as f32), Color::from_rgb(0.4, 0.6, 1.0)),
+                    Change::Deleted => (Size::new(8.0, 2.0), Color::from_rgb(1.0, 0.4, 0.4))
+                };
+
+                renderer.fill_quad(
+                    renderer::Quad {
+                        bounds: Rectangle::new(Point::new(bounds.x + bounds.width - area.width, y), area),
+                        ..renderer::Quad::default()
+                    },
+                    color,
+                );
+            }
+
+            let number = if self.relative && !is_current {
+                visible.line.abs_diff(self.cursor_line)
+            } else {
+                self.numbers.get(index).copied().unwrap_or(visible.line) + 1
+            };
+
+            renderer.fill_text(
+                text::Text {
+                    content: &number.to_string(),
+                    bounds: Size::new(bounds.width, LINE_HEIGHT),
+                    size: Pixels(TEXT_SIZE),
+                    line_height: LineHeight::default(),
+                    font: Font::MONOSPACE,
+                    horizontal_alignment: Horizontal::Right,
+                    vertical_alignment: Vertical::Top,
+                    shaping: Shaping::Basic,
+                },
+                Point::new(bounds.x + bounds.width - GUTTER_PADDING - FOLD_WIDTH, y),
+                match is_current {
+                    true => Color::WHITE,
+                    false => Color { a: 0.4, ..Color::WHITE }
+                },
+                bounds,
+            );
+
+            if let Some((toggle, folded)) = self.fold_toggle(bounds, visible).zip(self.folds.iter().find(|(line, _)| *line == visible.line)) {
+                renderer.fill_text(
+                    text::Text {
+                        content: match folded.1 {
+                            true => "\u{25B8}",
+                            false => "\u{25BE}"
+                        },
+                        bounds: toggle.size(),
+                        size: Pixels(TEXT_SIZE),
+                        line_height: LineHeight::default(),
+                        font: Font::MONOSPACE,
+                        horizontal_alignment: Horizontal::Center,
+                        vertical_alignment: Vertical::Top,
+                        shaping: Shaping::Advanced,
+                    },
+                    Point::new(toggle.center_x(), toggle.y),
+                    match folded.1 {
+                        true => Color::WHITE,
+                        false => Color { a: 0.4, ..Color::WHITE }
+                    },
+                    bounds,
+                );
+            }
+        }
+    }
+}
+
+impl<'a, Message, Theme, Renderer> From<Gutter<'a, Message>>
+    for Element<'a, Message, Theme, Renderer>
+where
+    Renderer: text::Renderer<Font = Font>,
+    Message: 'a,
+{
+    fn from(gutter: Gutter<'a, Message>) -> Self {
+        Self::new(gutter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn width_fits_the_longest_number() {
+        let beside = GUTTER_PADDING * 2.0 + FOLD_WIDTH;
+
+        assert_eq!(Gutter::<()>::width(0), 2.0 * CHAR_WIDTH + beside);
+        assert_eq!(Gutter::<()>::width(99), 2.0 * CHAR_WIDTH + beside);
+        assert_eq!(Gutter::<()>::width(12_345), 5.0 * CHAR_WIDTH + beside);
+    }
+}
@@ -0,0 +1,136 @@
+use iced::widget::text_editor::{Content, Edit};
+
+use crate::{buffer::{self, Position}, folding::Folds};
+
+/// How many undo steps are kept around.
+const LIMIT: usize = 200;
+
+/// Kinds of edits that are merged into a single undo step when they follow each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Group {
+    Typing,
+    Deleting,
+}
+
+impl Group {
+    /// The group an [`Edit`] belongs to, `None` if it should be its own undo step.
+    pub fn of(edit: &Edit) -> Option<Self> {
+        match edit {
+            Edit::Insert(_) => Some(Group::Typing),
+            Edit::Backspace | Edit::Delete => Some(Group::Deleting),
+            Edit::Enter | Edit::Paste(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Snapshot {
+    text: String,
+    cursor: Position,
+    /// The folds go with the text, it's missing the lines they hide.
+    folds: Folds,
+}
+
+impl Snapshot {
+    fn of(content: &Content, folds: &Folds) -> Self {
+        Self {
+            text: buffer::text(content),
+            cursor: buffer::cursor(content),
+            folds: folds.clone(),
+        }
+    }
+
+    fn restore(self, content: &mut Content, folds: &mut Folds) {
+        buffer::set_text(content, &self.text);
+        buffer::move_to(content, self.cursor);
+        *folds = self.folds;
+    }
+}
+
+/// Undo and redo stacks of a buffer.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    group: Option<Group>,
+}
+
+impl History {
+    /// Remembers `content` as it is right before an edit.
+    ///
+    /// Edits of the same [`Group`] share the snapshot taken before the first one.
+    pub fn record(&mut self, content: &Content, folds: &Folds, group: Option<Group>) {
+        if group.is_some() && group == self.group {
+            return;
+        }
+
+        self.group = group;
+        self.redo.clear();
+        self.undo.push(Snapshot::of(content, folds));
+
+        if self.undo.len() > LIMIT {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Makes the next edit start a new undo step, e.g. after the cursor moved.
+    pub fn break_group(&mut self) {
+        self.group = None;
+    }
+
+    /// Restores the state before the last edit, returns whether there was one.
+    pub fn undo(&mut self, content: &mut Content, folds: &mut Folds) -> bool {
+        let Some(snapshot) = self.undo.pop() else {
+            return false;
+        };
+
+        self.group = None;
+        self.redo.push(Snapshot::of(content, folds));
+        snapshot.restore(content, folds);
+
+        true
+    }
+
+    /// Reapplies the last undone edit, returns whether there was one.
+    pub fn redo(&mut self, content: &mut Content, folds: &mut Folds) -> bool {
+        let Some(snapshot) = self.redo.pop() else {
+            return false;
+        };
+
+        self.group = None;
+        self.undo.push(Snapshot::of(content, folds));
+        snapshot.restore(content, folds);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn typing_and_deleting_are_grouped() {
+        assert_eq!(Group::of(&Edit::Insert('a')), Some(Group::Typing));
+        assert_eq!(Group::of(&Edit::Delete), Some(Group::Deleting));
+        assert_eq!(Group::of(&Edit::Paste(Arc::new(String::from("a")))), None);
+    }
+
+    #[test]
+    fn grouped_edits_share_one_undo_step() {
+        let content = Content::with_text("a");
+        let folds = Folds::default();
+        let mut history = History::default();
+
+        history.record(&content, &folds, Some(Group::Typing));
+        history.record(&content, &folds, Some(Group::Typing));
+        assert_eq!(history.undo.len(), 1);
+
+        history.record(&content, &folds, Some(Group::Deleting));
+        history.break_group();
+        history.record(&content, &folds, Some(Group::Deleting));
+        assert_eq!(history.undo.len(), 3);
+    }
+}
@@ -0,0 +1,220 @@
+use std::fmt;
+
+use iced::widget::text_editor::Content;
+
+use crate::buffer::{self, Position};
+
+/// Whether a buffer is indented with tabs or spaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentStyle {
+    Tabs,
+    Spaces,
+}
+
+/// Indentation settings of a buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Indentation {
+    pub style: IndentStyle,
+    pub width: usize,
+}
+
+impl Default for Indentation {
+    fn default() -> Self {
+        Self {
+            style: IndentStyle::Spaces,
+            width: 4,
+        }
+    }
+}
+
+impl Indentation {
+    /// The presets offered in the status bar.
+    pub const ALL: &'static [Self] = &[
+        Self { style: IndentStyle::Spaces, width: 2 },
+        Self { style: IndentStyle::Spaces, width: 4 },
+        Self { style: IndentStyle::Spaces, width: 8 },
+        Self { style: IndentStyle::Tabs, width: 2 },
+        Self { style: IndentStyle::Tabs, width: 4 },
+        Self { style: IndentStyle::Tabs, width: 8 },
+    ];
+
+    /// Guesses the indentation of some text, `None` if nothing in it is indented.
+    pub fn detect(text: &str) -> Option<Self> {
+        let mut tabs = 0;
+        let mut spaces = 0;
+        // How often the indentation grew by a given amount of spaces between two lines.
+        let mut steps = [0usize; 9];
+        let mut previous = 0;
+
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            if line.starts_with('\t') {
+                tabs += 1;
+                continue;
+            }
+
+            let width = line.len() - line.trim_start_matches(' ').len();
+
+            if width > 0 {
+                spaces += 1;
+            }
+
+            if width > previous && width - previous < steps.len() {
+                steps[width - previous] += 1;
+            }
+
+            previous = width;
+        }
+
+        if tabs == 0 && spaces == 0 {
+            return None;
+        }
+
+        if tabs > spaces {
+            return Some(Self { style: IndentStyle::Tabs, ..Self::default() });
+        }
+
+        let width = (2..steps.len())
+            .max_by_key(|step| (steps[*step], usize::MAX - step))
+            .filter(|step| steps[*step] > 0)
+            .unwrap_or(4);
+
+        Some(Self { style: IndentStyle::Spaces, width })
+    }
+
+    /// One level of indentation.
+    pub fn unit(&self) -> String {
+        match self.style {
+            IndentStyle::Tabs => String::from("\t"),
+            IndentStyle::Spaces => " ".repeat(self.width),
+        }
+    }
+
+    /// Text to insert at `column` to reach the next indentation level.
+    pub fn unit_at(&self, column: usize) -> String {
+        match self.style {
+            IndentStyle::Tabs => String::from("\t"),
+            IndentStyle::Spaces => " ".repeat(self.width - column % self.width),
+        }
+    }
+
+    /// Adds one level of indentation to the start of `line`.
+    pub fn indent(&self, line: &str) -> String {
+        if line.is_empty() {
+            return String::new();
+        }
+
+        format!("{}{}", self.unit(), line)
+    }
+
+    /// Removes up to one level of indentation from the start of `line`.
+    pub fn outdent<'a>(&self, line: &'a str) -> &'a str {
+        if let Some(rest) = line.strip_prefix('\t') {
+            return rest;
+        }
+
+        let spaces = line.len() - line.trim_start_matches(' ').len();
+
+        &line[spaces.min(self.width)..]
+    }
+}
+
+/// Indents every line touched by the range between `from` and `to` and selects them.
+pub fn indent_lines(content: &mut Content, indentation: &Indentation, from: Position, to: Position) {
+    shift_lines(content, from, to, |line| indentation.indent(line));
+}
+
+/// Outdents every line touched by the range between `from` and `to`.
+///
+/// A selection is replaced by one covering the outdented lines, a lone
+/// cursor stays where it was relative to the text.
+pub fn outdent_lines(content: &mut Content, indentation: &Indentation, from: Position, to: Position) {
+    if from == to {
+        let line = content.line(from.line).map(|line| line.to_string()).unwrap_or_default();
+        let outdented = indentation.outdent(&line);
+        let removed = line.chars().count() - outdented.chars().count();
+
+        if removed > 0 {
+            buffer::replace(content, Position::new(from.line, 0), Position::new(from.line, removed), "");
+            buffer::move_to(content, Position::new(from.line, from.column.saturating_sub(removed)));
+        }
+
+        return;
+    }
+
+    shift_lines(content, from, to, |line| indentation.outdent(line).to_owned());
+}
+
+fn shift_lines(content: &mut Content, from: Position, to: Position, shift: impl Fn(&str) -> String) {
+    // A selection ending at the start of a line doesn't really include that line.
+    let last = if to.line > from.line && to.column == 0 { to.line - 1 } else { to.line };
+
+    let lines: Vec<String> = (from.line..=last)
+        .map(|line| shift(&content.line(line).map(|line| line.to_string()).unwrap_or_default()))
+        .collect();
+
+    let end = Position::new(last, buffer::line_length(content, last));
+
+    buffer::replace(content, Position::new(from.line, 0), end, &lines.join("\n"));
+
+    let end = Position::new(last, lines.last().map(|line| line.chars().count()).unwrap_or(0));
+
+    buffer::select(content, Position::new(from.line, 0), end);
+}
+
+/// The whitespace a line starts with.
+pub fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+impl fmt::Display for Indentation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.style {
+            IndentStyle::Tabs => write!(f, "Tab Size: {}", self.width),
+            IndentStyle::Spaces => write!(f, "Spaces: {}", self.width),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_the_most_common_step() {
+        let two = "fn main() {\n  if x {\n    y();\n  }\n  z();\n}\n";
+        let four = "class A:\n    def f(self):\n        pass\n\n    def g(self):\n        pass\n";
+
+        assert_eq!(Indentation::detect(two), Some(Indentation { style: IndentStyle::Spaces, width: 2 }));
+        assert_eq!(Indentation::detect(four), Some(Indentation { style: IndentStyle::Spaces, width: 4 }));
+    }
+
+    #[test]
+    fn detects_tabs_and_nothing() {
+        let tabs = "int main() {\n\tif (x) {\n\t\treturn 1;\n\t}\n}\n";
+
+        assert_eq!(Indentation::detect(tabs).map(|indentation| indentation.style), Some(IndentStyle::Tabs));
+        assert_eq!(Indentation::detect("a\nb\n\nc"), None);
+    }
+
+    #[test]
+    fn units_reach_the_next_level() {
+        let spaces = Indentation { style: IndentStyle::Spaces, width: 4 };
+
+        assert_eq!(spaces.unit(), "    ");
+        assert_eq!(spaces.unit_at(1), "   ");
+        assert_eq!(spaces.unit_at(4), "    ");
+        assert_eq!(Indentation { style: IndentStyle::Tabs, width: 8 }.unit_at(3), "\t");
+    }
+
+    #[test]
+    fn outdent_removes_at_most_one_level() {
+        let spaces = Indentation { style: IndentStyle::Spaces, width: 4 };
+
+        assert_eq!(spaces.indent("x"), "    x");
+        assert_eq!(spaces.indent(""), "");
+        assert_eq!(spaces.outdent("      x"), "  x");
+        assert_eq!(spaces.outdent("  x"), "x");
+        assert_eq!(spaces.outdent("\t\tx"), "\tx");
+        assert_eq!(leading_whitespace(" \t x "), " \t ");
+    }
+}
@@ -0,0 +1,99 @@
+use std::path::{Path, PathBuf};
+
+use iced::keyboard::{self, Modifiers};
+use iced::widget::text_editor::Action;
+use serde::{Deserialize, Serialize};
+
+use crate::{buffer::Position, emacs, macros::Step, vim};
+
+/// Which keybindings drive the editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Keymap {
+    /// The `text_editor`'s own keys and the control palette.
+    #[default]
+    Default,
+    Vim,
+    Emacs,
+}
+
+impl Keymap {
+    /// The profile that follows this one when cycling through them.
+    pub fn next(self) -> Self {
+        match self {
+            Keymap::Default => Keymap::Vim,
+            Keymap::Vim => Keymap::Emacs,
+            Keymap::Emacs => Keymap::Default,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Keymap::Default => "Default",
+            Keymap::Vim => "Vim",
+            Keymap::Emacs => "Emacs",
+        }
+    }
+
+    /// The step a key press takes through this profile's layer, `None` when
+    /// there's no layer or the key is left to the rest of the editor.
+    pub fn step(self, key: keyboard::Key<&str>, modifiers: Modifiers) -> Option<Step> {
+        match self {
+            Keymap::Default => None,
+            Keymap::Vim => vim::Key::from_keyboard(key, modifiers).map(Step::Vim),
+            Keymap::Emacs => emacs::Key::from_keyboard(key, modifiers).map(Step::Emacs),
+        }
+    }
+}
+
+/// Where [`Effect::Open`] should look for `path`, relative paths are taken
+/// from the folder of the open `file`.
+pub fn open_path(path: &str, file: Option<&Path>) -> PathBuf {
+    match (Path::new(path).is_relative(), file.and_then(Path::parent)) {
+        (true, Some(folder)) => folder.join(path),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// What the editor should do in response to a key of a [`Keymap`].
+#[derive(Debug, Clone)]
+pub enum Effect {
+    /// An action to run as if the `text_editor` had produced it.
+    Perform(Action),
+    MoveTo(Position),
+    /// Selects from `anchor` to `head`, either may come first.
+    Select { anchor: Position, head: Position },
+    /// Indents or outdents the lines `from..=to`.
+    Indent { from: usize, to: usize, outdent: bool },
+    Undo,
+    Save,
+    SaveAndQuit,
+    /// Closes the window, `force` skips the check for unsaved changes.
+    Quit { force: bool },
+    /// Opens the file at the path, or asks for one.
+    Open(Option<String>),
+    ShowPalette,
+    /// Keys to feed through the Vim layer again, which is what `.` does.
+    Replay(Vec<vim::Key>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_vim_and_emacs_take_keys() {
+        let key = keyboard::Key::Character("x");
+
+        assert!(Keymap::Default.step(key.clone(), Modifiers::default()).is_none());
+        assert!(matches!(Keymap::Vim.step(key.clone(), Modifiers::default()), Some(Step::Vim(_))));
+        assert!(matches!(Keymap::Emacs.step(key, Modifiers::CTRL), Some(Step::Emacs(_))));
+    }
+
+    #[test]
+    fn relative_paths_open_next_to_the_file() {
+        assert_eq!(open_path("b.rs", Some(Path::new("/project/src/a.rs"))), PathBuf::from("/project/src/b.rs"));
+        assert_eq!(open_path("/etc/hosts", Some(Path::new("/project/src/a.rs"))), PathBuf::from("/etc/hosts"));
+        assert_eq!(open_path("b.rs", None), PathBuf::from("b.rs"));
+    }
+}
\ No newline at end of file
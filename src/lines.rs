@@ -0,0 +1,517 @@
+use iced::widget::text_editor::Content;
+use serde::{Deserialize, Serialize};
+
+use crate::{buffer::{self, Position}, completion::is_word_character};
+
+/// Commands that rework whole lines, or the selection for case changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineOperation {
+    Duplicate,
+    MoveUp,
+    MoveDown,
+    Delete,
+    Join,
+    SortAscending,
+    SortDescending,
+    SortNumeric,
+    SortUnique,
+    Reverse,
+    Shuffle,
+    Upper,
+    Lower,
+    Title,
+    Snake,
+    Camel,
+}
+
+impl LineOperation {
+    pub const ALL: [LineOperation; 16] = [
+        LineOperation::Duplicate,
+        LineOperation::MoveUp,
+        LineOperation::MoveDown,
+        LineOperation::Delete,
+        LineOperation::Join,
+        LineOperation::SortAscending,
+        LineOperation::SortDescending,
+        LineOperation::SortNumeric,
+        LineOperation::SortUnique,
+        LineOperation::Reverse,
+        LineOperation::Shuffle,
+        LineOperation::Upper,
+        LineOperation::Lower,
+        LineOperation::Title,
+        LineOperation::Snake,
+        LineOperation::Camel,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LineOperation::Duplicate => "Duplicate",
+            LineOperation::MoveUp => "Move Up",
+            LineOperation::MoveDown => "Move Down",
+            LineOperation::Delete => "Delete Line",
+            LineOperation::Join => "Join Lines",
+            LineOperation::SortAscending => "Sort Ascending",
+            LineOperation::SortDescending => "Sort Descending",
+            LineOperation::SortNumeric => "Sort Numeric",
+            LineOperation::SortUnique => "Sort Unique",
+            LineOperation::Reverse => "Reverse",
+            LineOperation::Shuffle => "Shuffle",
+            LineOperation::Upper => "UPPER CASE",
+            LineOperation::Lower => "lower case",
+            LineOperation::Title => "Title Case",
+            LineOperation::Snake => "snake_case",
+            LineOperation::Camel => "camelCase",
+        }
+    }
+}
+
+/// A replacement worked out by [`plan`], and what to select afterwards.
+#[derive(Debug, Clone)]
+pub struct LineEdit {
+    pub from: Position,
+    pub to: Position,
+    pub text: String,
+    /// Start and end of the selection once the text is replaced, the same for just a cursor.
+    pub selection: (Position, Position),
+}
+
+/// Works out what `operation` does at the cursor or selection, `None` if it
+/// wouldn't change anything.
+pub fn plan(content: &Content, operation: LineOperation) -> Option<LineEdit> {
+    plan_at(content, buffer::cursor(content), buffer::selection(content), operation)
+}
+
+/// [`plan`] for the given cursor and selection instead of the `text_editor`'s.
+fn plan_at(content: &Content, cursor: Position, selection: Option<(Position, Position)>, operation: LineOperation) -> Option<LineEdit> {
+    let last_line = content.line_count().saturating_sub(1);
+    let (first, last) = selected_lines(cursor, selection);
+
+    let (start, end) = selection.unwrap_or((cursor, cursor));
+    let shifted = |lines: isize| {
+        let shift = |position: Position| Position::new(position.line.saturating_add_signed(lines), position.column);
+        (shift(start), shift(end))
+    };
+
+    match operation {
+        LineOperation::Duplicate => match selection {
+            Some((from, to)) if from.line == to.line => {
+                let text = buffer::text_between(content, from, to);
+
+                Some(LineEdit {
+                    from: to,
+                    to,
+                    selection: (to, end_of(to, &text)),
+                    text,
+                })
+            },
+            _ => {
+                let end = line_end(content, last);
+
+                Some(LineEdit {
+                    from: end,
+                    to: end,
+                    text: format!("\n{}", lines(content, first, last).join("\n")),
+                    selection: shifted((last - first + 1) as isize),
+                })
+            }
+        },
+        LineOperation::MoveUp if first > 0 => {
+            let mut block = lines(content, first - 1, last);
+            block.rotate_left(1);
+
+            Some(LineEdit {
+                from: Position::new(first - 1, 0),
+                to: line_end(content, last),
+                text: block.join("\n"),
+                selection: shifted(-1),
+            })
+        },
+        LineOperation::MoveDown if last < last_line => {
+            let mut block = lines(content, first, last + 1);
+            block.rotate_right(1);
+
+            Some(LineEdit {
+                from: Position::new(first, 0),
+                to: line_end(content, last + 1),
+                text: block.join("\n"),
+                selection: shifted(1),
+            })
+        },
+        LineOperation::MoveUp | LineOperation::MoveDown => None,
+        LineOperation::Delete => {
+            let (from, to, line) = if last < last_line {
+                (Position::new(first, 0), Position::new(last + 1, 0), first)
+            } else if first > 0 {
+                (line_end(content, first - 1), line_end(content, last), first - 1)
+            } else {
+                (Position::new(first, 0), line_end(content, last), first)
+            };
+
+            if from == to {
+                return None;
+            }
+
+            let position = Position::new(line, 0);
+
+            Some(LineEdit { from, to, text: String::new(), selection: (position, position) })
+        },
+        LineOperation::Join => {
+            let last = match first == last {
+                true => (last + 1).min(last_line),
+                false => last
+            };
+
+            if first == last {
+                return None;
+            }
+
+            let lines = lines(content, first, last);
+            let head = lines[0].trim_end();
+
+            let text = std::iter::once(head)
+                .chain(lines[1..].iter().map(|line| line.trim()).filter(|line| !line.is_empty()))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            let position = Position::new(first, head.chars().count());
+
+            Some(LineEdit {
+                from: Position::new(first, 0),
+                to: line_end(content, last),
+                text,
+                selection: (position, position),
+            })
+        },
+        LineOperation::SortAscending | LineOperation::SortDescending | LineOperation::SortNumeric
+        | LineOperation::SortUnique | LineOperation::Reverse | LineOperation::Shuffle => {
+            // Only the selected lines are reordered, a single one has nothing to reorder.
+            if first == last {
+                return None;
+            }
+
+            let mut block = lines(content, first, last);
+
+            match operation {
+                LineOperation::SortAscending => block.sort(),
+                LineOperation::SortDescending => block.sort_by(|a, b| b.cmp(a)),
+                LineOperation::SortNumeric => block.sort_by(|a, b| {
+                    match (leading_number(a), leading_number(b)) {
+                        (Some(a), Some(b)) => a.total_cmp(&b),
+                        (Some(_), None) => std::cmp::Ordering::Less,
+                        (None, Some(_)) => std::cmp::Ordering::Greater,
+                        (None, None) => a.cmp(b)
+                    }
+                }),
+                LineOperation::SortUnique => {
+                    block.sort();
+                    block.dedup();
+                },
+                LineOperation::Reverse => block.reverse(),
+                _ => fastrand::shuffle(&mut block)
+            }
+
+            let text = block.join("\n");
+            let from = Position::new(first, 0);
+
+            Some(LineEdit {
+                from,
+                to: line_end(content, last),
+                selection: (from, end_of(from, &text)),
+                text,
+            })
+        },
+        LineOperation::Upper | LineOperation::Lower | LineOperation::Title
+        | LineOperation::Snake | LineOperation::Camel => {
+            let (from, to) = selection.unwrap_or_else(|| word_at(content, cursor));
+
+            if from == to {
+                return None;
+            }
+
+            let original = buffer::text_between(content, from, to);
+
+            let text = match operation {
+                LineOperation::Upper => original.to_uppercase(),
+                LineOperation::Lower => original.to_lowercase(),
+                LineOperation::Title => title_case(&original),
+                LineOperation::Snake => map_identifiers(&original, snake_case),
+                _ => map_identifiers(&original, camel_case)
+            };
+
+            Some(LineEdit {
+                from,
+                to,
+                selection: (from, end_of(from, &text)),
+                text,
+            })
+        }
+    }
+}
+
+/// Carries out a [`LineEdit`].
+pub fn apply(content: &mut Content, edit: LineEdit) {
+    buffer::replace(content, edit.from, edit.to, &edit.text);
+
+    match edit.selection {
+        (from, to) if from == to => buffer::move_to(content, from),
+        (from, to) => buffer::select(content, from, to)
+    }
+}
+
+/// The first and last line the selection touches, or the cursor's line.
+pub fn selected_lines(cursor: Position, selection: Option<(Position, Position)>) -> (usize, usize) {
+    // A selection ending at the start of a line doesn't take that line with it.
+    match selection {
+        Some((from, to)) if to.line > from.line && to.column == 0 => (from.line, to.line - 1),
+        Some((from, to)) => (from.line, to.line),
+        None => (cursor.line, cursor.line)
+    }
+}
+
+pub fn lines(content: &Content, first: usize, last: usize) -> Vec<String> {
+    (first..=last)
+        .map(|line| content.line(line).map(|line| line.to_string()).unwrap_or_default())
+        .collect()
+}
+
+pub fn line_end(content: &Content, line: usize) -> Position {
+    Position::new(line, buffer::line_length(content, line))
+}
+
+/// Where `text` ends once it's inserted at `from`.
+pub fn end_of(from: Position, text: &str) -> Position {
+    match text.rsplit_once('\n') {
+        Some((before, after)) => Position::new(from.line + before.matches('\n').count() + 1, after.chars().count()),
+        None => Position::new(from.line, from.column + text.chars().count())
+    }
+}
+
+/// The word around `position`, empty if there's none.
+fn word_at(content: &Content, position: Position) -> (Position, Position) {
+    let line: Vec<char> = content.line(position.line).map(|line| line.chars().collect()).unwrap_or_default();
+
+    let mut start = position.column.min(line.len());
+    let mut end = start;
+
+    while start > 0 && is_word_character(line[start - 1]) {
+        start -= 1;
+    }
+
+    while end < line.len() && is_word_character(line[end]) {
+        end += 1;
+    }
+
+    (Position::new(position.line, start), Position::new(position.line, end))
+}
+
+fn leading_number(line: &str) -> Option<f64> {
+    let line = line.trim_start();
+    let length = line.char_indices()
+        .take_while(|(index, character)| character.is_ascii_digit() || *character == '.' || (*index == 0 && *character == '-'))
+        .count();
+
+    line[..length].parse().ok()
+}
+
+fn title_case(text: &str) -> String {
+    let mut previous_is_letter = false;
+
+    text.chars()
+        .flat_map(|character| {
+            let converted: Vec<char> = match previous_is_letter {
+                true => character.to_lowercase().collect(),
+                false => character.to_uppercase().collect()
+            };
+
+            previous_is_letter = character.is_alphanumeric() || character == '\'';
+            converted
+        })
+        .collect()
+}
+
+/// Rewrites every identifier-like run of `text` with `convert`, leaving what's between them alone.
+fn map_identifiers(text: &str, convert: fn(&[String]) -> String) -> String {
+    let mut result = String::new();
+    let mut identifier = String::new();
+
+    for character in text.chars().chain(std::iter::once('\0')) {
+        if character.is_alphanumeric() || character == '_' || character == '-' {
+            identifier.push(character);
+            continue;
+        }
+
+        if !identifier.is_empty() {
+            result.push_str(&convert(&words(&identifier)));
+            identifier.clear();
+        }
+
+        if character != '\0' {
+            result.push(character);
+        }
+    }
+
+    result
+}
+
+/// Splits an identifier into words at `_`, `-` and changes of case, so
+/// `parseHTTPRequest_now` gives `parse`, `HTTP`, `Request` and `now`.
+fn words(identifier: &str) -> Vec<String> {
+    let mut words = Vec::new();
+
+    for part in identifier.split(['_', '-']).filter(|part| !part.is_empty()) {
+        let characters: Vec<char> = part.chars().collect();
+        let mut word = String::new();
+
+        for (index, &character) in characters.iter().enumerate() {
+            let previous = index.checked_sub(1).map(|index| characters[index]);
+            let next = characters.get(index + 1);
+
+            let boundary = character.is_uppercase() && match previous {
+                Some(previous) if previous.is_lowercase() || previous.is_ascii_digit() => true,
+                Some(previous) if previous.is_uppercase() => next.is_some_and(|next| next.is_lowercase()),
+                _ => false
+            };
+
+            if boundary && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+
+            word.push(character);
+        }
+
+        if !word.is_empty() {
+            words.push(word);
+        }
+    }
+
+    words
+}
+
+fn snake_case(words: &[String]) -> String {
+    words.iter().map(|word| word.to_lowercase()).collect::<Vec<_>>().join("_")
+}
+
+fn camel_case(words: &[String]) -> String {
+    words.iter()
+        .enumerate()
+        .map(|(index, word)| match index {
+            0 => word.to_lowercase(),
+            _ => {
+                let mut characters = word.chars();
+
+                characters.next()
+                    .map(|first| first.to_uppercase().chain(characters.flat_map(char::to_lowercase)).collect())
+                    .unwrap_or_default()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "delta\ncharlie\nbravo\nalpha";
+
+    fn selecting(content: &Content, from: Position, to: Position, operation: LineOperation) -> Option<LineEdit> {
+        plan_at(content, to, Some((from, to)), operation)
+    }
+
+    #[test]
+    fn sorting_only_touches_the_selected_lines() {
+        let content = Content::with_text(TEXT);
+
+        let edit = selecting(&content, Position::new(1, 2), Position::new(2, 1), LineOperation::SortAscending).unwrap();
+
+        assert_eq!((edit.from, edit.to), (Position::new(1, 0), Position::new(2, 5)));
+        assert_eq!(edit.text, "bravo\ncharlie");
+        assert_eq!(edit.selection, (Position::new(1, 0), Position::new(2, 7)));
+    }
+
+    #[test]
+    fn reordering_fewer_than_two_lines_does_nothing() {
+        let content = Content::with_text(TEXT);
+
+        for operation in [LineOperation::SortAscending, LineOperation::SortUnique, LineOperation::Reverse, LineOperation::Shuffle] {
+            assert!(plan_at(&content, Position::new(1, 0), None, operation).is_none());
+            assert!(selecting(&content, Position::new(1, 0), Position::new(1, 4), operation).is_none());
+            // Up to the start of the next line is still just the one line.
+            assert!(selecting(&content, Position::new(1, 0), Position::new(2, 0), operation).is_none());
+        }
+    }
+
+    #[test]
+    fn lines_sort_by_text_number_or_not_at_all() {
+        let content = Content::with_text("10 apples\n-2.5 owed\npears\n10 apples\n3 figs");
+        let (from, to) = (Position::new(0, 0), Position::new(4, 6));
+        let text = |operation| selecting(&content, from, to, operation).unwrap().text;
+
+        assert_eq!(text(LineOperation::SortNumeric), "-2.5 owed\n3 figs\n10 apples\n10 apples\npears");
+        assert_eq!(text(LineOperation::SortUnique), "-2.5 owed\n10 apples\n3 figs\npears");
+        assert_eq!(text(LineOperation::SortDescending), "pears\n3 figs\n10 apples\n10 apples\n-2.5 owed");
+        assert_eq!(text(LineOperation::Reverse), "3 figs\n10 apples\npears\n-2.5 owed\n10 apples");
+
+        let mut shuffled: Vec<_> = text(LineOperation::Shuffle).split('\n').map(str::to_owned).collect();
+        shuffled.sort();
+        assert_eq!(shuffled, ["-2.5 owed", "10 apples", "10 apples", "3 figs", "pears"]);
+    }
+
+    #[test]
+    fn lines_move_and_duplicate_with_the_cursor() {
+        let content = Content::with_text(TEXT);
+        let cursor = Position::new(1, 3);
+
+        let duplicate = plan_at(&content, cursor, None, LineOperation::Duplicate).unwrap();
+        assert_eq!((duplicate.from, duplicate.text.as_str()), (Position::new(1, 7), "\ncharlie"));
+        assert_eq!(duplicate.selection, (Position::new(2, 3), Position::new(2, 3)));
+
+        let up = plan_at(&content, cursor, None, LineOperation::MoveUp).unwrap();
+        assert_eq!((up.from, up.to, up.text.as_str()), (Position::new(0, 0), Position::new(1, 7), "charlie\ndelta"));
+        assert_eq!(up.selection.0, Position::new(0, 3));
+
+        let down = plan_at(&content, cursor, None, LineOperation::MoveDown).unwrap();
+        assert_eq!(down.text, "bravo\ncharlie");
+
+        assert!(plan_at(&content, Position::new(0, 0), None, LineOperation::MoveUp).is_none());
+        assert!(plan_at(&content, Position::new(3, 0), None, LineOperation::MoveDown).is_none());
+    }
+
+    #[test]
+    fn deleting_the_last_line_takes_the_line_break_before_it() {
+        let content = Content::with_text(TEXT);
+
+        let edit = plan_at(&content, Position::new(3, 2), None, LineOperation::Delete).unwrap();
+
+        assert_eq!((edit.from, edit.to), (Position::new(2, 5), Position::new(3, 5)));
+        assert_eq!(edit.selection.0, Position::new(2, 0));
+    }
+
+    #[test]
+    fn joining_trims_the_lines_and_skips_empty_ones() {
+        let content = Content::with_text("let x =  \n    \n    1 +\n  2;");
+
+        let edit = selecting(&content, Position::new(0, 0), Position::new(3, 1), LineOperation::Join).unwrap();
+
+        assert_eq!(edit.text, "let x = 1 + 2;");
+        assert_eq!(edit.selection.0, Position::new(0, 7));
+    }
+
+    #[test]
+    fn case_changes_apply_to_the_word_at_the_cursor() {
+        let content = Content::with_text("call parseHTTPRequest_now(x)");
+        let text = |operation| plan_at(&content, Position::new(0, 8), None, operation).unwrap().text;
+
+        assert_eq!(text(LineOperation::Snake), "parse_http_request_now");
+        assert_eq!(text(LineOperation::Camel), "parseHttpRequestNow");
+        assert_eq!(text(LineOperation::Upper), "PARSEHTTPREQUEST_NOW");
+        assert_eq!(title_case("it's a TEST-case"), "It's A Test-Case");
+        assert_eq!(map_identifiers("my-var, other_var", camel_case), "myVar, otherVar");
+    }
+
+    #[test]
+    fn inserted_text_ends_where_its_last_line_does() {
+        assert_eq!(end_of(Position::new(2, 4), "abc"), Position::new(2, 7));
+        assert_eq!(end_of(Position::new(2, 4), "abc\nde\nf"), Position::new(4, 1));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,275 @@
+use std::{collections::HashMap, io};
+
+use lsp_types::{
+    CompletionResponse, DocumentSymbolResponse, GotoDefinitionResponse, Hover, HoverContents, MarkedString,
+    PublishDiagnosticsParams, Url,
+};
+use serde_json::{json, Value};
+use tokio::io::AsyncWrite;
+
+use super::{transport::write_message, Event, Request};
+
+/// What a request we sent to the server was for, so we know how to read the response.
+#[derive(Debug, Clone)]
+enum Pending {
+    Initialize,
+    Hover,
+    Definition,
+    Completion,
+    DocumentSymbols(Url),
+}
+
+/// The client half of a language server connection.
+///
+/// It only knows how to write to the server, incoming messages are fed to
+/// [`Client::handle`] so it can be driven by any reader, e.g. a fake server in memory.
+pub struct Client<W> {
+    writer: W,
+    next_id: i64,
+    pending: HashMap<i64, Pending>,
+    initialized: bool,
+    /// Whether the server answers `textDocument/documentSymbol`, the editor parses them itself otherwise.
+    document_symbols: bool,
+    /// Messages from the editor that arrived before the handshake finished.
+    queued: Vec<Request>,
+}
+
+impl<W: AsyncWrite + Unpin> Client<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            next_id: 0,
+            pending: HashMap::new(),
+            initialized: false,
+            document_symbols: false,
+            queued: Vec::new(),
+        }
+    }
+
+    /// Starts the handshake, everything sent before it completes gets queued.
+    pub async fn initialize(&mut self, root: Option<Url>) -> io::Result<()> {
+        let workspace_folders = root.as_ref().map(|uri| json!([{ "uri": uri, "name": "workspace" }]));
+
+        self.request(Pending::Initialize, "initialize", json!({
+            "processId": std::process::id(),
+            "rootUri": root,
+            "workspaceFolders": workspace_folders,
+            "capabilities": {
+                "textDocument": {
+                    "synchronization": { "didSave": true },
+                    "publishDiagnostics": { "relatedInformation": false },
+                    "hover": { "contentFormat": ["plaintext", "markdown"] },
+                    "definition": {},
+                    "completion": { "completionItem": { "snippetSupport": false } },
+                    "documentSymbol": { "hierarchicalDocumentSymbolSupport": true }
+                }
+            },
+            "clientInfo": { "name": "gfe" }
+        })).await
+    }
+
+    /// Sends a [`Request`] from the editor to the server.
+    pub async fn send(&mut self, request: Request) -> io::Result<()> {
+        if !self.initialized {
+            self.queued.push(request);
+            return Ok(());
+        }
+
+        match request {
+            Request::DidOpen { uri, language_id, version, text } => {
+                self.notify("textDocument/didOpen", json!({
+                    "textDocument": { "uri": uri, "languageId": language_id, "version": version, "text": text }
+                })).await
+            },
+            Request::DidChange { uri, version, text } => {
+                self.notify("textDocument/didChange", json!({
+                    "textDocument": { "uri": uri, "version": version },
+                    "contentChanges": [{ "text": text }]
+                })).await
+            },
+            Request::DidSave { uri, text } => {
+                self.notify("textDocument/didSave", json!({
+                    "textDocument": { "uri": uri },
+                    "text": text
+                })).await
+            },
+            Request::DidClose { uri } => {
+                self.notify("textDocument/didClose", json!({
+                    "textDocument": { "uri": uri }
+                })).await
+            },
+            Request::Hover { uri, position } => {
+                self.request(Pending::Hover, "textDocument/hover", json!({
+                    "textDocument": { "uri": uri },
+                    "position": position
+                })).await
+            },
+            Request::Definition { uri, position } => {
+                self.request(Pending::Definition, "textDocument/definition", json!({
+                    "textDocument": { "uri": uri },
+                    "position": position
+                })).await
+            },
+            Request::Completion { uri, position } => {
+                self.request(Pending::Completion, "textDocument/completion", json!({
+                    "textDocument": { "uri": uri },
+                    "position": position
+                })).await
+            },
+            Request::DocumentSymbols { .. } if !self.document_symbols => Ok(()),
+            Request::DocumentSymbols { uri } => {
+                self.request(Pending::DocumentSymbols(uri.clone()), "textDocument/documentSymbol", json!({
+                    "textDocument": { "uri": uri }
+                })).await
+            },
+        }
+    }
+
+    /// Handles a message from the server, returning what the editor should know about it.
+    pub async fn handle(&mut self, message: Value) -> io::Result<Option<Event>> {
+        let method = message.get("method").and_then(Value::as_str).map(str::to_owned);
+        let id = message.get("id").cloned();
+
+        match (method, id) {
+            // A request from the server, we don't support any so answer with nothing.
+            (Some(method), Some(id)) => {
+                let result = match method.as_str() {
+                    "workspace/configuration" => {
+                        let items = message["params"]["items"].as_array().map(Vec::len).unwrap_or(0);
+
+                        Value::Array(vec![Value::Null; items])
+                    },
+                    _ => Value::Null
+                };
+
+                write_message(&mut self.writer, &json!({ "jsonrpc": "2.0", "id": id, "result": result })).await?;
+
+                Ok(None)
+            },
+            (Some(method), None) => Ok(self.handle_notification(&method, message["params"].clone())),
+            (None, Some(id)) => {
+                let Some(pending) = id.as_i64().and_then(|id| self.pending.remove(&id)) else {
+                    return Ok(None);
+                };
+
+                if let Some(error) = message.get("error") {
+                    let text = error["message"].as_str().unwrap_or("Language server error");
+
+                    return Ok(Some(Event::Error(text.to_owned())));
+                }
+
+                self.handle_response(pending, message["result"].clone()).await
+            },
+            (None, None) => Ok(None)
+        }
+    }
+
+    fn handle_notification(&mut self, method: &str, params: Value) -> Option<Event> {
+        match method {
+            "textDocument/publishDiagnostics" => {
+                let params: PublishDiagnosticsParams = serde_json::from_value(params).ok()?;
+
+                Some(Event::Diagnostics { uri: params.uri, diagnostics: params.diagnostics })
+            },
+            "window/showMessage" => {
+                // Only errors (type 1) are worth interrupting the user for.
+                match params["type"].as_i64() {
+                    Some(1) => params["message"].as_str().map(|message| Event::Error(message.to_owned())),
+                    _ => None
+                }
+            },
+            _ => None
+        }
+    }
+
+    async fn handle_response(&mut self, pending: Pending, result: Value) -> io::Result<Option<Event>> {
+        let event = match pending {
+            Pending::Initialize => {
+                self.initialized = true;
+                self.document_symbols = match &result["capabilities"]["documentSymbolProvider"] {
+                    Value::Bool(provided) => *provided,
+                    other => other.is_object()
+                };
+
+                self.notify("initialized", json!({})).await?;
+
+                for request in std::mem::take(&mut self.queued) {
+                    Box::pin(self.send(request)).await?;
+                }
+
+                return Ok(Some(Event::Initialized));
+            },
+            Pending::Hover => {
+                let hover: Option<Hover> = serde_json::from_value(result).ok().flatten();
+
+                Event::Hover(hover.map(|hover| hover_text(hover.contents)).filter(|text| !text.trim().is_empty()))
+            },
+            Pending::Definition => {
+                let response: Option<GotoDefinitionResponse> = serde_json::from_value(result).ok().flatten();
+
+                let location = response.and_then(|response| match response {
+                    GotoDefinitionResponse::Scalar(location) => Some(location),
+                    GotoDefinitionResponse::Array(locations) => locations.into_iter().next(),
+                    GotoDefinitionResponse::Link(links) => links.into_iter().next().map(|link| lsp_types::Location {
+                        uri: link.target_uri,
+                        range: link.target_selection_range,
+                    }),
+                });
+
+                Event::Definition(location)
+            },
+            Pending::Completion => {
+                let response: Option<CompletionResponse> = serde_json::from_value(result).ok().flatten();
+
+                let items = match response {
+                    Some(CompletionResponse::Array(items)) => items,
+                    Some(CompletionResponse::List(list)) => list.items,
+                    None => Vec::new()
+                };
+
+                Event::Completion(items)
+            },
+            Pending::DocumentSymbols(uri) => {
+                let symbols: Option<DocumentSymbolResponse> = serde_json::from_value(result).ok().flatten();
+
+                Event::Symbols { uri, symbols }
+            },
+        };
+
+        Ok(Some(event))
+    }
+
+    async fn request(&mut self, pending: Pending, method: &str, params: Value) -> io::Result<()> {
+        self.next_id += 1;
+        self.pending.insert(self.next_id, pending);
+
+        write_message(&mut self.writer, &json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id,
+            "method": method,
+            "params": params
+        })).await
+    }
+
+    async fn notify(&mut self, method: &str, params: Value) -> io::Result<()> {
+        write_message(&mut self.writer, &json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params
+        })).await
+    }
+}
+
+/// Flattens the different shapes of hover contents into plain text.
+fn hover_text(contents: HoverContents) -> String {
+    let marked = |marked: MarkedString| match marked {
+        MarkedString::String(text) => text,
+        MarkedString::LanguageString(code) => code.value,
+    };
+
+    match contents {
+        HoverContents::Scalar(text) => marked(text),
+        HoverContents::Array(texts) => texts.into_iter().map(marked).collect::<Vec<_>>().join("\n\n"),
+        HoverContents::Markup(markup) => markup.value,
+    }
+}
\ No newline at end of file
@@ -0,0 +1,359 @@
+use std::{io, path::{Path, PathBuf}, process::Stdio};
+
+use iced::{futures::{channel::mpsc, stream, SinkExt, Stream, StreamExt}, Subscription};
+use iced::widget::text_editor::Content;
+use serde_json::Value;
+use tokio::{io::{AsyncBufRead, AsyncWrite, BufReader}, process};
+
+use crate::buffer::Position;
+use crate::worker::{self, Input};
+
+pub use lsp_types::{CompletionItem, Diagnostic, DiagnosticSeverity, DocumentSymbolResponse, Location, Url};
+
+mod client;
+mod transport;
+
+use client::Client;
+use transport::read_message;
+
+/// Something the language server told us.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The server was started, requests can be sent through the [`Handle`].
+    Connected(Handle),
+    /// The handshake finished and queued requests were sent.
+    Initialized,
+    Diagnostics { uri: Url, diagnostics: Vec<Diagnostic> },
+    Hover(Option<String>),
+    Definition(Option<Location>),
+    Completion(Vec<CompletionItem>),
+    /// The symbols of a document, `None` when the server doesn't know any.
+    Symbols { uri: Url, symbols: Option<DocumentSymbolResponse> },
+    Error(String),
+    /// The server exited, it gets started again when the subscription is recreated.
+    Disconnected,
+}
+
+/// A message from the editor to the language server.
+#[derive(Debug, Clone)]
+pub enum Request {
+    DidOpen { uri: Url, language_id: String, version: i32, text: String },
+    DidChange { uri: Url, version: i32, text: String },
+    DidSave { uri: Url, text: String },
+    DidClose { uri: Url },
+    Hover { uri: Url, position: lsp_types::Position },
+    Definition { uri: Url, position: lsp_types::Position },
+    Completion { uri: Url, position: lsp_types::Position },
+    DocumentSymbols { uri: Url },
+}
+
+/// Sends [`Request`]s to a running language server.
+pub type Handle = worker::Handle<Request>;
+
+/// The editor's end of a language server, requests go nowhere while none is running.
+#[derive(Debug, Clone, Default)]
+pub struct Connection(Option<Handle>);
+
+impl Connection {
+    pub fn connected(&mut self, handle: Handle) {
+        self.0 = Some(handle);
+    }
+
+    pub fn disconnected(&mut self) {
+        self.0 = None;
+    }
+
+    /// Sends what `request` makes of the uri of the file at `path`, when a
+    /// server is running and the file has one.
+    pub fn send(&self, path: Option<&Path>, request: impl FnOnce(Url) -> Request) {
+        if let (Some(handle), Some(uri)) = (&self.0, uri(path)) {
+            handle.send(request(uri));
+        }
+    }
+
+    /// Hands the file at `path` to the server, `text` is only read when there's one.
+    pub fn open(&self, path: Option<&Path>, version: i32, text: impl FnOnce() -> String) {
+        let Some(path) = path else {
+            return;
+        };
+
+        self.send(Some(path), |uri| Request::DidOpen { uri, language_id: language_id(path), version, text: text() });
+    }
+}
+
+/// Runs `command` as the language server of the workspace at `root`.
+pub fn connect(command: Vec<String>, root: PathBuf) -> Subscription<Event> {
+    let id = (command.clone(), root.clone());
+
+    worker::subscription(
+        id,
+        |mut output| async move { start(command, root, &mut output).await },
+        |result| match result {
+            Ok(()) => Event::Disconnected,
+            Err(error) => Event::Error(format!("Language server: {error}")),
+        },
+    )
+}
+
+async fn start(command: Vec<String>, root: PathBuf, output: &mut mpsc::Sender<Event>) -> io::Result<()> {
+    let (program, args) = command.split_first()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty command"))?;
+
+    let mut child = process::Command::new(program)
+        .args(args)
+        .current_dir(&root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+        return Err(io::Error::new(io::ErrorKind::BrokenPipe, "no stdio"));
+    };
+
+    serve(BufReader::new(stdout), stdin, Url::from_directory_path(&root).ok(), output).await
+}
+
+/// Runs a client over any reader and writer pair until the server side closes.
+///
+/// This is what [`connect`] uses with the stdio of a child process, tests can
+/// hand it the ends of an in-memory pipe instead.
+pub async fn serve<R, W>(reader: R, writer: W, root: Option<Url>, output: &mut mpsc::Sender<Event>) -> io::Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut client = Client::new(writer);
+    let (handle, requests) = Handle::new();
+
+    let _ = output.send(Event::Connected(handle)).await;
+
+    client.initialize(root).await?;
+
+    let mut inputs = Box::pin(worker::inputs(incoming(reader), requests));
+
+    while let Some(input) = inputs.next().await {
+        match input {
+            Input::Output(message) => {
+                if let Some(event) = client.handle(message?).await? {
+                    let _ = output.send(event).await;
+                }
+            },
+            Input::Request(request) => {
+                client.send(request).await?;
+            },
+            Input::Closed => break
+        }
+    }
+
+    Ok(())
+}
+
+fn incoming<R: AsyncBufRead + Unpin>(reader: R) -> impl Stream<Item = io::Result<Value>> {
+    stream::unfold(reader, |mut reader| async move {
+        match read_message(&mut reader).await {
+            Ok(Some(message)) => Some((Ok(message), reader)),
+            Ok(None) => None,
+            Err(error) => Some((Err(error), reader)),
+        }
+    })
+}
+
+/// How the server refers to the file at `path`.
+pub fn uri(path: Option<&Path>) -> Option<Url> {
+    Url::from_file_path(path?).ok()
+}
+
+/// The LSP language identifier for a file.
+pub fn language_id(path: &Path) -> String {
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("");
+
+    match extension {
+        "rs" => "rust",
+        "py" => "python",
+        "js" | "mjs" | "cjs" => "javascript",
+        "ts" => "typescript",
+        "tsx" => "typescriptreact",
+        "jsx" => "javascriptreact",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "hpp" => "cpp",
+        "md" => "markdown",
+        "sh" => "shellscript",
+        "yml" => "yaml",
+        other => other,
+    }.to_owned()
+}
+
+/// The directory a language server for `path` should treat as the workspace,
+/// the closest folder with a `.git` in it or otherwise the file's own folder.
+pub fn workspace_root(path: &Path) -> PathBuf {
+    let parent = path.parent().unwrap_or(Path::new("."));
+
+    parent.ancestors()
+        .find(|directory| directory.join(".git").exists())
+        .unwrap_or(parent)
+        .to_path_buf()
+}
+
+/// Converts a position in the buffer to one the server understands (UTF-16 columns).
+pub fn to_lsp_position(content: &Content, position: Position) -> lsp_types::Position {
+    let character: usize = content.line(position.line)
+        .map(|line| line.chars().take(position.column).map(char::len_utf16).sum())
+        .unwrap_or(0);
+
+    lsp_types::Position::new(position.line as u32, character as u32)
+}
+
+/// Converts a position from the server to one in the buffer.
+pub fn from_lsp_position(content: &Content, position: lsp_types::Position) -> Position {
+    let line = position.line as usize;
+    let mut units = 0;
+
+    let column = content.line(line)
+        .map(|text| {
+            text.chars()
+                .take_while(|character| {
+                    units += character.len_utf16();
+                    units <= position.character as usize
+                })
+                .count()
+        })
+        .unwrap_or(0);
+
+    Position::new(line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use iced::futures::join;
+    use serde_json::json;
+    use tokio::io::{duplex, split, AsyncBufReadExt, AsyncReadExt};
+
+    use super::*;
+    use transport::write_message;
+
+    #[tokio::test]
+    async fn talks_to_a_server() {
+        let (editor, server) = duplex(64 * 1024);
+        let (editor_reader, editor_writer) = split(editor);
+        let (server_reader, mut server_writer) = split(server);
+        let mut server_reader = BufReader::new(server_reader);
+        let (mut output, mut events) = mpsc::channel(100);
+
+        let root = Url::parse("file:///project/").unwrap();
+        let uri = Url::parse("file:///project/main.rs").unwrap();
+
+        let client = serve(BufReader::new(editor_reader), editor_writer, Some(root), &mut output);
+
+        let server = async move {
+            let Some(Event::Connected(handle)) = events.next().await else {
+                panic!("the client didn't connect");
+            };
+
+            // Sent before the handshake, so it waits for it.
+            handle.send(Request::DidOpen {
+                uri: uri.clone(),
+                language_id: String::from("rust"),
+                version: 0,
+                text: String::from("fn main() {}")
+            });
+
+            let initialize = read_message(&mut server_reader).await.unwrap().unwrap();
+            assert_eq!(initialize["method"], "initialize");
+            assert_eq!(initialize["params"]["rootUri"], "file:///project/");
+
+            write_message(&mut server_writer, &json!({
+                "jsonrpc": "2.0",
+                "id": initialize["id"],
+                "result": { "capabilities": {} }
+            })).await.unwrap();
+
+            assert!(matches!(events.next().await, Some(Event::Initialized)));
+
+            let initialized = read_message(&mut server_reader).await.unwrap().unwrap();
+            assert_eq!(initialized["method"], "initialized");
+            assert!(initialized.get("id").is_none());
+
+            let open = read_message(&mut server_reader).await.unwrap().unwrap();
+            assert_eq!(open["method"], "textDocument/didOpen");
+            assert_eq!(open["params"]["textDocument"]["languageId"], "rust");
+            assert_eq!(open["params"]["textDocument"]["text"], "fn main() {}");
+
+            handle.send(Request::DidChange { uri: uri.clone(), version: 1, text: String::from("fn main() { }") });
+
+            // Read by hand to check the framing itself.
+            let mut header = String::new();
+            server_reader.read_line(&mut header).await.unwrap();
+            let length: usize = header.strip_prefix("Content-Length: ").unwrap().strip_suffix("\r\n").unwrap().parse().unwrap();
+
+            let mut separator = String::new();
+            server_reader.read_line(&mut separator).await.unwrap();
+            assert_eq!(separator, "\r\n");
+
+            let mut body = vec![0; length];
+            server_reader.read_exact(&mut body).await.unwrap();
+
+            let change: Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(change["method"], "textDocument/didChange");
+            assert_eq!(change["params"]["textDocument"]["version"], 1);
+            assert_eq!(change["params"]["contentChanges"][0]["text"], "fn main() { }");
+
+            write_message(&mut server_writer, &json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/publishDiagnostics",
+                "params": {
+                    "uri": uri,
+                    "diagnostics": [{
+                        "range": { "start": { "line": 0, "character": 3 }, "end": { "line": 0, "character": 7 } },
+                        "severity": 2,
+                        "message": "unused"
+                    }]
+                }
+            })).await.unwrap();
+
+            let Some(Event::Diagnostics { uri: published, diagnostics }) = events.next().await else {
+                panic!("the diagnostics weren't passed on");
+            };
+
+            assert_eq!(published, uri);
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(diagnostics[0].message, "unused");
+            assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+
+            // Hanging up ends the client.
+            drop(server_writer);
+        };
+
+        let (result, ()) = join!(client, server);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn requests_wait_for_a_server_and_a_file() {
+        let (handle, mut requests) = Handle::new();
+        let mut connection = Connection::default();
+        let path = Path::new("/project/main.rs");
+
+        connection.open(Some(path), 0, || unreachable!("nobody to read the text"));
+
+        connection.connected(handle);
+        connection.send(None, |uri| Request::DidClose { uri });
+        connection.open(Some(path), 3, || String::from("fn main() {}"));
+
+        match requests.try_recv() {
+            Ok(Request::DidOpen { uri, language_id, version, text }) => {
+                assert_eq!(uri, Url::from_file_path(path).unwrap());
+                assert_eq!((language_id.as_str(), version, text.as_str()), ("rust", 3, "fn main() {}"));
+            },
+            other => panic!("expected the file to be opened, got {other:?}")
+        }
+
+        // Nothing else was sent before the handle went away with the server.
+        connection.disconnected();
+        connection.send(Some(path), |uri| Request::DidClose { uri });
+
+        assert!(matches!(requests.try_recv(), Err(mpsc::TryRecvError::Closed)));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,82 @@
+use std::io;
+
+use serde_json::Value;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Reads one JSON-RPC message framed by a `Content-Length` header.
+///
+/// Returns `None` once the other side closed the stream.
+pub async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> io::Result<Option<Value>> {
+    let mut length = None;
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+
+        let header = line.trim_end();
+
+        if header.is_empty() {
+            match length {
+                Some(_) => break,
+                None => continue
+            }
+        }
+
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = Some(value.trim().parse::<usize>().map_err(|error| {
+                io::Error::new(io::ErrorKind::InvalidData, error)
+            })?);
+        }
+    }
+
+    let mut body = vec![0; length.unwrap_or(0)];
+    reader.read_exact(&mut body).await?;
+
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+/// Writes one JSON-RPC message with its `Content-Length` header.
+pub async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, message: &Value) -> io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+
+    writer.write_all(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes()).await?;
+    writer.write_all(&body).await?;
+    writer.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn messages_are_framed_by_their_length() {
+        let mut written = Vec::new();
+        write_message(&mut written, &json!({ "id": 1 })).await.unwrap();
+
+        assert_eq!(written, b"Content-Length: 8\r\n\r\n{\"id\":1}");
+    }
+
+    #[tokio::test]
+    async fn messages_are_read_one_at_a_time() {
+        let mut stream: &[u8] = b"Content-Length: 8\r\nContent-Type: application/vscode-jsonrpc\r\n\r\n{\"id\":1}Content-Length: 8\r\n\r\n{\"id\":2}";
+
+        assert_eq!(read_message(&mut stream).await.unwrap(), Some(json!({ "id": 1 })));
+        assert_eq!(read_message(&mut stream).await.unwrap(), Some(json!({ "id": 2 })));
+        assert_eq!(read_message(&mut stream).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn broken_headers_are_an_error() {
+        let mut stream: &[u8] = b"Content-Length: many\r\n\r\n{}";
+
+        assert_eq!(read_message(&mut stream).await.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
\ No newline at end of file
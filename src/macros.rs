@@ -0,0 +1,225 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use iced::widget::text_editor::{Action, Edit, Motion};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::{config::config_dir, emacs, lines::LineOperation, vim, GFEError};
+
+/// One recorded keystroke or command of a macro.
+///
+/// Clicks and scrolling aren't recorded, they depend on where things
+/// happen to be on screen.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Step {
+    Insert(char),
+    Paste(String),
+    Enter,
+    Backspace,
+    Delete,
+    Move(Direction),
+    Select(Direction),
+    SelectWord,
+    SelectLine,
+    SelectAll,
+    Indent,
+    Outdent,
+    Undo,
+    Redo,
+    AddCursorAbove,
+    AddCursorBelow,
+    SelectNextOccurrence,
+    SplitSelection,
+    Lines(LineOperation),
+    ToggleComment,
+    ToggleBlockComment,
+    JumpToBracket,
+    ToggleFold,
+    FoldAll,
+    UnfoldAll,
+    /// A key handled by the Vim layer.
+    Vim(vim::Key),
+    /// A key handled by the Emacs layer.
+    Emacs(emacs::Key),
+}
+
+/// [`Motion`] in a form that can be written to disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+    WordLeft,
+    WordRight,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    DocumentStart,
+    DocumentEnd,
+}
+
+impl From<Motion> for Direction {
+    fn from(motion: Motion) -> Self {
+        match motion {
+            Motion::Left => Direction::Left,
+            Motion::Right => Direction::Right,
+            Motion::Up => Direction::Up,
+            Motion::Down => Direction::Down,
+            Motion::WordLeft => Direction::WordLeft,
+            Motion::WordRight => Direction::WordRight,
+            Motion::Home => Direction::Home,
+            Motion::End => Direction::End,
+            Motion::PageUp => Direction::PageUp,
+            Motion::PageDown => Direction::PageDown,
+            Motion::DocumentStart => Direction::DocumentStart,
+            Motion::DocumentEnd => Direction::DocumentEnd,
+        }
+    }
+}
+
+impl From<Direction> for Motion {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Left => Motion::Left,
+            Direction::Right => Motion::Right,
+            Direction::Up => Motion::Up,
+            Direction::Down => Motion::Down,
+            Direction::WordLeft => Motion::WordLeft,
+            Direction::WordRight => Motion::WordRight,
+            Direction::Home => Motion::Home,
+            Direction::End => Motion::End,
+            Direction::PageUp => Motion::PageUp,
+            Direction::PageDown => Motion::PageDown,
+            Direction::DocumentStart => Motion::DocumentStart,
+            Direction::DocumentEnd => Motion::DocumentEnd,
+        }
+    }
+}
+
+impl Step {
+    /// The step for an action of the `text_editor`, `None` for the ones
+    /// that aren't recorded.
+    pub fn from_action(action: &Action) -> Option<Self> {
+        match action {
+            Action::Edit(Edit::Insert(character)) => Some(Step::Insert(*character)),
+            Action::Edit(Edit::Paste(text)) => Some(Step::Paste(text.to_string())),
+            Action::Edit(Edit::Enter) => Some(Step::Enter),
+            Action::Edit(Edit::Backspace) => Some(Step::Backspace),
+            Action::Edit(Edit::Delete) => Some(Step::Delete),
+            Action::Move(motion) => Some(Step::Move((*motion).into())),
+            Action::Select(motion) => Some(Step::Select((*motion).into())),
+            Action::SelectWord => Some(Step::SelectWord),
+            Action::SelectLine => Some(Step::SelectLine),
+            Action::Click(_) | Action::Drag(_) | Action::Scroll { .. } => None,
+        }
+    }
+
+    /// The `text_editor` action to replay, `None` for steps that are commands.
+    pub fn action(&self) -> Option<Action> {
+        match self {
+            Step::Insert(character) => Some(Action::Edit(Edit::Insert(*character))),
+            Step::Paste(text) => Some(Action::Edit(Edit::Paste(Arc::new(text.clone())))),
+            Step::Enter => Some(Action::Edit(Edit::Enter)),
+            Step::Backspace => Some(Action::Edit(Edit::Backspace)),
+            Step::Delete => Some(Action::Edit(Edit::Delete)),
+            Step::Move(direction) => Some(Action::Move((*direction).into())),
+            Step::Select(direction) => Some(Action::Select((*direction).into())),
+            Step::SelectWord => Some(Action::SelectWord),
+            Step::SelectLine => Some(Action::SelectLine),
+            _ => None
+        }
+    }
+}
+
+/// Macros saved under a name, stored as TOML next to the config.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Macros {
+    pub macros: BTreeMap<String, Vec<Step>>,
+}
+
+pub async fn load_macros() -> Result<Macros, GFEError> {
+    let path = config_dir().ok_or(GFEError::NoConfigDir)?.join("macros.toml");
+
+    let contents = match fs::read_to_string(&path).await {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Macros::default()),
+        Err(error) => return Err(GFEError::IO(error.kind()))
+    };
+
+    toml::from_str(&contents).map_err(|error| GFEError::InvalidConfig(error.message().to_owned()))
+}
+
+pub async fn save_macros(macros: Macros) -> Result<(), GFEError> {
+    let directory = config_dir().ok_or(GFEError::NoConfigDir)?;
+
+    let contents = toml::to_string_pretty(&macros)
+        .map_err(|error| GFEError::InvalidConfig(error.to_string()))?;
+
+    fs::create_dir_all(&directory)
+        .await
+        .map_err(|error| error.kind())
+        .map_err(GFEError::IO)?;
+
+    fs::write(directory.join("macros.toml"), contents)
+        .await
+        .map_err(|error| error.kind())
+        .map_err(GFEError::IO)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use iced::{keyboard::{self, Modifiers}, Point};
+
+    use super::*;
+
+    #[test]
+    fn recorded_actions_replay_as_themselves() {
+        let actions = [
+            Action::Edit(Edit::Insert('x')),
+            Action::Edit(Edit::Paste(Arc::new(String::from("pasted")))),
+            Action::Edit(Edit::Enter),
+            Action::Move(Motion::WordLeft),
+            Action::Select(Motion::DocumentEnd),
+            Action::SelectLine,
+        ];
+
+        for action in actions {
+            let step = Step::from_action(&action).unwrap();
+
+            assert_eq!(step.action(), Some(action));
+        }
+    }
+
+    #[test]
+    fn mouse_actions_are_not_recorded() {
+        assert_eq!(Step::from_action(&Action::Click(Point::ORIGIN)), None);
+        assert_eq!(Step::from_action(&Action::Scroll { lines: 3 }), None);
+        assert_eq!(Step::ToggleComment.action(), None);
+    }
+
+    #[test]
+    fn macros_survive_being_saved_as_toml() {
+        let emacs_key = emacs::Key::from_keyboard(keyboard::Key::Character("k"), Modifiers::CTRL).unwrap();
+
+        let macros = Macros {
+            macros: BTreeMap::from([(String::from("tidy"), vec![
+                Step::Paste(String::from("two\nlines")),
+                Step::Move(Direction::Home),
+                Step::Lines(LineOperation::SortUnique),
+                Step::Vim(vim::Key::Char('x')),
+                Step::Vim(vim::Key::Escape),
+                Step::Emacs(emacs_key),
+            ])])
+        };
+
+        let saved = toml::to_string_pretty(&macros).unwrap();
+        let loaded: Macros = toml::from_str(&saved).unwrap();
+
+        assert_eq!(loaded.macros, macros.macros);
+    }
+}
\ No newline at end of file
@@ -1,33 +1,218 @@
-use std::{env, fs, io::ErrorKind, path::{Path, PathBuf}, sync::Arc};
+use std::{collections::BTreeMap, env, fmt, fs, io::ErrorKind, path::{Path, PathBuf}, sync::Arc};
+
+use completion::{BufferWords, Completion, Provider};
+use block::Block;
+use cursors::{Cursor, Cursors};
+use config::{load_config, save_config, Config};
+use history::{Group, History};
+use indent::Indentation;
+use snippets::{load_snippets, SnippetCompletions, Snippets};
+use lines::{LineEdit, LineOperation};
+use folding::Folds;
+use outline::Symbol;
+use session::{load_session, save_session, Session};
+use transforms::SavePipeline;
+use keymap::{Effect, Keymap};
+use macros::{load_macros, save_macros, Macros, Step};
+use vim::Vim;
+use emacs::Emacs;
 
 use modal::Modal;
 use circle::circle;
-use files::{load_file, pick_file, save_file};
-use iced::{alignment::Horizontal, executor, keyboard, theme, widget::{self, text_editor::{Action, Content}}, Alignment, Application, Command, Font, Length, Settings, Subscription, Theme};
+use decorations::{Decoration, Decorations};
+use diagnostics::{Diagnostic, Diagnostics, Severity};
+use diff::{Comparison, Hunk};
+use files::{load_file, pick_file, save_file, FileFormat};
+use gutter::{gutter, Gutter};
+use minimap::{minimap, MINIMAP_WIDTH};
+use panes::{Document, Panes};
+use iced::{alignment::Horizontal, event, executor, keyboard, theme, widget::{self, pane_grid, scrollable, text_editor::{Action, Content, Edit, Motion}}, window, Alignment, Application, Color, Command, Event, Font, Length, Point, Rectangle, Settings, Size, Subscription, Theme};
 use styles::text_box::TextBoxStyle;
+use viewport::{Viewport, Wrap, CHAR_WIDTH, DEFAULT_WRAP_COLUMN, EDITOR_PADDING, LINE_HEIGHT};
 
 mod files;
 mod styles;
 mod circle;
 mod modal;
+mod gutter;
+mod minimap;
+mod panes;
+mod viewport;
+mod decorations;
+mod config;
+mod buffer;
+mod indent;
+mod encoding;
+mod editorconfig;
+mod history;
+mod transforms;
+mod lsp;
+mod diagnostics;
+mod diff;
+mod completion;
+mod snippets;
+mod lines;
+mod comments;
+mod brackets;
+mod folding;
+mod git;
+mod session;
+mod outline;
+mod cursors;
+mod block;
+mod keymap;
+mod vim;
+mod emacs;
+mod macros;
+mod terminal;
+mod worker;
 
 #[derive(Debug, Clone)]
 enum Message {
     Open, 
-    FileOpened(Result<(PathBuf, Arc<String>), GFEError>), 
+    FileOpened(Result<(PathBuf, Arc<String>, FileFormat), GFEError>), 
     FileSaved(Result<(), GFEError>), 
+    Formatted(Result<String, GFEError>),
+    CompareWithSaved,
+    SavedLoaded(Result<Arc<String>, GFEError>),
+    ToggleDiffLayout,
+    RevertHunk(usize),
+    GitLoaded(Result<Option<git::Tracked>, GFEError>),
+    /// Puts back what the last commit has where the cursor is.
+    RevertChange,
+
+    ToggleSourceControl,
+    ChangesLoaded(Result<Option<git::Changes>, GFEError>),
+    SourceControl(git::Action),
+    Committed(Result<(), GFEError>),
+    GitDone(Result<(), GFEError>),
+
+    ToggleTerminal,
+    Terminal(terminal::Event),
+    FocusTerminal,
+    /// Scrolls the terminal's scrollback, up when positive.
+    ScrollTerminal(isize),
+    SelectInTerminal(Option<terminal::Selection>),
+    TerminalPasted(Option<String>),
 
     ToggleModal(bool),
+    ToggleRelativeNumbers,
+    CycleWrap,
+    ToggleRuler,
+    ToggleMinimap,
+    WindowResized(Size),
+    EditorScrolled(pane_grid::Pane, scrollable::Viewport),
+    /// Scrolls a pane so a line of the buffer is at the top.
+    ScrollTo(pane_grid::Pane, usize),
+
+    SplitPane(pane_grid::Axis),
+    ClosePane,
+    FocusPane(pane_grid::Pane),
+    FocusAdjacentPane(pane_grid::Direction),
+    PaneResized(pane_grid::ResizeEvent),
+    /// Something done in the `text_editor` of a pane that doesn't have the focus.
+    PaneAction(pane_grid::Pane, Action),
+
+    ConfigLoaded(Result<Config, GFEError>),
+    ConfigSaved(Result<(), GFEError>),
+    SnippetsLoaded(Result<Snippets, GFEError>),
 
     Save, 
     Edit(Action), 
     SelectAll,
+    Undo,
+    Redo,
+    Indent,
+    Outdent,
+    IndentationChanged(Indentation),
+
+    Lsp(lsp::Event),
+    Hover,
+    GoToDefinition,
+    Complete,
+    AcceptCompletion(usize),
+    DismissInfo,
+
+    ToggleProblems,
+    NextProblem,
+    PreviousProblem,
+    JumpTo(buffer::Position),
+
+    ShowSnippets,
+    InsertSnippet(usize),
+    ShowLineOperations,
+    Lines(LineOperation),
+    ToggleComment,
+    ToggleBlockComment,
+    JumpToBracket,
+
+    ToggleOutline,
+    ShowSymbols,
+    SymbolQueryChanged(String),
+    /// Goes to the symbol best matching what was typed in the symbol picker.
+    SubmitSymbolQuery,
+    /// Moves the cursor to a line and column of the document, opening the folds it's in.
+    GoToSymbol(usize, usize),
+
+    AddCursorAbove,
+    AddCursorBelow,
+    SelectNextOccurrence,
+    SplitSelection,
+    ExtendBlock(Motion),
+    Copy,
+    ModifiersChanged(keyboard::Modifiers),
+
+    KeyPressed(keyboard::Key, keyboard::Modifiers),
+    CycleKeymap,
+
+    ToggleRecording,
+    /// Runs a saved macro, or the last recorded one, a number of times or
+    /// until the end of the file when that's `None`.
+    RunMacro(Option<String>, Option<usize>),
+    ShowMacros,
+    MacroCountChanged(String),
+    MacroNameChanged(String),
+    SaveMacro,
+    DeleteMacro(String),
+    MacrosLoaded(Result<Macros, GFEError>),
+    MacrosSaved(Result<(), GFEError>),
+
+    /// Opens or closes the fold hanging off a line of the buffer.
+    ToggleFold(usize),
+    ToggleFoldAtCursor,
+    FoldAll,
+    UnfoldAll,
+    SessionLoaded(Result<Session, GFEError>),
+    SessionSaved(Result<(), GFEError>),
 }
 
 #[derive(Debug, Clone)]
 enum GFEError {
     DialogClosed,
-    IO(ErrorKind)
+    IO(ErrorKind),
+    NoConfigDir,
+    InvalidConfig(String),
+    Formatter(String),
+    LanguageServer(String),
+    Git(String),
+    Terminal(String),
+    UnsavedChanges
+}
+
+impl fmt::Display for GFEError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GFEError::DialogClosed => write!(f, "No file was picked"),
+            GFEError::IO(kind) => write!(f, "{kind}"),
+            GFEError::NoConfigDir => write!(f, "Couldn't find a config directory"),
+            GFEError::InvalidConfig(message) => write!(f, "Invalid config: {message}"),
+            GFEError::Formatter(message) => write!(f, "Formatter failed: {}", message.trim()),
+            GFEError::LanguageServer(message) => write!(f, "{message}"),
+            GFEError::Git(message) => write!(f, "Git failed: {}", message.trim()),
+            GFEError::Terminal(message) => write!(f, "{message}"),
+            GFEError::UnsavedChanges => write!(f, "Save your changes before closing the file")
+        }
+    }
 }
 
 fn main() -> iced::Result {
@@ -37,9 +222,76 @@ fn main() -> iced::Result {
 struct Editor {
     content: Content,
     path: Option<PathBuf>,
+    format: FileFormat,
     saved: bool,
     show_control_pallet: bool,
-    error: Option<GFEError>
+    error: Option<GFEError>,
+    viewport: Viewport,
+    window_size: Size,
+    relative_numbers: bool,
+    config: Config,
+    ruler: Option<usize>,
+    show_minimap: bool,
+    auto_pair: bool,
+    horizontal_offset: f32,
+    indentation: Indentation,
+    history: History,
+    lsp: lsp::Connection,
+    /// Bumped on every change so the language server can order them.
+    document_version: i32,
+    diagnostics: Diagnostics,
+    show_problems: bool,
+    hover: Option<String>,
+    completion: Option<Completion>,
+    /// Sources of completions that answer right away, see [`completion::Provider`].
+    providers: Vec<Box<dyn Provider>>,
+    snippets: Snippets,
+    /// The snippet whose tab stops Tab moves through.
+    snippet: Option<snippets::Session>,
+    show_snippets: bool,
+    show_line_operations: bool,
+    cursors: Cursors,
+    /// The rectangular selection the extra cursors were made from, if any.
+    block: Option<Block>,
+    modifiers: keyboard::Modifiers,
+    vim: Vim,
+    emacs: Emacs,
+    /// Steps of the macro being recorded.
+    recording: Option<Vec<Step>>,
+    last_macro: Vec<Step>,
+    macros: Macros,
+    show_macros: bool,
+    macro_count: String,
+    macro_name: String,
+    /// Set by `:wq` so the window closes once the file is written.
+    quit_after_save: bool,
+    /// Where to put the cursor once the file a definition lives in has been opened.
+    pending_jump: Option<lsp_types::Position>,
+    /// Folded lines aren't in `content`, these put them back.
+    folds: Folds,
+    session: Session,
+    /// Diagnostics as the language server sent them, in lines of the document.
+    published_diagnostics: Vec<lsp_types::Diagnostic>,
+    show_outline: bool,
+    show_symbols: bool,
+    symbol_query: String,
+    /// Symbols of the document for the outline and the symbol picker, kept
+    /// up to date while either is shown.
+    symbols: Vec<Symbol>,
+    /// Whether the symbols came from the language server, the parsed ones aren't used then.
+    symbols_from_server: bool,
+    /// The splits of the editor, the focused pane's cursor and scroll are the
+    /// ones in `content` and `viewport`.
+    panes: Panes,
+    /// The file on disk next to the buffer while the diff view is shown.
+    comparison: Option<Comparison>,
+    side_by_side_diff: bool,
+    /// What git knows about the file, `None` outside of a repository.
+    tracked: Option<git::Tracked>,
+    /// Where the buffer differs from the last commit.
+    git_hunks: Vec<Hunk>,
+    source_control: git::SourceControl,
+    terminal: terminal::Panel
 }
 
 impl Application for Editor {
@@ -70,14 +322,77 @@ impl Application for Editor {
             }
         };
 
+        let initial_command = Command::batch([
+            Command::perform(load_config(), Message::ConfigLoaded),
+            Command::perform(load_snippets(), Message::SnippetsLoaded),
+            Command::perform(load_macros(), Message::MacrosLoaded),
+            Command::perform(load_session(), Message::SessionLoaded),
+            initial_command
+        ]);
+
+
+        let mut editor = Self {
+            path: None, 
+            format: FileFormat::default(),
+            content: Content::with_text("Hewwo, type your text here or open a file. :)"), 
+            saved: false, 
+            show_control_pallet: false, 
+            error: None,
+            viewport: Viewport::new(),
+            window_size: Settings::<()>::default().window.size,
+            relative_numbers: false,
+            config: Config::default(),
+            ruler: None,
+            show_minimap: true,
+            auto_pair: true,
+            horizontal_offset: 0.0,
+            indentation: Indentation::default(),
+            history: History::default(),
+            lsp: lsp::Connection::default(),
+            document_version: 0,
+            diagnostics: Diagnostics::default(),
+            show_problems: false,
+            hover: None,
+            completion: None,
+            providers: vec![Box::new(BufferWords)],
+            snippets: Snippets::default(),
+            snippet: None,
+            show_snippets: false,
+            show_line_operations: false,
+            cursors: Cursors::default(),
+            block: None,
+            modifiers: keyboard::Modifiers::default(),
+            vim: Vim::default(),
+            emacs: Emacs::default(),
+            recording: None,
+            last_macro: Vec::new(),
+            macros: Macros::default(),
+            show_macros: false,
+            macro_count: String::new(),
+            macro_name: String::new(),
+            quit_after_save: false,
+            pending_jump: None,
+            folds: Folds::default(),
+            session: Session::default(),
+            published_diagnostics: Vec::new(),
+            show_outline: false,
+            show_symbols: false,
+            symbol_query: String::new(),
+            symbols: Vec::new(),
+            symbols_from_server: false,
+            panes: Panes::new(),
+            comparison: None,
+            side_by_side_diff: false,
+            tracked: None,
+            git_hunks: Vec::new(),
+            source_control: git::SourceControl::default(),
+            terminal: terminal::Panel::default()
+        };
+
+        editor.resize_viewport();
+
         (
-            Self {
-                path: None, 
-                content: Content::with_text("Hewwo, type your text here or open a file. :)"), 
-                saved: false, 
-                show_control_pallet: false, 
-                error: None
-            },
+            editor,
             initial_command
         )
     }
@@ -94,7 +409,20 @@ impl Application for Editor {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        if self.show_control_pallet == true {
+        let window_events = event::listen_with(|event, _| match event {
+            Event::Window(_, window::Event::Resized { width, height }) => {
+                Some(Message::WindowResized(Size::new(width as f32, height as f32)))
+            },
+            Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                Some(Message::ModifiersChanged(modifiers))
+            },
+            Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) => {
+                Some(Message::KeyPressed(key, modifiers))
+            },
+            _ => None
+        });
+
+        let keys = if self.show_control_pallet {
             keyboard::on_key_release(|key, _| match key.as_ref() {
                 keyboard::Key::Character("o") => {
                     Some(Message::Open)
@@ -105,50 +433,301 @@ impl Application for Editor {
                 keyboard::Key::Character("a") => {
                     Some(Message::SelectAll)
                 },
+                keyboard::Key::Character("z") => {
+                    Some(Message::Undo)
+                },
+                keyboard::Key::Character("y") => {
+                    Some(Message::Redo)
+                },
+                keyboard::Key::Character("l") => {
+                    Some(Message::ToggleRelativeNumbers)
+                },
+                keyboard::Key::Character("w") => {
+                    Some(Message::CycleWrap)
+                },
+                keyboard::Key::Character("r") => {
+                    Some(Message::ToggleRuler)
+                },
+                keyboard::Key::Character("[") => {
+                    Some(Message::ToggleMinimap)
+                },
+                keyboard::Key::Character("\\") => {
+                    Some(Message::SplitPane(pane_grid::Axis::Vertical))
+                },
+                keyboard::Key::Character("`") => {
+                    Some(Message::SplitPane(pane_grid::Axis::Horizontal))
+                },
+                keyboard::Key::Character("0") => {
+                    Some(Message::ClosePane)
+                },
+                keyboard::Key::Character("1") => {
+                    Some(Message::CompareWithSaved)
+                },
+                keyboard::Key::Character("2") => {
+                    Some(Message::RevertChange)
+                },
+                keyboard::Key::Character("3") => {
+                    Some(Message::ToggleSourceControl)
+                },
+                keyboard::Key::Character("4") => {
+                    Some(Message::ToggleTerminal)
+                },
+                keyboard::Key::Named(keyboard::key::Named::ArrowUp) => {
+                    Some(Message::FocusAdjacentPane(pane_grid::Direction::Up))
+                },
+                keyboard::Key::Named(keyboard::key::Named::ArrowDown) => {
+                    Some(Message::FocusAdjacentPane(pane_grid::Direction::Down))
+                },
+                keyboard::Key::Named(keyboard::key::Named::ArrowLeft) => {
+                    Some(Message::FocusAdjacentPane(pane_grid::Direction::Left))
+                },
+                keyboard::Key::Named(keyboard::key::Named::ArrowRight) => {
+                    Some(Message::FocusAdjacentPane(pane_grid::Direction::Right))
+                },
+                keyboard::Key::Character("h") => {
+                    Some(Message::Hover)
+                },
+                keyboard::Key::Character("d") => {
+                    Some(Message::GoToDefinition)
+                },
+                keyboard::Key::Named(keyboard::key::Named::Space) => {
+                    Some(Message::Complete)
+                },
+                keyboard::Key::Character("t") => {
+                    Some(Message::ShowSnippets)
+                },
+                keyboard::Key::Character("u") => {
+                    Some(Message::ShowLineOperations)
+                },
+                keyboard::Key::Character("/") => {
+                    Some(Message::ToggleComment)
+                },
+                keyboard::Key::Character("g") => {
+                    Some(Message::ToggleBlockComment)
+                },
+                keyboard::Key::Character("]") => {
+                    Some(Message::JumpToBracket)
+                },
+                keyboard::Key::Character("f") => {
+                    Some(Message::ToggleFoldAtCursor)
+                },
+                keyboard::Key::Character("-") => {
+                    Some(Message::FoldAll)
+                },
+                keyboard::Key::Character("=") => {
+                    Some(Message::UnfoldAll)
+                },
+                keyboard::Key::Character("'") => {
+                    Some(Message::ToggleOutline)
+                },
+                keyboard::Key::Character(";") => {
+                    Some(Message::ShowSymbols)
+                },
+                keyboard::Key::Character("k") => {
+                    Some(Message::AddCursorAbove)
+                },
+                keyboard::Key::Character("j") => {
+                    Some(Message::AddCursorBelow)
+                },
+                keyboard::Key::Character("n") => {
+                    Some(Message::SelectNextOccurrence)
+                },
+                keyboard::Key::Character("i") => {
+                    Some(Message::SplitSelection)
+                },
+                keyboard::Key::Character("c") => {
+                    Some(Message::Copy)
+                },
+                keyboard::Key::Character("m") => {
+                    Some(Message::CycleKeymap)
+                },
+                keyboard::Key::Character("q") => {
+                    Some(Message::ToggleRecording)
+                },
+                keyboard::Key::Character("e") => {
+                    Some(Message::RunMacro(None, Some(1)))
+                },
+                keyboard::Key::Character("b") => {
+                    Some(Message::ShowMacros)
+                },
+                keyboard::Key::Named(keyboard::key::Named::Escape) => {
+                    Some(Message::ToggleModal(false))
+                },
+                keyboard::Key::Character("p") => {
+                    Some(Message::ToggleProblems)
+                },
+                keyboard::Key::Character(".") => {
+                    Some(Message::NextProblem)
+                },
+                keyboard::Key::Character(",") => {
+                    Some(Message::PreviousProblem)
+                },
                 keyboard::Key::Named(keyboard::key::Named::Control) => {
                     Some(Message::ToggleModal(false))
                 },
                 _ => None
             })
+        } else if self.terminal.has_keys() {
+            // Every key is the shell's, Ctrl+C has to interrupt rather than open the palette.
+            Subscription::none()
         } else {
-            keyboard::on_key_press(|key, _| match key.as_ref() {
+            keyboard::on_key_press(|key, modifiers| match key.as_ref() {
                 keyboard::Key::Named(keyboard::key::Named::Control) => {
                     Some(Message::ToggleModal(true))
                 },
+                keyboard::Key::Named(keyboard::key::Named::Escape) => {
+                    Some(Message::DismissInfo)
+                },
+                keyboard::Key::Named(keyboard::key::Named::Tab) => {
+                    match modifiers.shift() {
+                        true => Some(Message::Outdent),
+                        false => Some(Message::Indent)
+                    }
+                },
+                keyboard::Key::Named(named) if modifiers.alt() && modifiers.shift() => {
+                    match named {
+                        keyboard::key::Named::ArrowUp => Some(Message::ExtendBlock(Motion::Up)),
+                        keyboard::key::Named::ArrowDown => Some(Message::ExtendBlock(Motion::Down)),
+                        keyboard::key::Named::ArrowLeft => Some(Message::ExtendBlock(Motion::Left)),
+                        keyboard::key::Named::ArrowRight => Some(Message::ExtendBlock(Motion::Right)),
+                        keyboard::key::Named::Home => Some(Message::ExtendBlock(Motion::Home)),
+                        keyboard::key::Named::End => Some(Message::ExtendBlock(Motion::End)),
+                        _ => None
+                    }
+                },
+                keyboard::Key::Named(keyboard::key::Named::ArrowUp) if modifiers.alt() => {
+                    Some(Message::Lines(LineOperation::MoveUp))
+                },
+                keyboard::Key::Named(keyboard::key::Named::ArrowDown) if modifiers.alt() => {
+                    Some(Message::Lines(LineOperation::MoveDown))
+                },
                 _ => None
             })
-        }
+        };
+
+        let language_server = self.path.as_deref().and_then(|path| {
+            let command = self.config.for_file(Some(path)).language_server?;
+
+            match command.is_empty() {
+                true => None,
+                false => Some(lsp::connect(command, lsp::workspace_root(path)).map(Message::Lsp))
+            }
+        });
+
+        let terminal = self.terminal.subscription().map(Message::Terminal);
+
+        Subscription::batch([window_events, keys, terminal].into_iter().chain(language_server))
     }
 
     fn update(&mut self, message: Self::Message) -> Command<Message> {
+        // Keys pressed while the palette is up are the palette's, they pick an entry on release.
+        if let Message::KeyPressed(..) | Message::ModifiersChanged(_) = message {
+            if self.show_control_pallet {
+                if let Message::ModifiersChanged(modifiers) = message {
+                    self.modifiers = modifiers;
+                }
+
+                return Command::none();
+            }
+        }
+
         if self.show_control_pallet == true {
             self.show_control_pallet = false;
             return self.update(message)
         }
 
+        if let Some(steps) = &mut self.recording {
+            let step = match message {
+                Message::SelectAll => Some(Step::SelectAll),
+                Message::Indent => Some(Step::Indent),
+                Message::Outdent => Some(Step::Outdent),
+                Message::Undo => Some(Step::Undo),
+                Message::Redo => Some(Step::Redo),
+                Message::AddCursorAbove => Some(Step::AddCursorAbove),
+                Message::AddCursorBelow => Some(Step::AddCursorBelow),
+                Message::SelectNextOccurrence => Some(Step::SelectNextOccurrence),
+                Message::SplitSelection => Some(Step::SplitSelection),
+                Message::Lines(operation) => Some(Step::Lines(operation)),
+                Message::ToggleComment => Some(Step::ToggleComment),
+                Message::ToggleBlockComment => Some(Step::ToggleBlockComment),
+                Message::JumpToBracket => Some(Step::JumpToBracket),
+                Message::ToggleFoldAtCursor => Some(Step::ToggleFold),
+                Message::FoldAll => Some(Step::FoldAll),
+                Message::UnfoldAll => Some(Step::UnfoldAll),
+                _ => None
+            };
+
+            steps.extend(step);
+        }
+
         match message {
             Message::Edit(action) => {
-                match action {
-                    Action::Edit(_) => {
-                        self.saved = false;
-                    },
-                    _ => {}
+                // Clicking the editor takes the keyboard back from the terminal, until then the keys are the shell's.
+                if self.terminal.has_keys() {
+                    match action {
+                        Action::Click(_) => self.terminal.focus(false),
+                        _ => return Command::none()
+                    }
                 }
 
-                self.content.perform(action);
-                Command::none()
+                // The symbol picker's filter or the commit message has the keyboard, the editor behind it still gets the keys too.
+                if self.show_symbols || self.source_control.is_shown() {
+                    return Command::none();
+                }
+
+                // With Vim or Emacs keys the keyboard goes through their layer instead,
+                // only pasting is let through while Vim inserts.
+                if self.config.keymap != Keymap::Default {
+                    match &action {
+                        Action::Edit(Edit::Paste(_)) if self.config.keymap == Keymap::Vim && self.vim.mode() == vim::Mode::Insert => {},
+                        Action::Edit(_) | Action::Move(_) | Action::Select(_) => return Command::none(),
+                        _ => {}
+                    }
+                }
+
+                if let Some(steps) = &mut self.recording {
+                    steps.extend(Step::from_action(&action));
+                }
+
+                self.edit(action)
             },
             Message::Open => {
-                Command::perform(pick_file(),Message::FileOpened)
+                match self.can_leave_document() {
+                    true => Command::perform(pick_file(),Message::FileOpened),
+                    false => {
+                        self.error = Some(GFEError::UnsavedChanges);
+                        Command::none()
+                    }
+                }
             },
             Message::Save => {
-                if self.path.is_some() {
-                    return Command::perform(save_file(self.path.clone().unwrap(), self.content.text()), Message::FileSaved);
+                if self.path.is_none() {
+                    return Command::none();
                 }
 
-                Command::none()
+                self.error = None;
+
+                // Files on disk end with the line break cosmic-text hides from us.
+                let text = self.document_text() + "\n";
+
+                match self.save_pipeline().formatter {
+                    Some(command) => Command::perform(transforms::format(command, text), Message::Formatted),
+                    None => self.write_file(text)
+                }
+            },
+            Message::Formatted(result) => {
+                match result {
+                    Ok(text) => self.write_file(text),
+                    Err(error) => {
+                        self.error = Some(error);
+                        self.write_file(self.document_text() + "\n")
+                    }
+                }
             },
             Message::FileSaved(result) => {
+                let written = result.is_ok();
+                let quit = std::mem::take(&mut self.quit_after_save) && written;
+
                 match result {
                     Err(error) => {
                         self.error = Some(error)
@@ -158,97 +737,2375 @@ impl Application for Editor {
 
                 self.saved = true;
 
-                Command::none()
+                // What was just written is what the buffer is compared with now.
+                if self.comparison.is_some() && written {
+                    let text = self.document_text();
+                    self.comparison = Some(Comparison::new(text.clone() + "\n", text));
+                }
+
+                self.lsp.send(self.path.as_deref(), |uri| lsp::Request::DidSave { uri, text: self.document_text() + "\n" });
+
+                // Edits may have moved the folds since they were last remembered.
+                let remember = self.remember_folds();
+
+                match quit {
+                    true => Command::batch([remember, window::close(window::Id::MAIN)]),
+                    false => Command::batch([remember, self.track_file(), self.refresh_changes()])
+                }
             }
+            Message::CompareWithSaved => {
+                let Some(path) = self.path.clone() else {
+                    return Command::none();
+                };
+
+                Command::perform(load_file(path), |result| Message::SavedLoaded(result.map(|(_, text, _)| text)))
+            },
+            Message::SavedLoaded(result) => {
+                match result {
+                    Ok(text) => self.comparison = Some(Comparison::new(text.to_string(), self.document_text())),
+                    Err(error) => self.error = Some(error)
+                }
+
+                Command::none()
+            },
+            Message::ToggleDiffLayout => {
+                self.side_by_side_diff = !self.side_by_side_diff;
+                Command::none()
+            },
+            Message::RevertHunk(index) => {
+                let Some(hunk) = self.comparison.as_ref().and_then(|comparison| comparison.hunks.get(index).cloned()) else {
+                    return Command::none();
+                };
+
+                // Hunks are in lines of the document, every one of them has to be in the buffer.
+                self.folds.unfold_all(&mut self.content);
+
+                let edit = self.comparison.as_ref()
+                    .map(|comparison| diff::revert(&self.content, &comparison.saved_lines(), &hunk));
+
+                self.apply_line_edit(edit)
+            },
+            Message::GitLoaded(result) => {
+                match result {
+                    Ok(tracked) => {
+                        self.tracked = tracked;
+                        self.refresh_git_hunks();
+                    },
+                    Err(error) => self.error = Some(error)
+                }
+
+                Command::none()
+            },
+            Message::RevertChange => {
+                let line = self.folds.document_line(buffer::cursor(&self.content).line);
+
+                let (Some(index), Some(head)) = (
+                    diff::hunk_at(&self.git_hunks, line, self.document_line_count()),
+                    self.tracked.as_ref().and_then(|tracked| tracked.head.clone())
+                ) else {
+                    return Command::none();
+                };
+
+                let hunk = self.git_hunks[index].clone();
+
+                // Hunks are in lines of the document, every one of them has to be in the buffer.
+                self.folds.unfold_all(&mut self.content);
+
+                let edit = diff::revert(&self.content, &diff::lines(&head), &hunk);
+                self.apply_line_edit(Some(edit))
+            },
+            Message::ToggleSourceControl => {
+                let focus = self.source_control.toggle();
+
+                Command::batch([self.refresh_changes(), focus])
+            },
+            Message::ToggleTerminal => {
+                self.terminal.toggle(|| match &self.path {
+                    Some(path) => lsp::workspace_root(path),
+                    None => env::current_dir().unwrap_or_default()
+                });
+
+                self.resize_viewport();
+                Command::none()
+            },
+            Message::Terminal(event) => {
+                let shown = self.terminal.is_shown();
+
+                if let Some(message) = self.terminal.event(event) {
+                    self.error = Some(GFEError::Terminal(message));
+                }
+
+                // The shell exited and took the panel with it.
+                if shown != self.terminal.is_shown() {
+                    self.resize_viewport();
+                }
+
+                Command::none()
+            },
+            Message::FocusTerminal => {
+                self.terminal.focus(true);
+                Command::none()
+            },
+            Message::ScrollTerminal(lines) => {
+                self.terminal.scroll(lines);
+                Command::none()
+            },
+            Message::SelectInTerminal(selection) => {
+                self.terminal.select(selection);
+                Command::none()
+            },
+            Message::TerminalPasted(text) => {
+                if let Some(text) = text {
+                    self.terminal.paste(&text);
+                }
+
+                Command::none()
+            },
+            Message::ChangesLoaded(result) => {
+                match result {
+                    Ok(changes) => self.source_control.loaded(changes),
+                    Err(error) => self.error = Some(error)
+                }
+
+                Command::none()
+            },
+            Message::SourceControl(action) => {
+                let done = match action {
+                    git::Action::Commit => Message::Committed,
+                    _ => Message::GitDone
+                };
+
+                match self.source_control.update(action, done) {
+                    Ok(command) => command,
+                    Err(error) => {
+                        self.error = Some(error);
+                        Command::none()
+                    }
+                }
+            },
+            Message::Committed(result) => {
+                if result.is_ok() {
+                    self.source_control.committed();
+                }
+
+                self.update(Message::GitDone(result))
+            },
+            Message::GitDone(result) => {
+                match result {
+                    Ok(()) => self.error = None,
+                    Err(error) => self.error = Some(error)
+                }
+
+                // The index, or the last commit, changed under the panel and the gutter.
+                Command::batch([self.refresh_changes(), self.track_file()])
+            },
             Message::FileOpened(result) => {
                 match result {
-                    Ok((path, content)) => {
-                        self.saved = true;
-                        self.path = Some(path);
-                        self.content = Content::with_text(&content);
+                    Ok((path, content, format)) => {
+                        self.error = None;
+
+                        match self.panes.take_document(&Some(path.clone())) {
+                            // Another pane shows the file already, this one edits the same text
+                            // instead of what's on disk.
+                            Some(document) => {
+                                let text = self.panes.text(&document.path).unwrap_or_default();
+
+                                self.park_document();
+
+                                self.content = Content::new();
+                                buffer::set_text(&mut self.content, &text);
+                                self.viewport = Viewport::new();
+                                self.restore_document(document);
+                            },
+                            None => {
+                                match self.path.as_ref() == Some(&path) {
+                                    // Opened again, the panes showing it follow what's on disk now.
+                                    true => self.lsp.send(self.path.as_deref(), |uri| lsp::Request::DidClose { uri }),
+                                    false => self.park_document()
+                                }
+
+                                let indentation = format.editorconfig.indentation(
+                                    Indentation::detect(&content).unwrap_or_default()
+                                );
+
+                                self.content = Content::with_text(&content);
+                                self.viewport = Viewport::new();
+                                self.restore_document(Document {
+                                    path: Some(path),
+                                    format,
+                                    saved: true,
+                                    indentation,
+                                    history: History::default(),
+                                    folds: Folds::default(),
+                                    version: 0,
+                                    diagnostics: Vec::new(),
+                                });
+                                self.open_document();
+
+                                if let Some(folds) = self.path.as_deref().and_then(|path| self.session.folds(path)) {
+                                    self.folds.restore(&mut self.content, folds);
+                                }
+                            }
+                        }
+
+                        if let Some(position) = self.pending_jump.take() {
+                            let position = self.reveal_document_position(position);
+
+                            buffer::move_to(&mut self.content, position);
+                            self.viewport.reveal_cursor(&self.content);
+                        }
+
+                        self.sync_panes();
                     }
                     Err(error) => {
                         self.error = Some(error)
                     }
                 }
 
-                Command::none()
+                self.track_file()
             },
             Message::SelectAll => {
                 self.content.perform(Action::Move(widget::text_editor::Motion::DocumentStart));
                 self.content.perform(Action::Select(widget::text_editor::Motion::DocumentEnd));
+                self.viewport.reveal_cursor(&self.content);
                 println!("HOW!!!");
                 Command::none()
             },
-            Message::ToggleModal(value) => {
-                self.show_control_pallet = value;
+            Message::Undo => {
+                if self.history.undo(&mut self.content, &mut self.folds) {
+                    self.snippet = None;
+                    self.cursors.clear();
+                    self.block = None;
+                    self.mark_changed();
+                    self.viewport.reveal_cursor(&self.content);
+                }
+
                 Command::none()
-            }
-        }
-    }
+            },
+            Message::Redo => {
+                if self.history.redo(&mut self.content, &mut self.folds) {
+                    self.snippet = None;
+                    self.cursors.clear();
+                    self.block = None;
+                    self.mark_changed();
+                    self.viewport.reveal_cursor(&self.content);
+                }
 
-    fn view(&self) -> iced::Element<'_, Self::Message> {
-        let input_box = widget::text_editor(&self.content)
-            .on_action(Message::Edit)
-            .style(theme::TextEditor::Custom(Box::new( TextBoxStyle { theme: self.theme() } )))
-            .font(Font::MONOSPACE)
-            .height(Length::Fill);
+                Command::none()
+            },
+            Message::Indent => {
+                if self.config.keymap == Keymap::Vim && self.vim.mode() != vim::Mode::Insert {
+                    return Command::none();
+                }
 
-        let unsaved_indictor = match self.saved {
-            true => widget::container(circle(0.0)),
-            false => widget::container(circle(10.0)).padding([0, 6, 0, 0])
-        };
+                if let Some(completion) = self.completion.as_ref().filter(|completion| !completion.is_empty()) {
+                    let selected = completion.selected();
+                    return self.update(Message::AcceptCompletion(selected));
+                }
 
-        let path_indictor = match &self.path {
-            Some(value) => {
-                widget::text(value.to_str().unwrap())
-            },
-            None => {
-                widget::text("")
-            }
-        };
+                if let Some(mut session) = self.snippet.take() {
+                    if session.has_mirrors() {
+                        self.history.record(&self.content, &self.folds, None);
+                        self.mark_changed();
+                    }
 
-        let cursor_position = {
-            let (line, column) = self.content.cursor_position();
+                    if session.next(&mut self.content) {
+                        self.snippet = Some(session);
+                    }
 
-            widget::text(format!("{}:{}", line + 1, column + 1)).horizontal_alignment(Horizontal::Left)
-        };
+                    self.viewport.reveal_cursor(&self.content);
+                    return self.reveal_cursor_horizontally();
+                }
 
-        let bottom_panel = widget::row![
-            unsaved_indictor, 
-            path_indictor, 
-            widget::horizontal_space(), 
-            cursor_position
-        ].padding([0, 5]);
+                if !self.cursors.is_empty() {
+                    self.history.record(&self.content, &self.folds, None);
+                    self.cursors.perform(&mut self.content, &Action::Edit(Edit::Paste(Arc::new(self.indentation.unit()))));
 
-        let content = widget::container(
-            widget::column![input_box, bottom_panel].spacing(10)
-        )
-        .padding(15);
+                    self.mark_changed();
+                    self.viewport.reveal_cursor(&self.content);
+                    return self.reveal_cursor_horizontally();
+                }
 
-        let modal = widget::container(
-            widget::row![
-                widget::column![
-                    widget::button("Open").padding([5, 10]).on_press(Message::Open),
-                    widget::text("(CTRL + O)").size(12)
-                ].spacing(5).align_items(Alignment::Center),
-                widget::column![
-                    widget::button("Save").padding([5, 10]).on_press(Message::Save),
-                    widget::text("(CTRL + S)").size(12)
-                ].spacing(5).align_items(Alignment::Center),
-            ].spacing(10)
-        ).padding(10);
+                let (start, prefix) = completion::prefix(&self.content);
 
-        if self.show_control_pallet {
-            Modal::new(content, modal).into()
-        } else {
-            content.into()
-        }
+                if let Some(snippet) = self.snippets.find(self.path.as_deref(), &prefix) {
+                    let body = snippet.body.clone();
+                    let cursor = buffer::cursor(&self.content);
 
-    }
+                    return self.insert_snippet(start, cursor, &body);
+                }
 
-    fn theme(&self) -> Theme {
-        Theme::Dark
-    }
+                self.history.record(&self.content, &self.folds, None);
+
+                match buffer::selection(&self.content) {
+                    Some((start, end)) if start.line != end.line => {
+                        indent::indent_lines(&mut self.content, &self.indentation, start, end);
+                    },
+                    _ => {
+                        let unit = self.indentation.unit_at(buffer::cursor(&self.content).column);
+
+                        self.content.perform(Action::Edit(Edit::Paste(Arc::new(unit))));
+                    }
+                }
+
+                self.mark_changed();
+                self.viewport.reveal_cursor(&self.content);
+                self.reveal_cursor_horizontally()
+            },
+            Message::Outdent => {
+                if self.config.keymap == Keymap::Vim && self.vim.mode() != vim::Mode::Insert {
+                    return Command::none();
+                }
+
+                if let Some(session) = &mut self.snippet {
+                    if session.has_mirrors() {
+                        self.history.record(&self.content, &self.folds, None);
+                    }
+
+                    session.previous(&mut self.content);
+
+                    self.mark_changed();
+                    self.viewport.reveal_cursor(&self.content);
+                    return self.reveal_cursor_horizontally();
+                }
+
+                self.cursors.clear();
+
+                self.block = None;
+
+                let cursor = buffer::cursor(&self.content);
+                let (start, end) = buffer::selection(&self.content).unwrap_or((cursor, cursor));
+
+                self.history.record(&self.content, &self.folds, None);
+                indent::outdent_lines(&mut self.content, &self.indentation, start, end);
+
+                self.mark_changed();
+                self.viewport.reveal_cursor(&self.content);
+                self.reveal_cursor_horizontally()
+            },
+            Message::IndentationChanged(indentation) => {
+                self.indentation = indentation;
+                Command::none()
+            },
+            Message::ToggleModal(value) => {
+                // Emacs needs Ctrl for itself, it opens the palette with M-x instead.
+                if value && self.config.keymap == Keymap::Emacs {
+                    return Command::none();
+                }
+
+                self.show_control_pallet = value;
+                Command::none()
+            },
+            Message::ToggleRelativeNumbers => {
+                self.relative_numbers = !self.relative_numbers;
+                Command::none()
+            },
+            Message::CycleWrap => {
+                let wrap = self.viewport.wrap().next();
+
+                self.config.for_file_mut(self.path.as_deref()).wrap = Some(wrap);
+                self.apply_file_config();
+
+                Command::perform(save_config(self.config.clone()), Message::ConfigSaved)
+            },
+            Message::ToggleRuler => {
+                let ruler = match self.ruler {
+                    Some(_) => 0,
+                    None => self.viewport.wrap_column()
+                };
+
+                self.config.for_file_mut(self.path.as_deref()).ruler = Some(ruler);
+                self.apply_file_config();
+
+                Command::perform(save_config(self.config.clone()), Message::ConfigSaved)
+            },
+            Message::WindowResized(size) => {
+                self.window_size = size;
+                self.resize_viewport();
+                Command::none()
+            },
+            Message::EditorScrolled(pane, viewport) => {
+                match self.panes.other_mut(pane) {
+                    Some(state) => state.horizontal_offset = viewport.absolute_offset().x,
+                    None => self.horizontal_offset = viewport.absolute_offset().x
+                }
+
+                Command::none()
+            },
+            Message::ToggleMinimap => {
+                self.show_minimap = !self.show_minimap;
+                self.resize_viewport();
+                Command::none()
+            },
+            Message::ScrollTo(pane, line) => {
+                if let Some(state) = self.panes.other_mut(pane) {
+                    state.scroll_to(line);
+                    return Command::none();
+                }
+
+                match self.viewport.rows_to(&self.content, line) {
+                    0 => Command::none(),
+                    lines => self.edit(Action::Scroll { lines: lines as i32 })
+                }
+            },
+            Message::SplitPane(axis) => {
+                let command = match self.panes.split(axis, self.path.clone(), &self.content) {
+                    Some(pane) => self.focus_pane(pane),
+                    None => Command::none()
+                };
+
+                self.resize_viewport();
+                command
+            },
+            Message::ClosePane => {
+                if !self.panes.is_split() {
+                    return Command::none();
+                }
+
+                // The file goes away with the last pane showing it.
+                if !self.can_leave_document() {
+                    self.error = Some(GFEError::UnsavedChanges);
+                    return Command::none();
+                }
+
+                let command = match self.panes.close(&mut self.content, &mut self.viewport, &mut self.horizontal_offset) {
+                    Some(path) => {
+                        self.leave_pane();
+                        self.switch_document(path)
+                    },
+                    None => Command::none()
+                };
+
+                self.resize_viewport();
+                command
+            },
+            Message::FocusPane(pane) => {
+                self.terminal.focus(false);
+                self.focus_pane(pane)
+            },
+            Message::FocusAdjacentPane(direction) => {
+                match self.panes.adjacent(direction) {
+                    Some(pane) => self.focus_pane(pane),
+                    None => Command::none()
+                }
+            },
+            Message::PaneResized(pane_grid::ResizeEvent { split, ratio }) => {
+                self.panes.resize(split, ratio);
+                self.resize_viewport();
+                Command::none()
+            },
+            Message::PaneAction(pane, action) => {
+                match action {
+                    // Scrolling a pane doesn't take the focus.
+                    Action::Scroll { .. } => {
+                        if let Some(state) = self.panes.other_mut(pane) {
+                            state.content.perform(action.clone());
+                            state.viewport.follow(&action, &state.content);
+                        }
+
+                        Command::none()
+                    },
+                    _ => {
+                        let focused = self.focus_pane(pane);
+
+                        Command::batch([focused, self.update(Message::Edit(action))])
+                    }
+                }
+            },
+            Message::ConfigLoaded(result) => {
+                match result {
+                    Ok(config) => {
+                        self.config = config;
+                        self.apply_file_config();
+                    },
+                    Err(error) => {
+                        self.error = Some(error)
+                    }
+                }
+
+                Command::none()
+            },
+            Message::ConfigSaved(result) => {
+                if let Err(error) = result {
+                    self.error = Some(error)
+                }
+
+                Command::none()
+            },
+            Message::Lsp(event) => {
+                match event {
+                    lsp::Event::Connected(handle) => {
+                        self.lsp.connected(handle);
+                        self.open_document();
+                        self.refresh_symbols();
+                    },
+                    lsp::Event::Initialized => {},
+                    lsp::Event::Diagnostics { uri, diagnostics } => {
+                        if Some(&uri) == lsp::uri(self.path.as_deref()).as_ref() {
+                            self.published_diagnostics = diagnostics;
+                            self.refresh_diagnostics();
+                        } else if let Some(document) = self.panes.documents_mut().find(|document| {
+                            lsp::uri(document.path.as_deref()).as_ref() == Some(&uri)
+                        }) {
+                            // Kept for when a pane showing the file gets the focus.
+                            document.diagnostics = diagnostics;
+                        }
+                    },
+                    lsp::Event::Hover(text) => {
+                        self.hover = text.filter(|text| !text.trim().is_empty());
+                    },
+                    lsp::Event::Definition(location) => {
+                        let Some(location) = location else {
+                            return Command::none();
+                        };
+
+                        if Some(&location.uri) == lsp::uri(self.path.as_deref()).as_ref() {
+                            let position = self.reveal_document_position(location.range.start);
+
+                            buffer::move_to(&mut self.content, position);
+                            self.viewport.reveal_cursor(&self.content);
+
+                            return self.reveal_cursor_horizontally();
+                        }
+
+                        if let Ok(path) = location.uri.to_file_path() {
+                            // Only jumped to once the file is loaded, which unsaved changes prevent.
+                            if self.can_leave_document() {
+                                self.pending_jump = Some(location.range.start);
+                            }
+
+                            return self.open_file(path);
+                        }
+                    },
+                    lsp::Event::Completion(items) => {
+                        if let Some(completion) = &mut self.completion {
+                            completion.extend(items.into_iter().map(completion::Item::from_lsp).collect());
+                        }
+                    },
+                    lsp::Event::Error(message) => {
+                        self.error = Some(GFEError::LanguageServer(message));
+                    },
+                    lsp::Event::Symbols { uri, symbols } => {
+                        if let (Some(symbols), true) = (symbols, Some(uri) == lsp::uri(self.path.as_deref())) {
+                            self.symbols = outline::from_lsp(symbols, &self.document_text());
+                            self.symbols_from_server = true;
+                        }
+                    },
+                    lsp::Event::Disconnected => {
+                        self.lsp.disconnected();
+                        self.symbols_from_server = false;
+                        self.refresh_symbols();
+                    }
+                }
+
+                Command::none()
+            },
+            Message::Hover => {
+                self.lsp.send(self.path.as_deref(), |uri| lsp::Request::Hover { uri, position: self.lsp_cursor() });
+
+                Command::none()
+            },
+            Message::GoToDefinition => {
+                self.lsp.send(self.path.as_deref(), |uri| lsp::Request::Definition { uri, position: self.lsp_cursor() });
+
+                Command::none()
+            },
+            Message::Complete => {
+                let (start, prefix) = completion::prefix(&self.content);
+
+                self.open_completion(start, prefix);
+
+                Command::none()
+            },
+            Message::AcceptCompletion(index) => {
+                let Some(completion) = self.completion.take() else {
+                    return Command::none();
+                };
+
+                let Some(item) = completion.get(index) else {
+                    return Command::none();
+                };
+
+                let cursor = buffer::cursor(&self.content);
+
+                if item.source == completion::Source::Snippet {
+                    return self.insert_snippet(completion.start, cursor, &item.insert);
+                }
+
+                self.history.record(&self.content, &self.folds, None);
+                buffer::replace(&mut self.content, completion.start, cursor, &item.insert);
+
+                self.mark_changed();
+                self.viewport.reveal_cursor(&self.content);
+                self.reveal_cursor_horizontally()
+            },
+            Message::DismissInfo => {
+                self.hover = None;
+                self.completion = None;
+                self.snippet = None;
+                self.show_snippets = false;
+                self.show_line_operations = false;
+                self.show_macros = false;
+                self.show_symbols = false;
+                self.comparison = None;
+                self.source_control.hide();
+                self.cursors.clear();
+                self.block = None;
+                Command::none()
+            },
+            Message::ToggleProblems => {
+                self.show_problems = !self.show_problems;
+                self.resize_viewport();
+                Command::none()
+            },
+            Message::NextProblem => {
+                match self.diagnostics.next(buffer::cursor(&self.content)) {
+                    Some(diagnostic) => self.update(Message::JumpTo(diagnostic.start)),
+                    None => Command::none()
+                }
+            },
+            Message::PreviousProblem => {
+                match self.diagnostics.previous(buffer::cursor(&self.content)) {
+                    Some(diagnostic) => self.update(Message::JumpTo(diagnostic.start)),
+                    None => Command::none()
+                }
+            },
+            Message::SnippetsLoaded(result) => {
+                match result {
+                    Ok(snippets) => self.snippets = snippets,
+                    Err(error) => self.error = Some(error)
+                }
+
+                Command::none()
+            },
+            Message::ShowSnippets => {
+                self.show_snippets = true;
+                Command::none()
+            },
+            Message::InsertSnippet(index) => {
+                self.show_snippets = false;
+                self.folds.unfold_selection(&mut self.content, 0);
+
+                let Some(body) = self.snippets.for_file(self.path.as_deref()).get(index).map(|snippet| snippet.body.clone()) else {
+                    return Command::none();
+                };
+
+                let (from, to) = buffer::selection(&self.content).unwrap_or_else(|| {
+                    let cursor = buffer::cursor(&self.content);
+                    (cursor, cursor)
+                });
+
+                self.insert_snippet(from, to, &body)
+            },
+            Message::ToggleOutline => {
+                self.show_outline = !self.show_outline;
+                self.refresh_symbols();
+                self.resize_viewport();
+                Command::none()
+            },
+            Message::ShowSymbols => {
+                self.show_symbols = true;
+                self.symbol_query.clear();
+                self.refresh_symbols();
+
+                widget::text_input::focus(symbol_input())
+            },
+            Message::SymbolQueryChanged(query) => {
+                self.symbol_query = query;
+                Command::none()
+            },
+            Message::SubmitSymbolQuery => {
+                match outline::matches(&self.symbols, &self.symbol_query).first() {
+                    Some(symbol) => self.update(Message::GoToSymbol(symbol.line, symbol.column)),
+                    None => Command::none()
+                }
+            },
+            Message::GoToSymbol(line, column) => {
+                self.show_symbols = false;
+
+                let unfolded = self.change_folds(|folds, content| {
+                    folds.reveal(content, line);
+                });
+
+                let (line, _) = self.folds.buffer_line(line);
+                let jump = self.update(Message::JumpTo(buffer::Position::new(line, column)));
+
+                Command::batch([unfolded, jump])
+            },
+            Message::ShowLineOperations => {
+                self.show_line_operations = true;
+                Command::none()
+            },
+            Message::Lines(operation) => {
+                self.show_line_operations = false;
+
+                // Line operations work on what's in the buffer, open the folds they'd move around.
+                self.folds.unfold_selection(&mut self.content, 1);
+
+                let edit = lines::plan(&self.content, operation);
+                self.apply_line_edit(edit)
+            },
+            Message::ToggleComment => {
+                self.folds.unfold_selection(&mut self.content, 0);
+
+                let (cursor, selection) = (buffer::cursor(&self.content), buffer::selection(&self.content));
+
+                let edit = comments::tokens(self.path.as_deref())
+                    .and_then(|tokens| comments::toggle_line(&self.content, cursor, selection, tokens));
+
+                self.apply_line_edit(edit)
+            },
+            Message::ToggleBlockComment => {
+                self.folds.unfold_selection(&mut self.content, 0);
+
+                let (cursor, selection) = (buffer::cursor(&self.content), buffer::selection(&self.content));
+
+                let edit = comments::tokens(self.path.as_deref())
+                    .and_then(|tokens| comments::toggle_block(&self.content, cursor, selection, tokens));
+
+                self.apply_line_edit(edit)
+            },
+            Message::JumpToBracket => {
+                match brackets::matching(&self.content, buffer::cursor(&self.content)) {
+                    Some((_, other)) => self.update(Message::JumpTo(other)),
+                    None => Command::none()
+                }
+            },
+            Message::AddCursorAbove => {
+                self.cursors.add_vertical(&self.content, true);
+                Command::none()
+            },
+            Message::AddCursorBelow => {
+                self.cursors.add_vertical(&self.content, false);
+                Command::none()
+            },
+            Message::SelectNextOccurrence => {
+                self.history.break_group();
+                self.cursors.select_next_occurrence(&mut self.content);
+                self.viewport.reveal_cursor(&self.content);
+                self.reveal_cursor_horizontally()
+            },
+            Message::SplitSelection => {
+                self.history.break_group();
+                self.cursors.split_lines(&mut self.content);
+                self.viewport.reveal_cursor(&self.content);
+                self.reveal_cursor_horizontally()
+            },
+            Message::ExtendBlock(motion) => {
+                let mut block = self.block.unwrap_or_else(|| {
+                    let (line, column) = self.content.cursor_position();
+                    let line_text = self.content.line(line).map(|line| line.to_string()).unwrap_or_default();
+
+                    Block::new(buffer::Position::new(line, line_text[..column].chars().count()))
+                });
+
+                block.extend(motion, &self.content);
+
+                self.history.break_group();
+                self.snippet = None;
+                self.completion = None;
+                self.block = Some(block);
+
+                self.apply_block()
+            },
+            Message::Copy => {
+                // The `text_editor` only copies its own selection, put every cursor's on the clipboard.
+                match self.cursors.is_empty() {
+                    true => Command::none(),
+                    false => iced::clipboard::write(self.cursors.selected_text(&self.content))
+                }
+            },
+            Message::KeyPressed(key, modifiers) => {
+                if self.terminal.has_keys() {
+                    return self.terminal.key(key, modifiers, Message::TerminalPasted);
+                }
+
+                if self.show_snippets || self.show_line_operations || self.show_macros || self.show_symbols || self.source_control.is_shown() {
+                    return Command::none();
+                }
+
+                let Some(step) = self.config.keymap.step(key.as_ref(), modifiers) else {
+                    return Command::none();
+                };
+
+                if let Some(steps) = &mut self.recording {
+                    steps.push(step.clone());
+                }
+
+                self.play_step(step)
+            },
+            Message::CycleKeymap => {
+                self.config.keymap = self.config.keymap.next();
+                self.vim = Vim::default();
+                self.emacs = Emacs::default();
+
+                Command::perform(save_config(self.config.clone()), Message::ConfigSaved)
+            },
+            Message::ToggleRecording => {
+                match self.recording.take() {
+                    Some(steps) => self.last_macro = steps,
+                    None => self.recording = Some(Vec::new())
+                }
+
+                Command::none()
+            },
+            Message::RunMacro(name, times) => {
+                let steps = match name {
+                    Some(name) => self.macros.macros.get(&name).cloned().unwrap_or_default(),
+                    None => self.last_macro.clone()
+                };
+
+                self.show_macros = false;
+
+                // A macro can't run while one is recorded, it would end up inside it.
+                match steps.is_empty() || self.recording.is_some() {
+                    true => Command::none(),
+                    false => self.run_macro(&steps, times)
+                }
+            },
+            Message::ShowMacros => {
+                self.show_macros = true;
+                Command::none()
+            },
+            Message::MacroCountChanged(count) => {
+                if count.chars().all(|character| character.is_ascii_digit()) {
+                    self.macro_count = count;
+                }
+
+                Command::none()
+            },
+            Message::MacroNameChanged(name) => {
+                self.macro_name = name;
+                Command::none()
+            },
+            Message::SaveMacro => {
+                let name = self.macro_name.trim().to_owned();
+
+                if name.is_empty() || self.last_macro.is_empty() {
+                    return Command::none();
+                }
+
+                self.macros.macros.insert(name, self.last_macro.clone());
+                self.macro_name.clear();
+
+                Command::perform(save_macros(self.macros.clone()), Message::MacrosSaved)
+            },
+            Message::DeleteMacro(name) => {
+                self.macros.macros.remove(&name);
+
+                Command::perform(save_macros(self.macros.clone()), Message::MacrosSaved)
+            },
+            Message::MacrosLoaded(result) => {
+                match result {
+                    Ok(macros) => self.macros = macros,
+                    Err(error) => self.error = Some(error)
+                }
+
+                Command::none()
+            },
+            Message::MacrosSaved(result) => {
+                if let Err(error) = result {
+                    self.error = Some(error);
+                }
+
+                Command::none()
+            },
+            Message::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers;
+                Command::none()
+            },
+            Message::JumpTo(position) => {
+                self.history.break_group();
+                buffer::move_to(&mut self.content, position);
+                self.viewport.reveal_cursor(&self.content);
+                self.reveal_cursor_horizontally()
+            },
+            Message::ToggleFold(line) => {
+                self.change_folds(|folds, content| {
+                    folds.toggle(content, line);
+                })
+            },
+            Message::ToggleFoldAtCursor => {
+                match self.folds.toggle_target(&self.content, buffer::cursor(&self.content).line) {
+                    Some(line) => self.update(Message::ToggleFold(line)),
+                    None => Command::none()
+                }
+            },
+            Message::FoldAll => {
+                self.change_folds(Folds::fold_all)
+            },
+            Message::UnfoldAll => {
+                self.change_folds(Folds::unfold_all)
+            },
+            Message::SessionLoaded(result) => {
+                match result {
+                    Ok(session) => self.session = session,
+                    Err(error) => self.error = Some(error)
+                }
+
+                // A file opened from the command line may have beaten the session to it.
+                let folds = self.path.as_deref().and_then(|path| self.session.folds(path));
+
+                if let (Some(folds), true) = (folds, self.folds.is_empty()) {
+                    self.folds.restore(&mut self.content, folds);
+                    self.refresh_diagnostics();
+                    self.sync_panes();
+                }
+
+                Command::none()
+            },
+            Message::SessionSaved(result) => {
+                if let Err(error) = result {
+                    self.error = Some(error);
+                }
+
+                Command::none()
+            }
+        }
+    }
+
+    fn view(&self) -> iced::Element<'_, Self::Message> {
+        let panes = self.panes.view(
+            |pane, state, focused, document| match focused {
+                true => self.editor_pane(pane, state.id, &self.content, &self.viewport, true, None),
+                false => self.editor_pane(pane, state.id, &state.content, &state.viewport, false, document)
+            },
+            Message::FocusPane,
+            Message::PaneResized
+        );
+
+        let mut editor_area = widget::row![panes];
+
+        if self.show_outline {
+            editor_area = editor_area.push(self.outline_panel());
+        }
+
+        // Clicking the unsaved indicator shows what changed.
+        let unsaved_indictor: iced::Element<'_, Self::Message> = match self.saved {
+            true => widget::container(circle(0.0)).into(),
+            false => widget::button(circle(10.0))
+                .style(theme::Button::Text)
+                .padding([0, 6, 0, 0])
+                .on_press(Message::CompareWithSaved)
+                .into()
+        };
+
+        let path_indictor = match &self.path {
+            Some(value) => {
+                widget::text(value.to_str().unwrap())
+            },
+            None => {
+                widget::text("")
+            }
+        };
+
+        let cursor_position = {
+            let (line, column) = self.content.cursor_position();
+            let line = self.folds.document_line(line);
+
+            let position = match self.cursors.count() {
+                1 => format!("{}:{}", line + 1, column + 1),
+                count => format!("{}:{} ({count} cursors)", line + 1, column + 1)
+            };
+
+            widget::text(position).horizontal_alignment(Horizontal::Left)
+        };
+
+        let recording_indicator = match &self.recording {
+            Some(steps) => widget::text(format!("Recording macro ({} steps)", steps.len()))
+                .style(theme::Text::Color(Color::from_rgb(1.0, 0.4, 0.4))),
+            None => widget::text("")
+        };
+
+        let branch_indicator = match &self.tracked {
+            Some(tracked) => widget::text(&tracked.branch).style(theme::Text::Color(Color { a: 0.6, ..Color::WHITE })),
+            None => widget::text("")
+        };
+
+        let keymap_indicator = match self.config.keymap {
+            Keymap::Default => widget::text(""),
+            Keymap::Vim => widget::text(self.vim.status()),
+            Keymap::Emacs => widget::text(self.emacs.status())
+        };
+
+        let indentation_indicator = widget::pick_list(
+            Indentation::ALL,
+            Some(self.indentation),
+            Message::IndentationChanged
+        )
+        .text_size(12)
+        .padding([0, 5]);
+
+        let error_indicator = match &self.error {
+            Some(GFEError::DialogClosed) | None => widget::text(""),
+            Some(error) => {
+                widget::text(error.to_string())
+                    .style(theme::Text::Color(Color::from_rgb(1.0, 0.4, 0.4)))
+            }
+        };
+
+        let diagnostics_indicator = {
+            let errors = self.diagnostics.count(Severity::Error);
+            let warnings = self.diagnostics.count(Severity::Warning);
+
+            widget::button(widget::text(format!("{errors} errors, {warnings} warnings")).size(12))
+                .style(theme::Button::Text)
+                .padding(0)
+                .on_press(Message::ToggleProblems)
+        };
+
+        let bottom_panel = widget::row![
+            unsaved_indictor, 
+            path_indictor, 
+            error_indicator,
+            diagnostics_indicator,
+            widget::horizontal_space(), 
+            recording_indicator,
+            keymap_indicator,
+            branch_indicator,
+            indentation_indicator,
+            cursor_position
+        ].spacing(10).padding([0, 5]);
+
+        let mut main_area = widget::column![editor_area];
+
+        if self.show_problems {
+            main_area = main_area.push(self.problems_panel());
+        }
+
+        if let Some(terminal) = self.terminal.view(Message::ToggleTerminal, Message::FocusTerminal, Message::ScrollTerminal, Message::SelectInTerminal) {
+            main_area = main_area.push(terminal);
+        }
+
+        let main_area = main_area.push(bottom_panel);
+
+        let content = widget::container(main_area.spacing(10)).padding(15);
+
+        let modal = widget::container(
+            widget::column![
+                widget::row![
+                    pallet_button("Open", "O", Message::Open),
+                    pallet_button("Save", "S", Message::Save),
+                    pallet_button("Undo", "Z", Message::Undo),
+                    pallet_button("Redo", "Y", Message::Redo),
+                ].spacing(10),
+                widget::row![
+                    pallet_button("Relative Lines", "L", Message::ToggleRelativeNumbers),
+                    pallet_button("Wrap", "W", Message::CycleWrap),
+                    pallet_button("Ruler", "R", Message::ToggleRuler),
+                    pallet_button("Minimap", "[", Message::ToggleMinimap),
+                ].spacing(10),
+                widget::row![
+                    pallet_button("Split Right", "\\", Message::SplitPane(pane_grid::Axis::Vertical)),
+                    pallet_button("Split Down", "`", Message::SplitPane(pane_grid::Axis::Horizontal)),
+                    pallet_button("Close Pane", "0", Message::ClosePane),
+                    pallet_button("Focus Pane", "Arrows", Message::FocusAdjacentPane(pane_grid::Direction::Right)),
+                    pallet_button("Compare with Saved", "1", Message::CompareWithSaved),
+                    pallet_button("Revert Change", "2", Message::RevertChange),
+                    pallet_button("Source Control", "3", Message::ToggleSourceControl),
+                ].spacing(10),
+                widget::row![
+                    pallet_button("Hover", "H", Message::Hover),
+                    pallet_button("Definition", "D", Message::GoToDefinition),
+                    pallet_button("Complete", "Space", Message::Complete),
+                    pallet_button("Terminal", "4", Message::ToggleTerminal),
+                ].spacing(10),
+                widget::row![
+                    pallet_button("Cursor Above", "K", Message::AddCursorAbove),
+                    pallet_button("Cursor Below", "J", Message::AddCursorBelow),
+                    pallet_button("Next Occurrence", "N", Message::SelectNextOccurrence),
+                    pallet_button("Split Lines", "I", Message::SplitSelection),
+                    pallet_button("Copy All", "C", Message::Copy),
+                ].spacing(10),
+                widget::row![
+                    pallet_button(&format!("Keys: {}", self.config.keymap.label()), "M", Message::CycleKeymap),
+                    pallet_button(match self.recording {
+                        Some(_) => "Stop Recording",
+                        None => "Record Macro"
+                    }, "Q", Message::ToggleRecording),
+                    pallet_button("Play Macro", "E", Message::RunMacro(None, Some(1))),
+                    pallet_button("Macros", "B", Message::ShowMacros),
+                ].spacing(10),
+                widget::row![
+                    pallet_button("Line Operations", "U", Message::ShowLineOperations),
+                    pallet_button("Comment", "/", Message::ToggleComment),
+                    pallet_button("Block Comment", "G", Message::ToggleBlockComment),
+                    pallet_button("Matching Bracket", "]", Message::JumpToBracket),
+                ].spacing(10),
+                widget::row![
+                    pallet_button("Toggle Fold", "F", Message::ToggleFoldAtCursor),
+                    pallet_button("Fold All", "-", Message::FoldAll),
+                    pallet_button("Unfold All", "=", Message::UnfoldAll),
+                    pallet_button("Outline", "'", Message::ToggleOutline),
+                    pallet_button("Go to Symbol", ";", Message::ShowSymbols),
+                ].spacing(10),
+                widget::row![
+                    pallet_button("Snippets", "T", Message::ShowSnippets),
+                    pallet_button("Problems", "P", Message::ToggleProblems),
+                    pallet_button("Previous Problem", ",", Message::PreviousProblem),
+                    pallet_button("Next Problem", ".", Message::NextProblem),
+                ].spacing(10),
+            ].spacing(10).align_items(Alignment::Center)
+        ).padding(10);
+
+        if self.show_control_pallet {
+            Modal::new(content, modal).into()
+        } else if self.show_snippets {
+            Modal::new(content, self.snippet_picker()).on_blur(Message::DismissInfo).into()
+        } else if self.show_line_operations {
+            Modal::new(content, self.line_operations()).on_blur(Message::DismissInfo).into()
+        } else if self.show_macros {
+            Modal::new(content, self.macros_panel()).on_blur(Message::DismissInfo).into()
+        } else if self.show_symbols {
+            Modal::new(content, self.symbol_picker()).on_blur(Message::DismissInfo).into()
+        } else if let Some(comparison) = &self.comparison {
+            let diff = diff::view(comparison, self.side_by_side_diff, Message::RevertHunk, Message::ToggleDiffLayout);
+
+            Modal::new(content, diff).on_blur(Message::DismissInfo).into()
+        } else if self.source_control.is_shown() {
+            let branch = self.tracked.as_ref().map(|tracked| tracked.branch.as_str()).unwrap_or_default();
+
+            Modal::new(content, self.source_control.view(branch, Message::SourceControl)).on_blur(Message::DismissInfo).into()
+        } else if let Some(info) = self.info_panel() {
+            Modal::new(content, info).on_blur(Message::DismissInfo).into()
+        } else if let Some((popup, anchor)) = self.completion_popup() {
+            Modal::new(content, popup).anchor(anchor).on_blur(Message::DismissInfo).into()
+        } else {
+            content.into()
+        }
+
+    }
+
+    fn theme(&self) -> Theme {
+        Theme::Dark
+    }
+
+}
+
+impl Editor {
+    /// The gutter, text and minimap of one pane, the focused one also shows
+    /// the extra cursors and matching brackets.
+    ///
+    /// `document` is the file the pane shows when it's another one than the
+    /// focused pane's, which has no diagnostics or changes to show.
+    fn editor_pane<'a>(&'a self, pane: pane_grid::Pane, id: usize, content: &'a Content, viewport: &'a Viewport, focused: bool, document: Option<&'a Document>) -> iced::Element<'a, Message> {
+        let input_box = widget::text_editor(content)
+            .on_action(move |action| match focused {
+                true => Message::Edit(action),
+                false => Message::PaneAction(pane, action)
+            })
+            .style(theme::TextEditor::Custom(Box::new( TextBoxStyle { theme: self.theme() } )))
+            .font(Font::MONOSPACE)
+            .height(Length::Fill);
+
+        let (cursor_line, _) = content.cursor_position();
+        let visible_lines = viewport.visible_lines(content);
+        let (top_line, visible_count) = (visible_lines.first().map_or(0, |visible| visible.line), visible_lines.len());
+
+        let line_highlight = visible_lines.iter()
+            .find(|visible| visible.line == cursor_line)
+            .map(|visible| Decoration::Line { row: visible.row, rows: visible.rows });
+
+        let folds = document.map_or(&self.folds, |document| &document.folds);
+        let line_count = folds.document_line(content.line_count());
+
+        let markers = visible_lines.iter()
+            .filter(|_| document.is_none())
+            .filter_map(|visible| {
+                self.diagnostics.line_severity(visible.line).map(|severity| (visible.line, severity.color()))
+            })
+            .collect();
+
+        let underlines = match document {
+            Some(_) => Vec::new(),
+            None => self.diagnostics.underlines(content, &visible_lines, viewport.columns())
+        };
+
+        let selection_color = self.theme().extended_palette().secondary.weak.color;
+
+        // A block is drawn as a whole, past the end of short lines too.
+        let block = self.block
+            .filter(|_| focused)
+            .map(|block| block.decorations(content, &visible_lines, viewport.columns(), selection_color))
+            .unwrap_or_default();
+
+        let extra_cursors = match focused {
+            true => self.cursors.decorations(
+                content,
+                &visible_lines,
+                viewport.columns(),
+                match self.block {
+                    Some(_) => None,
+                    None => Some(selection_color)
+                }
+            ),
+            false => Vec::new()
+        };
+
+        let vim_cursor = match focused && self.config.keymap == Keymap::Vim {
+            true => self.vim.decorations(
+                content,
+                &visible_lines,
+                viewport.columns(),
+                Color { a: 0.5, ..self.theme().palette().text },
+                selection_color
+            ),
+            false => Vec::new()
+        };
+
+        let bracket_color = Color { a: 0.25, ..self.theme().palette().text };
+
+        let matching_brackets = brackets::matching(content, buffer::cursor(content))
+            .filter(|_| focused)
+            .into_iter()
+            .flat_map(|(bracket, other)| [bracket, other])
+            .filter_map(|position| viewport::locate(&visible_lines, position, viewport.columns()))
+            .map(|(row, column)| Decoration::Selection { row, from: column, to: column + 1, color: bracket_color })
+            .collect::<Vec<_>>();
+
+        let folded = folds.decorations(content, &visible_lines, viewport.columns());
+        let fold_toggles = folds.toggles(content, &visible_lines);
+        let numbers = folds.numbers(&visible_lines);
+
+        let changes = match document {
+            Some(_) => Vec::new(),
+            None => diff::changes(&self.git_hunks, line_count)
+        };
+
+        let change_markers = visible_lines.iter()
+            .filter_map(|visible| {
+                let line = self.folds.document_line(visible.line);
+                changes.iter().find(|(changed, _)| *changed == line).map(|(_, change)| (visible.line, *change))
+            })
+            .collect();
+
+        // Who last changed the cursor line, after its end.
+        let blame = self.tracked.as_ref()
+            .and_then(|tracked| tracked.blame(&self.git_hunks, self.folds.document_line(cursor_line)))
+            .filter(|_| focused)
+            .and_then(|blame| {
+                let end = buffer::Position::new(cursor_line, buffer::line_length(content, cursor_line));
+                let (row, column) = viewport::locate(&visible_lines, end, viewport.columns())?;
+
+                // Past the mark of a fold hanging off the line.
+                let gap = match self.folds.is_folded(cursor_line) {
+                    true => 6,
+                    false => 3
+                };
+
+                Some(Decoration::Annotation { row, column: column + gap, text: blame })
+            });
+
+        let line_numbers = gutter(visible_lines, cursor_line, line_count)
+            .relative(self.relative_numbers)
+            .markers(markers)
+            .changes(change_markers)
+            .numbers(numbers)
+            .folds(fold_toggles, Message::ToggleFold);
+
+        let ruler = match document {
+            Some(document) => self.config.for_file(document.path.as_deref()).ruler.filter(|column| *column > 0),
+            None => self.ruler
+        };
+        let ruler = ruler.map(|column| Decoration::Ruler { column });
+
+        let decorated = Decorations::new(
+            input_box,
+            line_highlight.into_iter().chain(ruler).chain(block).chain(extra_cursors).chain(vim_cursor).chain(matching_brackets).chain(folded).chain(blame).chain(underlines).collect()
+        );
+
+        let text_area: iced::Element<'_, Message> = match viewport.wrap() {
+            Wrap::Window => decorated.into(),
+            Wrap::Column => {
+                let width = viewport.wrap_column() as f32 * CHAR_WIDTH + EDITOR_PADDING * 2.0;
+
+                // Filling the rest of the row keeps what's beside the editor at the edge of the window.
+                widget::container(widget::container(decorated).width(Length::Fixed(width))).width(Length::Fill).into()
+            },
+            Wrap::Off => {
+                let longest = content.lines()
+                    .map(|line| line.chars().count())
+                    .max()
+                    .unwrap_or(0);
+
+                // One extra column so the cursor fits after the last character.
+                let width = (longest + 1) as f32 * CHAR_WIDTH + EDITOR_PADDING * 2.0;
+
+                widget::scrollable(
+                    widget::container(decorated).width(Length::Fixed(width)).height(Length::Fill)
+                )
+                .id(editor_scrollable(id))
+                .direction(scrollable::Direction::Horizontal(scrollable::Properties::default()))
+                .on_scroll(move |scrolled| Message::EditorScrolled(pane, scrolled))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into()
+            }
+        };
+
+        let mut editor_area = widget::row![line_numbers, text_area];
+
+        if self.show_minimap {
+            editor_area = editor_area.push(minimap(content, top_line, visible_count, move |line| Message::ScrollTo(pane, line)));
+        }
+
+        editor_area.into()
+    }
+
+    /// Works out the size of each pane's text area from the window size, mirroring the layout in `view`.
+    fn resize_viewport(&mut self) {
+        let beside = self.gutter_width() + self.minimap_width();
+
+        for (pane, region) in self.pane_regions() {
+            match self.panes.other_mut(pane) {
+                Some(state) => {
+                    let file_config = self.config.for_file(state.path.as_deref());
+
+                    state.viewport.set_wrap(file_config.wrap.unwrap_or_default(), file_config.wrap_column.unwrap_or(DEFAULT_WRAP_COLUMN));
+                    state.viewport.resize(region.width - beside, region.height);
+                    state.viewport.reveal_cursor(&state.content);
+                },
+                None => {
+                    self.viewport.resize(region.width - beside, region.height);
+                    self.viewport.reveal_cursor(&self.content);
+                }
+            }
+        }
+
+        // The terminal spans the window below the panes.
+        self.terminal.fit(self.window_size.width - 30.0);
+    }
+
+    /// Where each pane is in the editor area, mirroring the layout in `view`.
+    fn pane_regions(&self) -> BTreeMap<pane_grid::Pane, Rectangle> {
+        let outline = match self.show_outline {
+            true => OUTLINE_WIDTH,
+            false => 0.0
+        };
+
+        let mut height = self.window_size.height - 30.0 - 10.0 - LINE_HEIGHT;
+
+        if self.show_problems {
+            height -= PROBLEMS_HEIGHT + 10.0;
+        }
+
+        if self.terminal.is_shown() {
+            height -= terminal::PANEL_HEIGHT + 10.0;
+        }
+
+        self.panes.regions(Size::new(self.window_size.width - 30.0 - outline, height))
+    }
+
+    fn focused_region(&self) -> Rectangle {
+        self.pane_regions()
+            .remove(&self.panes.focused())
+            .unwrap_or(Rectangle::new(Point::ORIGIN, Size::ZERO))
+    }
+
+    /// Gives a pane the focus, its cursor and scroll become the editor's, and
+    /// its file the one edited.
+    fn focus_pane(&mut self, pane: pane_grid::Pane) -> Command<Message> {
+        let Some(path) = self.panes.focus(pane, &mut self.content, &mut self.viewport, &mut self.horizontal_offset) else {
+            return Command::none();
+        };
+
+        self.leave_pane();
+        self.switch_document(path)
+    }
+
+    /// Drops what pointed at the cursor of the pane that lost the focus.
+    fn leave_pane(&mut self) {
+        self.history.break_group();
+        self.snippet = None;
+        self.completion = None;
+        self.hover = None;
+        self.cursors.clear();
+        self.block = None;
+    }
+
+    /// Makes the same changes to the other panes showing the focused pane's file.
+    fn sync_panes(&mut self) {
+        self.panes.sync(&self.path, &self.content);
+    }
+
+    /// Whether the focused pane can stop showing its file without losing changes.
+    fn can_leave_document(&self) -> bool {
+        self.saved || self.panes.shown_elsewhere(&self.path)
+    }
+
+    /// Has the focused pane edit the file at `path`, which another pane shows.
+    fn switch_document(&mut self, path: Option<PathBuf>) -> Command<Message> {
+        if path == self.path {
+            return Command::none();
+        }
+
+        let Some(document) = self.panes.take_document(&path) else {
+            return Command::none();
+        };
+
+        // The pane's own horizontal scroll came along with its cursor, the file's settings don't reset it.
+        let horizontal_offset = self.horizontal_offset;
+
+        self.park_document();
+        self.restore_document(document);
+        self.horizontal_offset = horizontal_offset;
+
+        self.track_file()
+    }
+
+    /// Sets the focused pane's file aside for the other panes showing it, the
+    /// language server is done with it when none does.
+    fn park_document(&mut self) {
+        if !self.panes.shown_elsewhere(&self.path) {
+            self.lsp.send(self.path.as_deref(), |uri| lsp::Request::DidClose { uri });
+            return;
+        }
+
+        self.panes.park(Document {
+            path: self.path.take(),
+            format: std::mem::take(&mut self.format),
+            saved: self.saved,
+            indentation: self.indentation,
+            history: std::mem::take(&mut self.history),
+            folds: std::mem::take(&mut self.folds),
+            version: self.document_version,
+            diagnostics: std::mem::take(&mut self.published_diagnostics),
+        });
+    }
+
+    /// Makes `document` the one edited, the text is already in `content`.
+    fn restore_document(&mut self, document: Document) {
+        self.path = document.path;
+        self.format = document.format;
+        self.saved = document.saved;
+        self.indentation = document.indentation;
+        self.history = document.history;
+        self.folds = document.folds;
+        self.document_version = document.version;
+        self.published_diagnostics = document.diagnostics;
+        self.apply_file_config();
+
+        self.panes.set_path(self.path.clone());
+
+        self.diagnostics.clear();
+        self.refresh_diagnostics();
+        self.hover = None;
+        self.completion = None;
+        self.snippet = None;
+        self.cursors.clear();
+        self.block = None;
+        self.comparison = None;
+        self.tracked = None;
+        self.git_hunks.clear();
+
+        self.symbols_from_server = false;
+        self.refresh_symbols();
+    }
+
+    /// The pre-save steps configured for the current file.
+    fn save_pipeline(&self) -> SavePipeline {
+        SavePipeline::new(&self.config.for_file(self.path.as_deref()), &self.format.editorconfig)
+    }
+
+    /// Runs the save pipeline over `text`, puts the result in the buffer as an
+    /// undoable edit and writes it to disk.
+    fn write_file(&mut self, text: String) -> Command<Message> {
+        let Some(path) = self.path.clone() else {
+            return Command::none();
+        };
+
+        let pipeline = self.save_pipeline();
+        let text = pipeline.apply(&text);
+        let buffer_text = text.strip_suffix('\n').unwrap_or(&text);
+
+        if buffer_text != self.document_text() {
+            // The whole text is replaced, the folds can't tell where their lines went.
+            self.folds.unfold_all(&mut self.content);
+            self.history.record(&self.content, &self.folds, None);
+            buffer::set_text(&mut self.content, buffer_text);
+            self.viewport.reveal_cursor(&self.content);
+            self.sync_panes();
+        }
+
+        if let Some(line_ending) = pipeline.line_ending {
+            self.format.line_ending = line_ending;
+        }
+
+        Command::perform(save_file(path, text, self.format.clone()), Message::FileSaved)
+    }
+
+    /// Marks the buffer as modified and tells the language server about it.
+    fn mark_changed(&mut self) {
+        self.saved = false;
+        self.document_version += 1;
+
+        self.lsp.send(self.path.as_deref(), |uri| lsp::Request::DidChange {
+            uri,
+            version: self.document_version,
+            text: self.document_text() + "\n"
+        });
+
+        self.refresh_symbols();
+        self.sync_panes();
+
+        if let Some(mut comparison) = self.comparison.take() {
+            comparison.update(self.document_text());
+            self.comparison = Some(comparison);
+        }
+
+        self.refresh_git_hunks();
+    }
+
+    /// Asks git about the open file again.
+    fn track_file(&self) -> Command<Message> {
+        match self.path.clone() {
+            Some(path) => Command::perform(git::track(path), Message::GitLoaded),
+            None => Command::none()
+        }
+    }
+
+    /// Lists the changed files of the repository again while the source control panel shows them.
+    fn refresh_changes(&self) -> Command<Message> {
+        self.source_control.refresh(self.path.as_deref(), Message::ChangesLoaded)
+    }
+
+    /// Compares the buffer with the last commit for the change markers in the gutter.
+    fn refresh_git_hunks(&mut self) {
+        self.git_hunks = match &self.tracked {
+            Some(tracked) => tracked.hunks(&self.document_text()),
+            None => Vec::new()
+        };
+    }
+
+    /// Hands the current buffer to the language server.
+    fn open_document(&self) {
+        self.lsp.open(self.path.as_deref(), self.document_version, || self.document_text() + "\n");
+    }
+
+    fn document_line_count(&self) -> usize {
+        self.folds.document_line(self.content.line_count())
+    }
+
+    fn gutter_width(&self) -> f32 {
+        Gutter::<Message>::width(self.document_line_count())
+    }
+
+    fn minimap_width(&self) -> f32 {
+        match self.show_minimap {
+            true => MINIMAP_WIDTH,
+            false => 0.0
+        }
+    }
+
+    /// Width left for the text of the focused pane between its gutter and minimap, mirroring the layout in `view`.
+    fn text_area_width(&self) -> f32 {
+        self.focused_region().width - self.gutter_width() - self.minimap_width()
+    }
+
+    /// The text of the file, with the folded lines that aren't in `content`.
+    fn document_text(&self) -> String {
+        match self.folds.is_empty() {
+            true => buffer::text(&self.content),
+            false => self.folds.document_text(&self.content)
+        }
+    }
+
+    /// Turns a position the language server sent into one in the buffer,
+    /// opening the folds it's hidden in.
+    fn reveal_document_position(&mut self, position: lsp_types::Position) -> buffer::Position {
+        let line = self.folds.reveal(&mut self.content, position.line as usize);
+        self.sync_panes();
+
+        lsp::from_lsp_position(&self.content, lsp_types::Position::new(line as u32, position.character))
+    }
+
+    /// Places the diagnostics from the language server in the buffer, the ones
+    /// inside a fold at the end of the line it hangs off.
+    fn refresh_diagnostics(&mut self) {
+        let to_buffer = |position: lsp_types::Position| match self.folds.buffer_line(position.line as usize) {
+            (line, true) => lsp_types::Position::new(line as u32, u32::MAX),
+            (line, false) => lsp_types::Position::new(line as u32, position.character)
+        };
+
+        let diagnostics = self.published_diagnostics.iter()
+            .map(|diagnostic| {
+                let range = lsp_types::Range::new(to_buffer(diagnostic.range.start), to_buffer(diagnostic.range.end));
+                Diagnostic::from_lsp(&self.content, lsp_types::Diagnostic { range, ..diagnostic.clone() })
+            })
+            .collect();
+
+        self.diagnostics.set(diagnostics);
+    }
+
+    /// Finds the symbols of the document again when the outline or the symbol
+    /// picker shows them, the language server's replace the parsed ones once it sends some.
+    fn refresh_symbols(&mut self) {
+        if !self.show_outline && !self.show_symbols {
+            return;
+        }
+
+        if !self.symbols_from_server {
+            self.symbols = outline::parse(&self.document_text(), self.path.as_deref());
+        }
+
+        self.lsp.send(self.path.as_deref(), |uri| lsp::Request::DocumentSymbols { uri });
+    }
+
+    /// Runs a fold command, keeping everything that points into the buffer in step.
+    fn change_folds(&mut self, change: impl FnOnce(&mut Folds, &mut Content)) -> Command<Message> {
+        let before = self.folds.clone();
+        change(&mut self.folds, &mut self.content);
+
+        if self.folds == before {
+            return Command::none();
+        }
+
+        self.history.break_group();
+        self.snippet = None;
+        self.completion = None;
+        self.cursors.clear();
+        self.block = None;
+
+        self.refresh_diagnostics();
+        self.viewport.reveal_cursor(&self.content);
+        self.sync_panes();
+
+        Command::batch([self.remember_folds(), self.reveal_cursor_horizontally()])
+    }
+
+    /// Keeps the folds of the file in the session so they're back when it's opened again.
+    fn remember_folds(&mut self) -> Command<Message> {
+        match &self.path {
+            Some(path) if self.session.remember(path, self.folds.ranges()) => {
+                Command::perform(save_session(self.session.clone()), Message::SessionSaved)
+            },
+            _ => Command::none()
+        }
+    }
+
+    /// Loads another file in place of this one, unless that would throw away unsaved changes.
+    fn open_file(&mut self, path: PathBuf) -> Command<Message> {
+        match self.can_leave_document() {
+            true => Command::perform(load_file(path), Message::FileOpened),
+            false => {
+                self.error = Some(GFEError::UnsavedChanges);
+                Command::none()
+            }
+        }
+    }
+
+    /// The cursor as the language server counts, in lines of the document.
+    fn lsp_cursor(&self) -> lsp_types::Position {
+        let mut position = lsp::to_lsp_position(&self.content, buffer::cursor(&self.content));
+        position.line = self.folds.document_line(position.line as usize) as u32;
+
+        position
+    }
+
+    /// The list of diagnostics below the editor, clicking one jumps to it.
+    fn problems_panel(&self) -> iced::Element<'_, Message> {
+        let problems = self.diagnostics.iter().map(|diagnostic| {
+            let location = format!("{}:{}", self.folds.document_line(diagnostic.start.line) + 1, diagnostic.start.column + 1);
+            let source = diagnostic.source.as_deref().map(|source| format!(" ({source})")).unwrap_or_default();
+
+            widget::button(
+                widget::row![
+                    widget::text(diagnostic.severity.label())
+                        .style(theme::Text::Color(diagnostic.severity.color()))
+                        .width(Length::Fixed(60.0)),
+                    widget::text(location).width(Length::Fixed(70.0)),
+                    widget::text(format!("{}{}", diagnostic.message.lines().next().unwrap_or(""), source))
+                ].spacing(10)
+            )
+            .style(theme::Button::Text)
+            .padding([2, 5])
+            .width(Length::Fill)
+            .on_press(Message::JumpTo(diagnostic.start))
+            .into()
+        });
+
+        widget::container(widget::scrollable(widget::column(problems)).width(Length::Fill))
+            .height(Length::Fixed(PROBLEMS_HEIGHT))
+            .into()
+    }
+
+    /// Hover text from the language server, shown over the editor.
+    fn info_panel(&self) -> Option<iced::Element<'_, Message>> {
+        let text = self.hover.as_ref()?;
+
+        Some(
+            widget::container(widget::scrollable(widget::text(text).font(Font::MONOSPACE)))
+                .max_width(700.0)
+                .max_height(400.0)
+                .padding(10)
+                .into()
+        )
+    }
+
+    /// Expands a snippet body in place of the text between `from` and `to`.
+    fn insert_snippet(&mut self, from: buffer::Position, to: buffer::Position, body: &str) -> Command<Message> {
+        let variables = snippets::variables(self.path.as_deref());
+        let line_count = self.content.line_count();
+
+        self.history.record(&self.content, &self.folds, None);
+        self.completion = None;
+        self.snippet = snippets::expand(&mut self.content, from, to, body, &variables, &self.indentation);
+        self.folds.shift(to.line, self.content.line_count() as isize - line_count as isize);
+
+        self.mark_changed();
+        self.viewport.reveal_cursor(&self.content);
+        self.reveal_cursor_horizontally()
+    }
+
+    /// The snippets of the current file type to pick from, with their prefix and description.
+    fn snippet_picker(&self) -> iced::Element<'_, Message> {
+        let snippets = self.snippets.for_file(self.path.as_deref());
+
+        if snippets.is_empty() {
+            let directory = config::config_dir()
+                .map(|directory| directory.join("snippets").display().to_string())
+                .unwrap_or_default();
+
+            return widget::container(widget::text(format!("No snippets, add some to {directory}")))
+                .padding(10)
+                .into();
+        }
+
+        let items = snippets.into_iter().enumerate().map(|(index, snippet)| {
+            widget::button(
+                widget::row![
+                    widget::text(&snippet.prefix).font(Font::MONOSPACE).width(Length::Fixed(120.0)),
+                    widget::text(snippet.description.as_deref().unwrap_or(""))
+                ].spacing(10)
+            )
+            .style(theme::Button::Text)
+            .width(Length::Fill)
+            .on_press(Message::InsertSnippet(index))
+            .into()
+        });
+
+        widget::container(widget::scrollable(widget::column(items)))
+            .width(Length::Fixed(500.0))
+            .max_height(400.0)
+            .padding(10)
+            .into()
+    }
+
+    /// The symbols of the document beside the editor, the one the cursor is in highlighted.
+    fn outline_panel(&self) -> iced::Element<'_, Message> {
+        let cursor_line = self.folds.document_line(buffer::cursor(&self.content).line);
+        let current = outline::current(&self.symbols, cursor_line);
+
+        let items = self.symbols.iter().enumerate().map(|(index, symbol)| {
+            widget::button(symbol_row(symbol))
+                .style(match Some(index) == current {
+                    true => theme::Button::Primary,
+                    false => theme::Button::Text
+                })
+                .padding([2.0, 5.0, 2.0, 5.0 + symbol.depth as f32 * 12.0])
+                .width(Length::Fill)
+                .on_press(Message::GoToSymbol(symbol.line, symbol.column))
+                .into()
+        });
+
+        let list: iced::Element<'_, Message> = match self.symbols.is_empty() {
+            true => widget::text("No symbols").size(12).into(),
+            false => widget::scrollable(widget::column(items)).width(Length::Fill).into()
+        };
+
+        widget::container(list)
+            .width(Length::Fixed(OUTLINE_WIDTH))
+            .height(Length::Fill)
+            .padding([0, 0, 0, 10])
+            .into()
+    }
+
+    /// A filter over the symbols of the document, picking one goes to it.
+    fn symbol_picker(&self) -> iced::Element<'_, Message> {
+        let matches = outline::matches(&self.symbols, &self.symbol_query);
+
+        let items = matches.into_iter().map(|symbol| {
+            widget::button(symbol_row(symbol))
+                .style(theme::Button::Text)
+                .width(Length::Fill)
+                .on_press(Message::GoToSymbol(symbol.line, symbol.column))
+                .into()
+        });
+
+        widget::container(
+            widget::column![
+                widget::text_input("Go to symbol", &self.symbol_query)
+                    .id(symbol_input())
+                    .on_input(Message::SymbolQueryChanged)
+                    .on_submit(Message::SubmitSymbolQuery),
+                widget::scrollable(widget::column(items)),
+            ].spacing(10)
+        )
+        .width(Length::Fixed(500.0))
+        .max_height(400.0)
+        .padding(10)
+        .style(theme::Container::Box)
+        .into()
+    }
+
+    /// Makes `edit` a step of its own in the history, if there is one.
+    fn apply_line_edit(&mut self, edit: Option<LineEdit>) -> Command<Message> {
+        let Some(edit) = edit else {
+            return Command::none();
+        };
+
+        self.snippet = None;
+        self.completion = None;
+        self.cursors.clear();
+        self.block = None;
+
+        let (last, line_count) = (edit.to.line, self.content.line_count());
+
+        self.history.record(&self.content, &self.folds, None);
+        lines::apply(&mut self.content, edit);
+        self.folds.shift(last, self.content.line_count() as isize - line_count as isize);
+
+        self.mark_changed();
+        self.viewport.reveal_cursor(&self.content);
+        self.reveal_cursor_horizontally()
+    }
+
+    /// The line operations to pick from.
+    fn line_operations(&self) -> iced::Element<'_, Message> {
+        let items = LineOperation::ALL.into_iter().map(|operation| {
+            widget::button(widget::text(operation.label()))
+                .style(theme::Button::Text)
+                .width(Length::Fill)
+                .on_press(Message::Lines(operation))
+                .into()
+        });
+
+        widget::container(widget::scrollable(widget::column(items)))
+            .width(Length::Fixed(300.0))
+            .max_height(400.0)
+            .padding(10)
+            .into()
+    }
+
+    /// Keeps the completion popup in step with the word at the cursor,
+    /// opening it when `open` is set and enough of a word was typed.
+    fn update_completion(&mut self, open: bool) {
+        let (start, prefix) = completion::prefix(&self.content);
+
+        // Completions only know about the primary cursor.
+        if prefix.is_empty() || !self.cursors.is_empty() {
+            self.completion = None;
+            return;
+        }
+
+        if let Some(completion) = self.completion.as_mut().filter(|completion| completion.start == start) {
+            completion.filter(prefix);
+            return;
+        }
+
+        match open && prefix.chars().count() >= 2 {
+            true => self.open_completion(start, prefix),
+            false => self.completion = None
+        }
+    }
+
+    /// Asks every provider, and the language server, what the word at `start` could become.
+    fn open_completion(&mut self, start: buffer::Position, prefix: String) {
+        let snippets = SnippetCompletions(self.snippets.for_file(self.path.as_deref()));
+
+        let items = self.providers.iter()
+            .map(|provider| provider.as_ref())
+            .chain(std::iter::once(&snippets as &dyn Provider))
+            .flat_map(|provider| provider.complete(&self.content, &prefix))
+            .collect();
+
+        self.completion = Some(Completion::new(start, prefix, items));
+
+        self.lsp.send(self.path.as_deref(), |uri| lsp::Request::Completion { uri, position: self.lsp_cursor() });
+    }
+
+    /// The completion popup and the spot right under the word being completed it's anchored to.
+    fn completion_popup(&self) -> Option<(iced::Element<'_, Message>, Rectangle)> {
+        const VISIBLE: usize = 8;
+
+        let completion = self.completion.as_ref().filter(|completion| !completion.is_empty())?;
+        let selected = completion.selected();
+        let first = selected.saturating_sub(VISIBLE - 1);
+
+        let items = completion.matches().enumerate().skip(first).take(VISIBLE).map(|(index, item)| {
+            let detail: String = item.detail.as_deref()
+                .unwrap_or(item.source.label())
+                .chars()
+                .take(30)
+                .collect();
+
+            let label = widget::row![
+                widget::text(&item.label).font(Font::MONOSPACE),
+                widget::horizontal_space(),
+                widget::text(detail).size(12).style(theme::Text::Color(Color { a: 0.5, ..Color::WHITE }))
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center);
+
+            widget::button(label)
+                .style(match index == selected {
+                    true => theme::Button::Primary,
+                    false => theme::Button::Text
+                })
+                .width(Length::Fill)
+                .padding([2, 6])
+                .on_press(Message::AcceptCompletion(index))
+                .into()
+        });
+
+        let popup = widget::container(widget::column(items))
+            .width(Length::Fixed(350.0))
+            .padding(4)
+            .style(theme::Container::Box);
+
+        // Mirrors the layout in `view`, the content has 15 pixels of padding.
+        let region = self.focused_region();
+        let column = match self.viewport.wrap() {
+            Wrap::Off => completion.start.column as f32 * CHAR_WIDTH - self.horizontal_offset,
+            _ => (completion.start.column % self.viewport.columns()) as f32 * CHAR_WIDTH
+        };
+
+        let anchor = Rectangle::new(
+            Point::new(
+                15.0 + region.x + self.gutter_width() + EDITOR_PADDING + column,
+                15.0 + region.y + EDITOR_PADDING + self.viewport.visible_cursor_row(&self.content) as f32 * LINE_HEIGHT
+            ),
+            Size::new(CHAR_WIDTH, LINE_HEIGHT)
+        );
+
+        Some((popup.into(), anchor))
+    }
+
+    /// Applies the wrap and ruler preferences for the current file type.
+    fn apply_file_config(&mut self) {
+        let file_config = self.config.for_file(self.path.as_deref());
+        let wrap_column = file_config.wrap_column.unwrap_or(DEFAULT_WRAP_COLUMN);
+
+        self.viewport.set_wrap(file_config.wrap.unwrap_or_default(), wrap_column);
+        self.ruler = file_config.ruler.filter(|column| *column > 0);
+        self.auto_pair = file_config.auto_pair.unwrap_or(true);
+        self.horizontal_offset = 0.0;
+
+        self.resize_viewport();
+    }
+
+    /// Performs an action of the `text_editor`, keeping everything that follows the text in sync.
+    fn edit(&mut self, action: Action) -> Command<Message> {
+        // Alt turns clicking and dragging into a block selection, and
+        // Alt+Shift+arrows extend one instead of selecting text.
+        if self.modifiers.alt() {
+            match action {
+                Action::Click(point) => {
+                    let position = self.viewport.position_at(&self.content, point);
+
+                    self.history.break_group();
+                    self.snippet = None;
+                    self.completion = None;
+                    self.block = Some(Block::new(position));
+
+                    return self.apply_block();
+                },
+                Action::Drag(point) if self.block.is_some() => {
+                    if let Some(block) = &mut self.block {
+                        block.head = self.viewport.position_at(&self.content, point);
+                    }
+
+                    return self.apply_block();
+                },
+                // Alt+Up and Alt+Down move lines instead.
+                Action::Select(_) | Action::Move(Motion::Up | Motion::Down) => {
+                    return Command::none();
+                },
+                _ => {}
+            }
+        }
+
+        // Once its text is edited or the cursors move a block is just multiple cursors.
+        if !matches!(action, Action::Scroll { .. }) {
+            self.block = None;
+        }
+
+        // The keys that move the cursor pick a completion while the popup is open.
+        if let Some(completion) = &mut self.completion {
+            match &action {
+                Action::Move(Motion::Up) => {
+                    completion.select_previous();
+                    return Command::none();
+                },
+                Action::Move(Motion::Down) => {
+                    completion.select_next();
+                    return Command::none();
+                },
+                Action::Edit(Edit::Enter) if !completion.is_empty() => {
+                    let selected = completion.selected();
+                    return self.update(Message::AcceptCompletion(selected));
+                },
+                _ => {}
+            }
+        }
+
+        // Folds hang off a line, edits that can add or remove lines open the
+        // ones they touch and move the ones below along.
+        let touched = match &action {
+            Action::Edit(_) if !self.folds.is_empty() && !self.cursors.is_empty() => {
+                self.folds.unfold_all(&mut self.content);
+                None
+            },
+            Action::Edit(edit) if !self.folds.is_empty() => self.folds.unfold_for_edit(&mut self.content, edit),
+            _ => None
+        };
+
+        let line_count = self.content.line_count();
+
+        let (cursor, selection) = (buffer::cursor(&self.content), buffer::selection(&self.content));
+
+        // Brackets and quotes are typed and deleted in pairs, with a single cursor.
+        let pairing = match (&action, self.auto_pair && self.cursors.is_empty()) {
+            (Action::Edit(Edit::Insert(character)), true) => brackets::typed(&self.content, cursor, selection, *character, self.path.as_deref()),
+            (Action::Edit(Edit::Backspace), true) => brackets::deleted(&self.content, cursor, selection, self.path.as_deref()),
+            _ => None
+        };
+
+        // Typing over a closing character just moves past it.
+        let action = match pairing {
+            Some(brackets::Pairing::Skip) => Action::Move(Motion::Right),
+            _ => action
+        };
+
+        match &action {
+            Action::Edit(edit) => {
+                self.history.record(&self.content, &self.folds, Group::of(edit));
+            },
+            Action::Scroll { .. } => {},
+            Action::Click(_) | Action::Drag(_) | Action::SelectWord | Action::SelectLine => {
+                self.history.break_group();
+                self.snippet = None;
+                self.cursors.clear();
+                self.block = None;
+                self.emacs.deactivate_mark();
+            },
+            _ => {
+                self.history.break_group();
+            }
+        }
+
+        // Carry the indentation of the current line over to the new one.
+        let carried_indent = match action {
+            Action::Edit(Edit::Enter) if self.cursors.is_empty() => {
+                let cursor = buffer::cursor(&self.content);
+                let line = self.content.line(cursor.line).map(|line| line.to_string()).unwrap_or_default();
+
+                indent::leading_whitespace(&line).chars().take(cursor.column).collect()
+            },
+            _ => String::new()
+        };
+
+        match action {
+            Action::Edit(_) | Action::Move(_) | Action::Select(_) if !self.cursors.is_empty() => {
+                self.cursors.perform(&mut self.content, &action);
+            },
+            _ => match pairing {
+                Some(pairing) if pairing != brackets::Pairing::Skip => brackets::perform(&mut self.content, pairing),
+                _ => self.content.perform(action.clone())
+            }
+        }
+
+        if !carried_indent.is_empty() {
+            self.content.perform(Action::Edit(Edit::Paste(Arc::new(carried_indent))));
+        }
+
+        if let Some((_, last)) = touched {
+            self.folds.shift(last, self.content.line_count() as isize - line_count as isize);
+        }
+
+        if let Action::Edit(_) = action {
+            self.mark_changed();
+        }
+
+        match action {
+            Action::Edit(Edit::Insert(character)) if completion::is_word_character(character) => {
+                self.update_completion(true);
+            },
+            Action::Edit(Edit::Backspace) => {
+                self.update_completion(false);
+            },
+            Action::Scroll { .. } => {},
+            _ => {
+                self.completion = None;
+            }
+        }
+
+        self.viewport.follow(&action, &self.content);
+
+        match action {
+            Action::Scroll { .. } => Command::none(),
+            _ => self.reveal_cursor_horizontally()
+        }
+    }
+
+    fn vim_key(&mut self, key: vim::Key) -> Command<Message> {
+        let effects = self.vim.key(key, &self.content);
+
+        self.run_effects(effects)
+    }
+
+    /// Carries out what the Vim or Emacs layer asked for.
+    fn run_effects(&mut self, effects: Vec<Effect>) -> Command<Message> {
+        let mut commands = Vec::new();
+
+        // The key that led here is recorded already, not the commands it runs.
+        let recording = self.recording.take();
+
+        for effect in effects {
+            let command = match effect {
+                Effect::Perform(action) => self.edit(action),
+                Effect::MoveTo(position) => {
+                    self.history.break_group();
+                    buffer::move_to(&mut self.content, position);
+                    Command::none()
+                },
+                Effect::Select { anchor, head } => {
+                    self.history.break_group();
+                    Cursor::new(buffer::offset(&self.content, anchor), buffer::offset(&self.content, head)).apply(&mut self.content);
+                    Command::none()
+                },
+                Effect::Indent { from, to, outdent } => {
+                    let (from, to) = (buffer::Position::new(from, 0), buffer::Position::new(to, 0));
+
+                    self.history.record(&self.content, &self.folds, None);
+
+                    match outdent {
+                        true => indent::outdent_lines(&mut self.content, &self.indentation, from, to),
+                        false => indent::indent_lines(&mut self.content, &self.indentation, from, to)
+                    }
+
+                    self.mark_changed();
+                    Command::none()
+                },
+                Effect::Undo => self.update(Message::Undo),
+                Effect::Save => self.update(Message::Save),
+                Effect::SaveAndQuit => {
+                    self.quit_after_save = self.path.is_some();
+                    self.update(Message::Save)
+                },
+                Effect::Quit { force } => {
+                    match force || (self.saved && self.panes.all_saved()) {
+                        true => window::close(window::Id::MAIN),
+                        false => {
+                            match self.config.keymap {
+                                Keymap::Emacs => self.emacs.refuse_quit(),
+                                _ => self.vim.refuse_quit()
+                            }
+
+                            Command::none()
+                        }
+                    }
+                },
+                Effect::Open(None) => self.update(Message::Open),
+                Effect::ShowPalette => {
+                    self.show_control_pallet = true;
+                    Command::none()
+                },
+                Effect::Open(Some(path)) => self.open_file(keymap::open_path(&path, self.path.as_deref())),
+                Effect::Replay(keys) => {
+                    let mut replayed = Vec::new();
+
+                    for key in keys {
+                        replayed.push(self.vim_key(key));
+                    }
+
+                    Command::batch(replayed)
+                }
+            };
+
+            commands.push(command);
+        }
+
+        self.recording = recording;
+
+        self.viewport.reveal_cursor(&self.content);
+        commands.push(self.reveal_cursor_horizontally());
+
+        Command::batch(commands)
+    }
+
+    /// Replays one step of a macro, or a key of the Vim or Emacs layer.
+    fn play_step(&mut self, step: Step) -> Command<Message> {
+        if let Some(action) = step.action() {
+            return self.edit(action);
+        }
+
+        match step {
+            Step::SelectAll => self.update(Message::SelectAll),
+            Step::Indent => self.update(Message::Indent),
+            Step::Outdent => self.update(Message::Outdent),
+            Step::Undo => self.update(Message::Undo),
+            Step::Redo => self.update(Message::Redo),
+            Step::AddCursorAbove => self.update(Message::AddCursorAbove),
+            Step::AddCursorBelow => self.update(Message::AddCursorBelow),
+            Step::SelectNextOccurrence => self.update(Message::SelectNextOccurrence),
+            Step::SplitSelection => self.update(Message::SplitSelection),
+            Step::Lines(operation) => self.update(Message::Lines(operation)),
+            Step::ToggleComment => self.update(Message::ToggleComment),
+            Step::ToggleBlockComment => self.update(Message::ToggleBlockComment),
+            Step::JumpToBracket => self.update(Message::JumpToBracket),
+            Step::ToggleFold => self.update(Message::ToggleFoldAtCursor),
+            Step::FoldAll => self.update(Message::FoldAll),
+            Step::UnfoldAll => self.update(Message::UnfoldAll),
+            Step::Vim(key) => self.vim_key(key),
+            Step::Emacs(key) => {
+                let effects = self.emacs.key(key, &self.content);
+                self.run_effects(effects)
+            },
+            _ => Command::none()
+        }
+    }
+
+    /// Plays `steps` `times` times, or until they stop making their way down the file.
+    fn run_macro(&mut self, steps: &[Step], times: Option<usize>) -> Command<Message> {
+        let mut commands = Vec::new();
+
+        for _ in 0..times.unwrap_or(MACRO_RUN_LIMIT) {
+            let before = buffer::cursor(&self.content);
+
+            for step in steps {
+                commands.push(self.play_step(step.clone()));
+            }
+
+            let after = buffer::cursor(&self.content);
+            let on_last_line = before.line == after.line && after.line + 1 >= self.content.line_count();
+
+            if times.is_none() && (after <= before || on_last_line) {
+                break;
+            }
+        }
+
+        Command::batch(commands)
+    }
+
+    fn macros_panel(&self) -> iced::Element<'_, Message> {
+        let count = self.macro_count.parse().unwrap_or(1);
+
+        let last = widget::row![
+            widget::text(format!("Last recorded: {} steps", self.last_macro.len())),
+            widget::horizontal_space(),
+            widget::text_input("1", &self.macro_count)
+                .on_input(Message::MacroCountChanged)
+                .width(Length::Fixed(60.0)),
+            widget::button(widget::text("Run")).on_press(Message::RunMacro(None, Some(count))),
+            widget::button(widget::text("Run to end")).on_press(Message::RunMacro(None, None)),
+        ].spacing(10).align_items(Alignment::Center);
+
+        let save = widget::row![
+            widget::text_input("Name", &self.macro_name)
+                .on_input(Message::MacroNameChanged)
+                .on_submit(Message::SaveMacro),
+            widget::button(widget::text("Save")).on_press(Message::SaveMacro),
+        ].spacing(10);
+
+        let saved = self.macros.macros.iter().map(|(name, steps)| {
+            widget::row![
+                widget::text(name).font(Font::MONOSPACE),
+                widget::text(format!("{} steps", steps.len())).size(12),
+                widget::horizontal_space(),
+                widget::button(widget::text("Run")).style(theme::Button::Text).on_press(Message::RunMacro(Some(name.clone()), Some(count))),
+                widget::button(widget::text("Run to end")).style(theme::Button::Text).on_press(Message::RunMacro(Some(name.clone()), None)),
+                widget::button(widget::text("Delete")).style(theme::Button::Text).on_press(Message::DeleteMacro(name.clone())),
+            ].spacing(10).align_items(Alignment::Center).into()
+        });
+
+        widget::container(
+            widget::column![
+                last,
+                save,
+                widget::scrollable(widget::column(saved)),
+            ].spacing(10)
+        )
+        .width(Length::Fixed(500.0))
+        .max_height(400.0)
+        .padding(10)
+        .style(theme::Container::Box)
+        .into()
+    }
+
+    /// Puts a cursor on every line of the block selection.
+    fn apply_block(&mut self) -> Command<Message> {
+        let Some(block) = self.block else {
+            return Command::none();
+        };
+
+        let (cursors, primary) = block.cursors(&self.content);
+
+        self.cursors.set(&mut self.content, cursors, primary);
+        self.viewport.reveal_cursor(&self.content);
+        self.reveal_cursor_horizontally()
+    }
+
+    /// Scrolls the editor sideways so the cursor stays visible when wrapping is off.
+    fn reveal_cursor_horizontally(&mut self) -> Command<Message> {
+        if self.viewport.wrap() != Wrap::Off {
+            return Command::none();
+        }
+
+        let visible_width = self.text_area_width();
+        let margin = CHAR_WIDTH * 4.0;
+        let cursor_x = buffer::cursor(&self.content).column as f32 * CHAR_WIDTH + EDITOR_PADDING;
+
+        let offset = if cursor_x < self.horizontal_offset + margin {
+            (cursor_x - margin).max(0.0)
+        } else if cursor_x > self.horizontal_offset + visible_width - margin {
+            cursor_x - visible_width + margin
+        } else {
+            return Command::none();
+        };
+
+        self.horizontal_offset = offset;
+
+        let id = self.panes.focused_id();
+
+        scrollable::scroll_to(editor_scrollable(id), scrollable::AbsoluteOffset { x: offset, y: 0.0 })
+    }
+}
+
+/// Most times "run to end" plays a macro, in case it never stops moving forward.
+const MACRO_RUN_LIMIT: usize = 10_000;
+
+/// Height of the problems panel, see [`Editor::problems_panel`].
+const PROBLEMS_HEIGHT: f32 = 150.0;
+
+/// Width of the outline beside the editor, see [`Editor::outline_panel`].
+const OUTLINE_WIDTH: f32 = 250.0;
+
+/// A button in the control pallet with the key that triggers it underneath.
+fn pallet_button(label: &str, key: &str, message: Message) -> widget::Column<'static, Message> {
+    widget::column![
+        widget::button(widget::text(label)).padding([5, 10]).on_press(message),
+        widget::text(format!("(CTRL + {key})")).size(12)
+    ].spacing(5).align_items(Alignment::Center)
+}
+
+/// A symbol's name with what kind of symbol it is, dimmed, after it.
+fn symbol_row(symbol: &Symbol) -> widget::Row<'_, Message> {
+    widget::row![
+        widget::text(&symbol.name).font(Font::MONOSPACE),
+        widget::horizontal_space(),
+        widget::text(symbol.kind.label()).size(12).style(theme::Text::Color(Color { a: 0.5, ..Color::WHITE }))
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center)
+}
+
+/// Id of the symbol picker's filter, so it can take the keyboard when it opens.
+fn symbol_input() -> widget::text_input::Id {
+    widget::text_input::Id::new("symbols")
+}
 
+/// Id of the scrollable a pane's editor sits in when wrapping is off.
+fn editor_scrollable(id: usize) -> scrollable::Id {
+    scrollable::Id::new(format!("editor-{id}"))
 }
@@ -0,0 +1,335 @@
+use iced::mouse;
+use iced::widget::text_editor::Content;
+use iced::{Color, Element, Length, Point, Rectangle, Size};
+use iced_wgpu::core::widget::tree::{self, Tree};
+use iced_wgpu::core::{event, layout, renderer, Clipboard, Event, Layout, Shell, Widget};
+
+use crate::viewport::{CHAR_WIDTH, LINE_HEIGHT};
+
+/// How much smaller than the editor the text is drawn.
+const SCALE: f32 = 1.0 / 7.0;
+const MINIMAP_LINE_HEIGHT: f32 = LINE_HEIGHT * SCALE;
+const MINIMAP_CHAR_WIDTH: f32 = CHAR_WIDTH * SCALE;
+/// Columns a tab takes up, the minimap doesn't know the indentation settings.
+const TAB_WIDTH: usize = 4;
+
+/// Width of the minimap next to the editor.
+pub const MINIMAP_WIDTH: f32 = 120.0;
+
+/// A scaled down view of the whole buffer next to the `text_editor`, with the
+/// part that's on screen highlighted. Clicking or dragging it scrolls the editor.
+pub struct Minimap<'a, Message> {
+    content: &'a Content,
+    /// First line of the buffer that's on screen.
+    top: usize,
+    /// How many lines of the buffer are on screen.
+    visible: usize,
+    on_scroll: Box<dyn Fn(usize) -> Message + 'a>,
+}
+
+impl<'a, Message> Minimap<'a, Message> {
+    /// `on_scroll` gets the line of the buffer that should end up at the top of the editor.
+    pub fn new(content: &'a Content, top: usize, visible: usize, on_scroll: impl Fn(usize) -> Message + 'a) -> Self {
+        Self {
+            content,
+            top,
+            visible: visible.max(1),
+            on_scroll: Box::new(on_scroll),
+        }
+    }
+
+    /// The line of the buffer drawn at the top of the minimap.
+    ///
+    /// When the buffer is longer than the minimap it scrolls along with the
+    /// editor, reaching its end when the editor does.
+    fn first_line(&self, height: f32) -> usize {
+        let capacity = (height / MINIMAP_LINE_HEIGHT) as usize;
+        let total = self.content.line_count();
+
+        if total <= capacity {
+            return 0;
+        }
+
+        let progress = self.top as f32 / total.saturating_sub(self.visible).max(1) as f32;
+
+        (progress.min(1.0) * (total - capacity) as f32).round() as usize
+    }
+
+    /// Where the part of the buffer that's on screen is drawn.
+    fn viewport_bounds(&self, bounds: Rectangle) -> Rectangle {
+        let first = self.first_line(bounds.height);
+
+        Rectangle::new(
+            Point::new(bounds.x, bounds.y + self.top.saturating_sub(first) as f32 * MINIMAP_LINE_HEIGHT),
+            Size::new(bounds.width, self.visible as f32 * MINIMAP_LINE_HEIGHT)
+        )
+    }
+
+    /// The line to scroll to for the top of the highlighted part to be `offset` pixels from the top.
+    fn top_at(&self, bounds: Rectangle, offset: f32) -> usize {
+        let capacity = (bounds.height / MINIMAP_LINE_HEIGHT) as usize;
+        let total = self.content.line_count();
+        let last_top = total.saturating_sub(self.visible);
+
+        let line = match total <= capacity {
+            true => offset / MINIMAP_LINE_HEIGHT,
+            // The minimap scrolls too, the highlight only covers the room it leaves.
+            false => {
+                let room = capacity.saturating_sub(self.visible).max(1) as f32 * MINIMAP_LINE_HEIGHT;
+                offset / room * last_top as f32
+            }
+        };
+
+        (line.max(0.0).round() as usize).min(last_top)
+    }
+}
+
+/// Where the highlighted part was grabbed while it's dragged, from its top.
+#[derive(Debug, Default)]
+struct State {
+    grabbed: Option<f32>,
+}
+
+impl<'a, Message, Theme, Renderer> Widget<Message, Theme, Renderer> for Minimap<'a, Message>
+where
+    Renderer: renderer::Renderer,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn size(&self) -> Size<Length> {
+        Size {
+            width: Length::Fixed(MINIMAP_WIDTH),
+            height: Length::Fill,
+        }
+    }
+
+    fn layout(
+        &self,
+        _tree: &mut Tree,
+        _renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        layout::Node::new(limits.width(MINIMAP_WIDTH).height(Length::Fill).max())
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        _viewport: &Rectangle,
+    ) -> event::Status {
+        let state = tree.state.downcast_mut::<State>();
+        let bounds = layout.bounds();
+        let highlighted = self.viewport_bounds(bounds);
+
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let Some(position) = cursor.position_over(bounds) else {
+                    return event::Status::Ignored;
+                };
+
+                // Clicking next to the highlight centers it there, either way it can be dragged on from there.
+                let grabbed = match highlighted.contains(position) {
+                    true => position.y - highlighted.y,
+                    false => highlighted.height / 2.0
+                };
+
+                state.grabbed = Some(grabbed);
+                shell.publish((self.on_scroll)(self.top_at(bounds, position.y - bounds.y - grabbed)));
+
+                event::Status::Captured
+            },
+            Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                let Some(grabbed) = state.grabbed else {
+                    return event::Status::Ignored;
+                };
+
+                let top = self.top_at(bounds, position.y - bounds.y - grabbed);
+
+                if top != self.top {
+                    shell.publish((self.on_scroll)(top));
+                }
+
+                event::Status::Captured
+            },
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) if state.grabbed.is_some() => {
+                state.grabbed = None;
+                event::Status::Captured
+            },
+            _ => event::Status::Ignored
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let bounds = layout.bounds();
+
+        if tree.state.downcast_ref::<State>().grabbed.is_some() {
+            return mouse::Interaction::Grabbing;
+        }
+
+        match cursor.position_over(bounds) {
+            Some(position) if self.viewport_bounds(bounds).contains(position) => mouse::Interaction::Grab,
+            Some(_) => mouse::Interaction::Pointer,
+            None => mouse::Interaction::default()
+        }
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let first = self.first_line(bounds.height);
+        let capacity = (bounds.height / MINIMAP_LINE_HEIGHT) as usize;
+        let columns = (bounds.width / MINIMAP_CHAR_WIDTH) as usize;
+
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds: self.viewport_bounds(bounds),
+                ..renderer::Quad::default()
+            },
+            Color { a: 0.08, ..Color::WHITE },
+        );
+
+        for line in first..self.content.line_count().min(first + capacity) {
+            let Some(text) = self.content.line(line) else {
+                break;
+            };
+
+            let y = bounds.y + (line - first) as f32 * MINIMAP_LINE_HEIGHT;
+
+            // Each run of characters between whitespace becomes a block.
+            let mut runs = Vec::new();
+            let mut run: Option<usize> = None;
+            let mut column = 0;
+
+            for character in text.chars().chain(std::iter::once(' ')) {
+                if column >= columns {
+                    break;
+                }
+
+                match (character.is_whitespace(), run) {
+                    (true, Some(start)) => {
+                        runs.push((start, column));
+                        run = None;
+                    },
+                    (false, None) => run = Some(column),
+                    _ => {}
+                }
+
+                column += match character {
+                    '\t' => TAB_WIDTH - column % TAB_WIDTH,
+                    _ => 1
+                };
+            }
+
+            // A run cut off at the edge is still drawn up to it.
+            runs.extend(run.map(|start| (start, columns)));
+
+            for (start, end) in runs {
+                renderer.fill_quad(
+                    renderer::Quad {
+                        bounds: Rectangle::new(
+                            Point::new(bounds.x + start as f32 * MINIMAP_CHAR_WIDTH, y),
+                            Size::new((end - start) as f32 * MINIMAP_CHAR_WIDTH, MINIMAP_LINE_HEIGHT - 1.0)
+                        ),
+                        ..renderer::Quad::default()
+                    },
+                    Color { a: 0.35, ..Color::WHITE },
+                );
+            }
+        }
+    }
+}
+
+impl<'a, Message, Theme, Renderer> From<Minimap<'a, Message>>
+    for Element<'a, Message, Theme, Renderer>
+where
+    Renderer: renderer::Renderer,
+    Message: 'a,
+{
+    fn from(minimap: Minimap<'a, Message>) -> Self {
+        Self::new(minimap)
+    }
+}
+
+pub fn minimap<'a, Message>(content: &'a Content, top: usize, visible: usize, on_scroll: impl Fn(usize) -> Message + 'a) -> Minimap<'a, Message> {
+    Minimap::new(content, top, visible, on_scroll)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bounds with room for `lines` lines of the minimap.
+    fn bounds(lines: f32) -> Rectangle {
+        Rectangle::new(Point::ORIGIN, Size::new(MINIMAP_WIDTH, (lines + 0.5) * MINIMAP_LINE_HEIGHT))
+    }
+
+    fn content(lines: usize) -> Content {
+        Content::with_text(&vec!["line"; lines].join("\n"))
+    }
+
+    #[test]
+    fn short_buffers_are_drawn_from_the_top() {
+        let content = content(8);
+        let minimap = Minimap::new(&content, 3, 4, |line| line);
+
+        assert_eq!(minimap.first_line(bounds(10.0).height), 0);
+
+        let highlight = minimap.viewport_bounds(bounds(10.0));
+        assert_eq!(highlight.y, 3.0 * MINIMAP_LINE_HEIGHT);
+        assert_eq!(highlight.height, 4.0 * MINIMAP_LINE_HEIGHT);
+    }
+
+    #[test]
+    fn long_buffers_scroll_along_with_the_editor() {
+        let content = content(100);
+        let height = bounds(20.0).height;
+
+        assert_eq!(Minimap::new(&content, 0, 10, |line| line).first_line(height), 0);
+        assert_eq!(Minimap::new(&content, 45, 10, |line| line).first_line(height), 40);
+        assert_eq!(Minimap::new(&content, 90, 10, |line| line).first_line(height), 80);
+    }
+
+    #[test]
+    fn clicks_scroll_to_the_line_under_them() {
+        let content = content(8);
+        let minimap = Minimap::new(&content, 0, 4, |line| line);
+
+        assert_eq!(minimap.top_at(bounds(10.0), 2.0 * MINIMAP_LINE_HEIGHT), 2);
+        assert_eq!(minimap.top_at(bounds(10.0), 9.0 * MINIMAP_LINE_HEIGHT), 4);
+        assert_eq!(minimap.top_at(bounds(10.0), -5.0), 0);
+
+        // With the minimap scrolling too, the room left by the highlight spans the whole buffer.
+        let content = self::content(100);
+        let minimap = Minimap::new(&content, 0, 10, |line| line);
+
+        assert_eq!(minimap.top_at(bounds(20.0), 5.0 * MINIMAP_LINE_HEIGHT), 45);
+        assert_eq!(minimap.top_at(bounds(20.0), 10.0 * MINIMAP_LINE_HEIGHT), 90);
+    }
+}
\ No newline at end of file
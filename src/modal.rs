@@ -13,6 +13,7 @@ pub struct Modal<'a, Message, Theme, Renderer> {
     base: Element<'a, Message, Theme, Renderer>,
     modal: Element<'a, Message, Theme, Renderer>,
     on_blur: Option<Message>,
+    anchor: Option<Rectangle>,
 }
 
 impl<'a, Message, Theme, Renderer> Modal<'a, Message, Theme, Renderer> {
@@ -25,6 +26,7 @@ impl<'a, Message, Theme, Renderer> Modal<'a, Message, Theme, Renderer> {
             base: base.into(),
             modal: modal.into(),
             on_blur: None,
+            anchor: None,
         }
     }
 
@@ -36,6 +38,17 @@ impl<'a, Message, Theme, Renderer> Modal<'a, Message, Theme, Renderer> {
             ..self
         }
     }
+
+    /// Shows the modal as a popup right below `anchor` (or above it when
+    /// there's no room) instead of centering it over a dimmed base.
+    ///
+    /// The base keeps getting events, so the popup can follow what's typed.
+    pub fn anchor(self, anchor: Rectangle) -> Self {
+        Self {
+            anchor: Some(anchor),
+            ..self
+        }
+    }
 }
 
 impl<'a, Message, Theme, Renderer> Widget<Message, Theme, Renderer>
@@ -129,6 +142,7 @@ where
             tree: &mut state.children[1],
             size: layout.bounds().size(),
             on_blur: self.on_blur.clone(),
+            anchor: self.anchor,
         })))
     }
 
@@ -171,6 +185,7 @@ struct Overlay<'a, 'b, Message, Theme, Renderer> {
     tree: &'b mut widget::Tree,
     size: Size,
     on_blur: Option<Message>,
+    anchor: Option<Rectangle>,
 }
 
 impl<'a, 'b, Message, Theme, Renderer>
@@ -185,6 +200,26 @@ where
         renderer: &Renderer,
         _bounds: Size,
     ) -> layout::Node {
+        if let Some(anchor) = self.anchor {
+            let limits = layout::Limits::new(Size::ZERO, self.size);
+
+            let child = self
+                .content
+                .as_widget()
+                .layout(self.tree, renderer, &limits);
+
+            let size = child.size();
+
+            let x = anchor.x.min(self.size.width - size.width).max(0.0);
+            let y = match anchor.y + anchor.height + size.height > self.size.height {
+                true => (anchor.y - size.height).max(0.0),
+                false => anchor.y + anchor.height,
+            };
+
+            return layout::Node::with_children(size, vec![child])
+                .move_to(self.position + Vector::new(x, y));
+        }
+
         let limits = layout::Limits::new(Size::ZERO, self.size)
             .width(Length::Fill)
             .height(Length::Fill);
@@ -217,7 +252,11 @@ where
             {
                 if !cursor.is_over(content_bounds) {
                     shell.publish(message.clone());
-                    return event::Status::Captured;
+
+                    // Popups let the click through to whatever is below them.
+                    if self.anchor.is_none() {
+                        return event::Status::Captured;
+                    }
                 }
             }
         }
@@ -242,16 +281,18 @@ where
         layout: Layout<'_>,
         cursor: mouse::Cursor,
     ) {
-        renderer.fill_quad(
-            renderer::Quad {
-                bounds: layout.bounds(),
-                ..renderer::Quad::default()
-            },
-            Color {
-                a: 0.90,
-                ..Color::BLACK
-            },
-        );
+        if self.anchor.is_none() {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: layout.bounds(),
+                    ..renderer::Quad::default()
+                },
+                Color {
+                    a: 0.90,
+                    ..Color::BLACK
+                },
+            );
+        }
 
         self.content.as_widget().draw(
             self.tree,
@@ -0,0 +1,700 @@
+use std::path::Path;
+
+use lsp_types::{DocumentSymbol, DocumentSymbolResponse, SymbolKind};
+
+use crate::{completion::fuzzy_score, config::file_type};
+
+/// What a [`Symbol`] is, the parsers and the language server agree on these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Heading,
+    Module,
+    Class,
+    Struct,
+    Enum,
+    Interface,
+    Trait,
+    Impl,
+    Type,
+    Function,
+    Method,
+    Constant,
+    Variable,
+    Field,
+    Macro,
+    Other,
+}
+
+impl Kind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Kind::Heading => "heading",
+            Kind::Module => "module",
+            Kind::Class => "class",
+            Kind::Struct => "struct",
+            Kind::Enum => "enum",
+            Kind::Interface => "interface",
+            Kind::Trait => "trait",
+            Kind::Impl => "impl",
+            Kind::Type => "type",
+            Kind::Function => "fn",
+            Kind::Method => "method",
+            Kind::Constant => "const",
+            Kind::Variable => "variable",
+            Kind::Field => "field",
+            Kind::Macro => "macro",
+            Kind::Other => "",
+        }
+    }
+
+    /// Whether the functions found inside are its methods.
+    fn has_methods(self) -> bool {
+        matches!(self, Kind::Class | Kind::Struct | Kind::Interface | Kind::Trait | Kind::Impl)
+    }
+
+    fn from_lsp(kind: SymbolKind) -> Self {
+        match kind {
+            SymbolKind::FILE | SymbolKind::MODULE | SymbolKind::NAMESPACE | SymbolKind::PACKAGE => Kind::Module,
+            SymbolKind::CLASS | SymbolKind::OBJECT => Kind::Class,
+            SymbolKind::STRUCT => Kind::Struct,
+            SymbolKind::ENUM => Kind::Enum,
+            SymbolKind::INTERFACE => Kind::Interface,
+            SymbolKind::TYPE_PARAMETER => Kind::Type,
+            SymbolKind::FUNCTION => Kind::Function,
+            SymbolKind::METHOD | SymbolKind::CONSTRUCTOR => Kind::Method,
+            SymbolKind::CONSTANT | SymbolKind::ENUM_MEMBER => Kind::Constant,
+            SymbolKind::VARIABLE => Kind::Variable,
+            SymbolKind::FIELD | SymbolKind::PROPERTY => Kind::Field,
+            // Markdown servers report headings as strings.
+            SymbolKind::STRING => Kind::Heading,
+            _ => Kind::Other
+        }
+    }
+}
+
+/// Something in the document worth jumping to, a heading or a definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: Kind,
+    /// Line of the document it's on, not of the buffer, folds don't move it.
+    pub line: usize,
+    /// Column in characters where its name starts.
+    pub column: usize,
+    /// How deep it's nested in other symbols.
+    pub depth: usize,
+}
+
+/// How the definitions of a language are spotted, a line at a time.
+struct Grammar {
+    /// Words that can come before a keyword, e.g. `pub` or `async`.
+    modifiers: &'static [&'static str],
+    keywords: &'static [(&'static str, Kind)],
+    /// Whether `int main(...) {` style lines with a return type in front are functions.
+    typed_functions: bool,
+    /// Whether `name(...) {` lines inside classes are methods.
+    methods: bool,
+    /// Whether `const name = (...) => {` and the like are functions.
+    arrow_functions: bool,
+}
+
+const RUST: Grammar = Grammar {
+    modifiers: &["pub", "pub(crate)", "pub(super)", "pub(self)", "async", "unsafe", "extern", "default", "const"],
+    keywords: &[
+        ("fn", Kind::Function),
+        ("struct", Kind::Struct),
+        ("enum", Kind::Enum),
+        ("union", Kind::Struct),
+        ("trait", Kind::Trait),
+        ("impl", Kind::Impl),
+        ("mod", Kind::Module),
+        ("const", Kind::Constant),
+        ("static", Kind::Constant),
+        ("type", Kind::Type),
+        ("macro_rules!", Kind::Macro),
+    ],
+    typed_functions: false,
+    methods: false,
+    arrow_functions: false,
+};
+
+const PYTHON: Grammar = Grammar {
+    modifiers: &["async"],
+    keywords: &[("def", Kind::Function), ("class", Kind::Class)],
+    typed_functions: false,
+    methods: false,
+    arrow_functions: false,
+};
+
+const JAVASCRIPT: Grammar = Grammar {
+    modifiers: &["export", "default", "async", "declare", "abstract", "public", "private", "protected", "static", "readonly", "override", "get", "set"],
+    keywords: &[
+        ("function", Kind::Function),
+        ("function*", Kind::Function),
+        ("class", Kind::Class),
+        ("interface", Kind::Interface),
+        ("type", Kind::Type),
+        ("enum", Kind::Enum),
+        ("namespace", Kind::Module),
+        ("module", Kind::Module),
+    ],
+    typed_functions: false,
+    methods: true,
+    arrow_functions: true,
+};
+
+const GO: Grammar = Grammar {
+    modifiers: &[],
+    keywords: &[("func", Kind::Function), ("type", Kind::Type)],
+    typed_functions: false,
+    methods: false,
+    arrow_functions: false,
+};
+
+const C_LIKE: Grammar = Grammar {
+    modifiers: &[
+        "public", "private", "protected", "internal", "static", "final", "abstract", "virtual", "inline",
+        "extern", "export", "sealed", "override", "async", "partial", "typedef", "template", "open",
+        "data", "inner", "suspend", "fileprivate", "mutating", "unsafe", "readonly",
+    ],
+    keywords: &[
+        ("class", Kind::Class),
+        ("struct", Kind::Struct),
+        ("enum", Kind::Enum),
+        ("union", Kind::Struct),
+        ("interface", Kind::Interface),
+        ("protocol", Kind::Interface),
+        ("record", Kind::Class),
+        ("extension", Kind::Impl),
+        ("namespace", Kind::Module),
+        ("fun", Kind::Function),
+        ("func", Kind::Function),
+    ],
+    typed_functions: true,
+    methods: true,
+    arrow_functions: false,
+};
+
+/// Words that start statements, never the name of a function.
+const STATEMENTS: [&str; 16] = [
+    "if", "else", "for", "foreach", "while", "do", "switch", "case", "catch", "return",
+    "throw", "new", "sizeof", "using", "lock", "await",
+];
+
+/// Finds the symbols of `text`, parsing it the way files like the one at `path` are written.
+pub fn parse(text: &str, path: Option<&Path>) -> Vec<Symbol> {
+    let lines: Vec<&str> = text.lines().collect();
+
+    let grammar = match file_type(path).as_str() {
+        "md" | "markdown" => return headings(&lines),
+        "rs" => RUST,
+        "py" | "pyi" => PYTHON,
+        "js" | "mjs" | "cjs" | "jsx" | "ts" | "tsx" | "mts" | "cts" => JAVASCRIPT,
+        "go" => GO,
+        "c" | "h" | "cpp" | "cc" | "hpp" | "cxx" | "java" | "cs" | "kt" | "kts" | "swift" | "scala" | "dart" => C_LIKE,
+        _ => return Vec::new()
+    };
+
+    definitions(&lines, &grammar)
+}
+
+/// The `#` and underlined headings of a Markdown document, outside of code blocks.
+fn headings(lines: &[&str]) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    let mut fence: Option<&str> = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+
+        if let Some(marker) = ["```", "~~~"].into_iter().find(|marker| trimmed.starts_with(marker)) {
+            match fence {
+                Some(open) if open == marker => fence = None,
+                None => fence = Some(marker),
+                _ => {}
+            }
+
+            continue;
+        }
+
+        // Code and indented code blocks can have lines that look like headings.
+        if fence.is_some() || line.starts_with("    ") || line.starts_with('\t') {
+            continue;
+        }
+
+        let level = trimmed.chars().take_while(|character| *character == '#').count();
+        let rest = &trimmed[level..];
+
+        if (1..=6).contains(&level) && (rest.is_empty() || rest.starts_with(' ')) {
+            let name = rest.trim().trim_end_matches('#').trim_end();
+
+            if !name.is_empty() {
+                symbols.push(Symbol {
+                    name: name.to_owned(),
+                    kind: Kind::Heading,
+                    line: index,
+                    column: line.chars().count() - rest.trim_start().chars().count(),
+                    depth: level - 1,
+                });
+            }
+
+            continue;
+        }
+
+        // A line of `=` or `-` underlines the paragraph line above it.
+        let underline = match trimmed.trim_end() {
+            underline if !underline.is_empty() && underline.chars().all(|character| character == '=') => Some(0),
+            underline if underline.len() >= 2 && underline.chars().all(|character| character == '-') => Some(1),
+            _ => None
+        };
+
+        let above = index.checked_sub(1).map(|above| lines[above].trim()).unwrap_or("");
+        let above_is_heading = symbols.last().is_some_and(|symbol| symbol.line + 1 == index);
+
+        if let Some(depth) = underline.filter(|_| !above.is_empty() && !above_is_heading && !above.starts_with(['-', '*', '>'])) {
+            symbols.push(Symbol {
+                name: above.to_owned(),
+                kind: Kind::Heading,
+                line: index - 1,
+                column: lines[index - 1].chars().count() - lines[index - 1].trim_start().chars().count(),
+                depth,
+            });
+        }
+    }
+
+    symbols
+}
+
+/// The definitions of a source file, nested by how far they're indented.
+fn definitions(lines: &[&str], grammar: &Grammar) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    // The indentation and kind of the symbols the current line could be inside of.
+    let mut scopes: Vec<(usize, Kind)> = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() || is_comment(trimmed) {
+            continue;
+        }
+
+        let indentation = line.chars().count() - line.trim_start().chars().count();
+
+        while scopes.last().is_some_and(|(scope, _)| *scope >= indentation) {
+            scopes.pop();
+        }
+
+        let parent = scopes.last().map(|(_, kind)| *kind);
+        let next = lines[index + 1..].iter().map(|line| line.trim()).find(|line| !line.is_empty());
+
+        let Some((kind, name, offset)) = definition(trimmed, grammar, parent, next) else {
+            continue;
+        };
+
+        symbols.push(Symbol {
+            name,
+            kind,
+            line: index,
+            column: indentation + offset,
+            depth: scopes.len(),
+        });
+
+        scopes.push((indentation, kind));
+    }
+
+    symbols
+}
+
+/// Comments, and the Rust attributes and C preprocessor lines that look like them.
+fn is_comment(line: &str) -> bool {
+    ["//", "/*", "*", "#", "--"].into_iter().any(|start| line.starts_with(start))
+}
+
+/// The kind, name and column of the name of what `line` defines, if anything.
+fn definition(line: &str, grammar: &Grammar, parent: Option<Kind>, next: Option<&str>) -> Option<(Kind, String, usize)> {
+    let words: Vec<(usize, &str)> = words(line);
+
+    for (position, &(start, word)) in words.iter().enumerate() {
+        let following = words.get(position + 1).map(|(_, word)| *word);
+        let keyword = grammar.keywords.iter().find(|(keyword, _)| is_keyword(word, keyword));
+
+        match keyword {
+            // `const fn` and `unsafe impl`, the keyword that counts comes later.
+            Some(_) if following.is_some_and(|following| grammar.keywords.iter().any(|(keyword, _)| is_keyword(following, keyword))) => continue,
+            Some((keyword, kind)) => return named(line, start + keyword.len(), *kind, parent),
+            None if grammar.modifiers.contains(&word) || word.starts_with('"') => continue,
+            None => break
+        }
+    }
+
+    if grammar.arrow_functions {
+        if let Some(found) = arrow_function(line) {
+            return Some(found);
+        }
+    }
+
+    function(line, grammar, parent, next)
+}
+
+/// Whether `word` is `keyword`, or it with generic parameters stuck to it like `impl<T>`.
+fn is_keyword(word: &str, keyword: &str) -> bool {
+    word.strip_prefix(keyword).is_some_and(|rest| rest.is_empty() || rest.starts_with('<'))
+}
+
+/// The name after a keyword that ends at `after`.
+fn named(line: &str, after: usize, kind: Kind, parent: Option<Kind>) -> Option<(Kind, String, usize)> {
+    let rest = line[after..].trim_start();
+
+    let (kind, rest) = match (kind, rest.starts_with('(')) {
+        // Go's methods have their receiver in front of the name.
+        (Kind::Function, true) => (Kind::Method, rest[rest.find(')')? + 1..].trim_start()),
+        (Kind::Function, false) if parent.is_some_and(Kind::has_methods) => (Kind::Method, rest),
+        _ => (kind, rest)
+    };
+
+    let name = match kind {
+        // An impl is named after the whole header, `impl Display for Editor`.
+        Kind::Impl => {
+            let header = rest.split(['{', ';']).next().unwrap_or("");
+            let header = header.split(" where").next().unwrap_or("").trim();
+
+            strip_generics(header)
+        },
+        _ => {
+            let length = rest.find(|character| !is_identifier(character)).unwrap_or(rest.len());
+            &rest[..length]
+        }
+    };
+
+    // `type Name struct` in Go says what the type is after the name.
+    let kind = match kind {
+        Kind::Type => match rest[name.len()..].split_whitespace().next() {
+            Some("struct") => Kind::Struct,
+            Some("interface") => Kind::Interface,
+            _ => kind
+        },
+        _ => kind
+    };
+
+    match name.is_empty() {
+        true => None,
+        false => Some((kind, name.to_owned(), line[..offset(line, name)].chars().count()))
+    }
+}
+
+/// Leaves out the generic parameters that come right after `impl`.
+fn strip_generics(header: &str) -> &str {
+    if !header.starts_with('<') {
+        return header;
+    }
+
+    let mut depth = 0;
+
+    for (index, character) in header.char_indices() {
+        match character {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return header[index + 1..].trim_start();
+                }
+            },
+            _ => {}
+        }
+    }
+
+    header
+}
+
+/// `const name = (...) =>` and `let name = function`.
+fn arrow_function(line: &str) -> Option<(Kind, String, usize)> {
+    let rest = line.strip_prefix("export ").unwrap_or(line);
+    let declaration = ["const ", "let ", "var "].into_iter().find_map(|keyword| rest.strip_prefix(keyword))?;
+
+    let (name, value) = declaration.split_once('=')?;
+    let name = name.split(':').next().unwrap_or("").trim();
+    let value = value.trim_start();
+
+    let is_function = value.contains("=>") || value.starts_with("function") || value.starts_with("async");
+
+    match is_function && !name.is_empty() && name.chars().all(is_identifier) {
+        true => Some((Kind::Function, name.to_owned(), line[..offset(line, name)].chars().count())),
+        false => None
+    }
+}
+
+/// `int main(void) {` and, inside classes, `render() {` style functions.
+fn function(line: &str, grammar: &Grammar, parent: Option<Kind>, next: Option<&str>) -> Option<(Kind, String, usize)> {
+    if !grammar.typed_functions && !grammar.methods {
+        return None;
+    }
+
+    if line.starts_with(['}', ')', '.', '@']) || line.ends_with(';') || line.ends_with(',') {
+        return None;
+    }
+
+    // The body starts on this line or the next.
+    let has_body = line.ends_with('{') || (line.ends_with(')') && next.is_some_and(|next| next.starts_with('{')));
+
+    if !has_body {
+        return None;
+    }
+
+    let paren = line.find('(')?;
+    let before = line[..paren].trim_end();
+
+    let name_start = before.rfind(|character: char| !(is_identifier(character) || character == ':' || character == '~'))
+        .map(|index| index + 1)
+        .unwrap_or(0);
+
+    let name = &before[name_start..];
+    let first_word = before.split_whitespace().next().unwrap_or("");
+
+    if name.is_empty() || name.starts_with(':') || before.contains(['=', '.', '(', '"'])
+        || STATEMENTS.contains(&first_word) || STATEMENTS.contains(&name) {
+        return None;
+    }
+
+    let typed = name_start > 0;
+    let in_class = parent.is_some_and(Kind::has_methods);
+
+    let kind = match (typed, in_class) {
+        (_, true) if grammar.methods => Kind::Method,
+        (true, false) if grammar.typed_functions => Kind::Function,
+        _ => return None
+    };
+
+    Some((kind, name.to_owned(), line[..name_start].chars().count()))
+}
+
+/// The words of `line` split at whitespace, with the byte they start at.
+fn words(line: &str) -> Vec<(usize, &str)> {
+    line.split_whitespace()
+        .map(|word| (offset(line, word), word))
+        .collect()
+}
+
+/// Where `part`, a slice of `line`, starts in it in bytes.
+fn offset(line: &str, part: &str) -> usize {
+    part.as_ptr() as usize - line.as_ptr() as usize
+}
+
+fn is_identifier(character: char) -> bool {
+    character.is_alphanumeric() || character == '_' || character == '$'
+}
+
+/// The symbols a language server sent, with their columns counted in characters of `text`.
+pub fn from_lsp(response: DocumentSymbolResponse, text: &str) -> Vec<Symbol> {
+    let lines: Vec<&str> = text.lines().collect();
+
+    let column = |line: u32, character: u32| {
+        let mut units = 0;
+
+        lines.get(line as usize)
+            .map(|text| text.chars().take_while(|next| {
+                units += next.len_utf16();
+                units <= character as usize
+            }).count())
+            .unwrap_or(0)
+    };
+
+    let mut symbols = Vec::new();
+
+    match response {
+        DocumentSymbolResponse::Flat(information) => {
+            for symbol in information {
+                let start = symbol.location.range.start;
+
+                symbols.push(Symbol {
+                    name: symbol.name,
+                    kind: Kind::from_lsp(symbol.kind),
+                    line: start.line as usize,
+                    column: column(start.line, start.character),
+                    depth: usize::from(symbol.container_name.is_some_and(|name| !name.is_empty())),
+                });
+            }
+        },
+        DocumentSymbolResponse::Nested(nested) => {
+            fn flatten(symbols: Vec<DocumentSymbol>, depth: usize, column: &dyn Fn(u32, u32) -> usize, output: &mut Vec<Symbol>) {
+                for symbol in symbols {
+                    let start = symbol.selection_range.start;
+
+                    output.push(Symbol {
+                        name: symbol.name,
+                        kind: Kind::from_lsp(symbol.kind),
+                        line: start.line as usize,
+                        column: column(start.line, start.character),
+                        depth,
+                    });
+
+                    flatten(symbol.children.unwrap_or_default(), depth + 1, column, output);
+                }
+            }
+
+            flatten(nested, 0, &column, &mut symbols);
+        }
+    }
+
+    symbols.sort_by_key(|symbol| (symbol.line, symbol.column));
+    symbols
+}
+
+/// The symbols fuzzily matching `query`, best first, or all of them in order when it's empty.
+pub fn matches<'a>(symbols: &'a [Symbol], query: &str) -> Vec<&'a Symbol> {
+    let query = query.trim();
+
+    if query.is_empty() {
+        return symbols.iter().collect();
+    }
+
+    let mut matches: Vec<(i64, &Symbol)> = symbols.iter()
+        .filter_map(|symbol| fuzzy_score(query, &symbol.name).map(|score| (score, symbol)))
+        .collect();
+
+    matches.sort_by(|(a, a_symbol), (b, b_symbol)| b.cmp(a).then_with(|| a_symbol.line.cmp(&b_symbol.line)));
+
+    matches.into_iter().map(|(_, symbol)| symbol).collect()
+}
+
+/// The innermost symbol `line` of the document is in, the last one starting at or above it.
+pub fn current(symbols: &[Symbol], line: usize) -> Option<usize> {
+    symbols.iter().rposition(|symbol| symbol.line <= line)
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Position, Range};
+
+    use super::*;
+
+    /// Kind, name and depth of each symbol.
+    fn outline(text: &str, path: &str) -> Vec<(Kind, String, usize)> {
+        parse(text, Some(Path::new(path))).into_iter()
+            .map(|symbol| (symbol.kind, symbol.name, symbol.depth))
+            .collect()
+    }
+
+    fn symbol(kind: Kind, name: &str, depth: usize) -> (Kind, String, usize) {
+        (kind, name.to_owned(), depth)
+    }
+
+    #[test]
+    fn rust_definitions_nest_by_indentation() {
+        let text = "\
+pub struct Editor {
+    path: PathBuf,
+}
+
+// fn commented() {}
+impl<T: Clone> fmt::Display for Wrapper<T> where T: Debug {
+    pub(crate) const fn new() -> Self {
+        let inner = 1;
+    }
+}
+
+macro_rules! twice {
+    () => {};
+}";
+
+        assert_eq!(outline(text, "main.rs"), [
+            symbol(Kind::Struct, "Editor", 0),
+            symbol(Kind::Impl, "fmt::Display for Wrapper<T>", 0),
+            symbol(Kind::Method, "new", 1),
+            symbol(Kind::Macro, "twice", 0),
+        ]);
+
+        let symbols = parse(text, Some(Path::new("main.rs")));
+        assert_eq!((symbols[2].line, symbols[2].column), (6, 24));
+    }
+
+    #[test]
+    fn other_languages_have_grammars_of_their_own() {
+        assert_eq!(outline("class Shape:\n    async def area(self):\n        pass\ndef main():\n    pass", "shapes.py"), [
+            symbol(Kind::Class, "Shape", 0),
+            symbol(Kind::Method, "area", 1),
+            symbol(Kind::Function, "main", 0),
+        ]);
+
+        assert_eq!(outline("export const add = (a, b) => a + b;\nclass View {\n  render() {\n    if (x) {\n    }\n  }\n}", "view.ts"), [
+            symbol(Kind::Function, "add", 0),
+            symbol(Kind::Class, "View", 0),
+            symbol(Kind::Method, "render", 1),
+        ]);
+
+        assert_eq!(outline("type Point struct {\n}\nfunc (p Point) Norm() float64 {\n}", "point.go"), [
+            symbol(Kind::Struct, "Point", 0),
+            symbol(Kind::Method, "Norm", 0),
+        ]);
+
+        assert_eq!(outline("static int main(void)\n{\n    return 0;\n}", "main.c"), [symbol(Kind::Function, "main", 0)]);
+        assert!(parse("fn main() {}", Some(Path::new("notes.txt"))).is_empty());
+    }
+
+    #[test]
+    fn markdown_headings_skip_code_blocks() {
+        let text = "\
+# Title
+Intro
+=====
+```
+# not a heading
+```
+## Section ##
+    # indented code
+Sub
+---
+- item
+---";
+
+        assert_eq!(outline(text, "README.md"), [
+            symbol(Kind::Heading, "Title", 0),
+            symbol(Kind::Heading, "Intro", 0),
+            symbol(Kind::Heading, "Section", 1),
+            symbol(Kind::Heading, "Sub", 1),
+        ]);
+    }
+
+    #[test]
+    fn server_symbols_are_flattened_in_order() {
+        #[allow(deprecated)]
+        let symbol = |name: &str, kind, line, character, children| DocumentSymbol {
+            name: name.to_owned(),
+            detail: None,
+            kind,
+            tags: None,
+            deprecated: None,
+            range: Range::new(Position::new(line, 0), Position::new(line + 1, 0)),
+            selection_range: Range::new(Position::new(line, character), Position::new(line, character + 1)),
+            children,
+        };
+
+        let response = DocumentSymbolResponse::Nested(vec![
+            symbol("later", SymbolKind::FUNCTION, 2, 3, None),
+            symbol("Thing", SymbolKind::STRUCT, 0, 9, Some(vec![symbol("field", SymbolKind::FIELD, 1, 4, None)])),
+        ]);
+
+        let symbols = from_lsp(response, "/* 😀 */ Thing {\n    field\n😀 later");
+
+        let summary: Vec<_> = symbols.iter().map(|symbol| (symbol.name.as_str(), symbol.kind, symbol.column, symbol.depth)).collect();
+
+        assert_eq!(summary, [
+            ("Thing", Kind::Struct, 8, 0),
+            ("field", Kind::Field, 4, 1),
+            ("later", Kind::Function, 2, 0),
+        ]);
+    }
+
+    #[test]
+    fn the_current_symbol_is_the_last_one_above() {
+        let symbols = parse("fn one() {\n}\n\nfn two() {\n    body();\n}", Some(Path::new("main.rs")));
+
+        assert_eq!(current(&symbols, 0), Some(0));
+        assert_eq!(current(&symbols, 2), Some(0));
+        assert_eq!(current(&symbols, 4), Some(1));
+        assert_eq!(current(&[], 4), None);
+
+        let names: Vec<_> = matches(&symbols, "tw").into_iter().map(|symbol| symbol.name.as_str()).collect();
+        assert_eq!(names, ["two"]);
+        assert_eq!(matches(&symbols, " ").len(), 2);
+    }
+}
\ No newline at end of file
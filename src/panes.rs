@@ -0,0 +1,484 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use iced::widget::pane_grid::{self, Axis, Direction, PaneGrid, ResizeEvent, Split};
+use iced::widget::text_editor::{Action, Content};
+use iced::{Element, Rectangle, Size};
+
+use crate::buffer::{self, Position};
+use crate::files::FileFormat;
+use crate::folding::Folds;
+use crate::history::History;
+use crate::indent::Indentation;
+use crate::viewport::Viewport;
+
+/// Space between the panes of a split, and what can be grabbed to resize them.
+pub const PANE_SPACING: f32 = 6.0;
+
+/// A view of a file in a split of the editor, with a cursor and scroll of its own.
+///
+/// The pane that has focus keeps its cursor and scroll in the editor itself,
+/// they're swapped in here while another pane has it.
+pub struct Pane {
+    /// Tells the panes' widgets apart, e.g. the scrollable they sit in when wrapping is off.
+    pub id: usize,
+    /// The file shown, panes showing the same one share its [`Document`].
+    pub path: Option<PathBuf>,
+    pub content: Content,
+    pub viewport: Viewport,
+    pub horizontal_offset: f32,
+}
+
+impl Pane {
+    pub fn new(id: usize, path: Option<PathBuf>) -> Self {
+        Self {
+            id,
+            path,
+            content: Content::new(),
+            viewport: Viewport::new(),
+            horizontal_offset: 0.0,
+        }
+    }
+
+    /// A pane showing the file at `path` as it is in `content`, with the cursor where it is there.
+    pub fn showing(id: usize, path: Option<PathBuf>, content: &Content) -> Self {
+        let mut pane = Self::new(id, path);
+
+        buffer::set_text(&mut pane.content, &buffer::text(content));
+        buffer::move_to(&mut pane.content, buffer::cursor(content));
+
+        pane
+    }
+
+    /// Makes the same [`Change`] another pane made to the file, only the lines
+    /// that differ are replaced. The cursor and scroll stay on their lines,
+    /// moved along when lines are added or removed above them.
+    pub fn follow(&mut self, document: &Content, change: Change) {
+        let cursor = buffer::cursor(&self.content);
+        let top = self.viewport.visible_lines(&self.content).first().map_or(0, |visible| visible.line);
+
+        let lines: Vec<String> = (change.start..change.new_end)
+            .filter_map(|line| document.line(line).map(|text| text.to_string()))
+            .collect();
+
+        let (from, to, text) = change.replacement(&self.content, lines.join("\n"));
+
+        buffer::replace(&mut self.content, from, to, &text);
+        buffer::move_to(&mut self.content, Position::new(change.follow(cursor.line), cursor.column));
+
+        // Moving the cursor starts at the top of the document and scrolls it into view, then back to where it was.
+        self.viewport.scroll_to_top();
+        self.viewport.reveal_cursor(&self.content);
+        self.scroll_to(change.follow(top));
+    }
+
+    /// Scrolls so `line` is at the top.
+    pub fn scroll_to(&mut self, line: usize) {
+        let action = Action::Scroll { lines: self.viewport.rows_to(&self.content, line) as i32 };
+
+        self.content.perform(action.clone());
+        self.viewport.follow(&action, &self.content);
+    }
+}
+
+/// The splits of the editor and the files their panes show.
+///
+/// The focused pane's cursor and scroll are the ones in the editor, while its
+/// slot holds whatever was there before.
+pub struct Panes {
+    grid: pane_grid::State<Pane>,
+    focused: pane_grid::Pane,
+    next_id: usize,
+    /// The files shown in the other panes, when they aren't the focused pane's.
+    documents: Vec<Document>,
+}
+
+impl Panes {
+    pub fn new() -> Self {
+        let (grid, focused) = pane_grid::State::new(Pane::new(0, None));
+
+        Self {
+            grid,
+            focused,
+            next_id: 1,
+            documents: Vec::new(),
+        }
+    }
+
+    pub fn focused(&self) -> pane_grid::Pane {
+        self.focused
+    }
+
+    /// Tells the focused pane's widgets apart from the others'.
+    pub fn focused_id(&self) -> usize {
+        self.grid.get(self.focused).map_or(0, |pane| pane.id)
+    }
+
+    pub fn is_split(&self) -> bool {
+        self.grid.len() > 1
+    }
+
+    /// A pane that doesn't have the focus, its cursor and scroll are its own.
+    pub fn other_mut(&mut self, pane: pane_grid::Pane) -> Option<&mut Pane> {
+        match pane == self.focused {
+            true => None,
+            false => self.grid.get_mut(pane)
+        }
+    }
+
+    /// Records the file the focused pane shows.
+    pub fn set_path(&mut self, path: Option<PathBuf>) {
+        if let Some(pane) = self.grid.get_mut(self.focused) {
+            pane.path = path;
+        }
+    }
+
+    /// Splits the focused pane, the new one shows the file at `path` as it is in `content`.
+    pub fn split(&mut self, axis: Axis, path: Option<PathBuf>, content: &Content) -> Option<pane_grid::Pane> {
+        let pane = Pane::showing(self.next_id, path, content);
+        self.next_id += 1;
+
+        self.grid.split(axis, self.focused, pane).map(|(pane, _)| pane)
+    }
+
+    /// Moves the focus to `pane`, swapping the editor's cursor and scroll for
+    /// its own. The file it shows, `None` when it had the focus already.
+    pub fn focus(&mut self, pane: pane_grid::Pane, content: &mut Content, viewport: &mut Viewport, horizontal_offset: &mut f32) -> Option<Option<PathBuf>> {
+        let path = self.other_mut(pane)?.path.clone();
+
+        // The focused pane's cursor and scroll go back to its slot, then the other's come out of theirs.
+        self.swap(self.focused, content, viewport, horizontal_offset);
+        self.swap(pane, content, viewport, horizontal_offset);
+        self.focused = pane;
+
+        Some(path)
+    }
+
+    /// Closes the focused pane and moves the focus to its sibling, the file
+    /// that shows. `None` when it's the only pane.
+    pub fn close(&mut self, content: &mut Content, viewport: &mut Viewport, horizontal_offset: &mut f32) -> Option<Option<PathBuf>> {
+        let (_, sibling) = self.grid.close(self.focused)?;
+
+        // The closed pane's cursor and scroll are the editor's, the sibling's take their place.
+        self.swap(sibling, content, viewport, horizontal_offset);
+        self.focused = sibling;
+
+        Some(self.grid.get(sibling).and_then(|pane| pane.path.clone()))
+    }
+
+    fn swap(&mut self, pane: pane_grid::Pane, content: &mut Content, viewport: &mut Viewport, horizontal_offset: &mut f32) {
+        let Some(pane) = self.grid.get_mut(pane) else {
+            return;
+        };
+
+        std::mem::swap(content, &mut pane.content);
+        std::mem::swap(viewport, &mut pane.viewport);
+        std::mem::swap(horizontal_offset, &mut pane.horizontal_offset);
+    }
+
+    pub fn adjacent(&self, direction: Direction) -> Option<pane_grid::Pane> {
+        self.grid.adjacent(self.focused, direction)
+    }
+
+    pub fn resize(&mut self, split: Split, ratio: f32) {
+        self.grid.resize(split, ratio);
+    }
+
+    /// Where each pane is in an editor area of `size`.
+    pub fn regions(&self, size: Size) -> BTreeMap<pane_grid::Pane, Rectangle> {
+        self.grid.layout().pane_regions(PANE_SPACING, size)
+    }
+
+    /// Makes the same changes to the other panes showing the file at `path`,
+    /// now `content` in the focused one.
+    pub fn sync(&mut self, path: &Option<PathBuf>, content: &Content) {
+        let focused = self.focused;
+        let mut change = None;
+
+        for (pane, state) in self.grid.iter_mut() {
+            if *pane == focused || state.path != *path {
+                continue;
+            }
+
+            // The panes of a file all have the same text, so what changed is the same for each.
+            if let Some(change) = *change.get_or_insert_with(|| Change::between(&state.content, content)) {
+                state.follow(content, change);
+            }
+        }
+    }
+
+    /// Whether a pane other than the focused one shows the file at `path`.
+    pub fn shown_elsewhere(&self, path: &Option<PathBuf>) -> bool {
+        self.grid.iter().any(|(pane, state)| *pane != self.focused && state.path == *path)
+    }
+
+    /// The text of the file at `path` in a pane that doesn't have the focus.
+    pub fn text(&self, path: &Option<PathBuf>) -> Option<String> {
+        self.grid.iter()
+            .find(|(pane, state)| **pane != self.focused && state.path == *path)
+            .map(|(_, state)| buffer::text(&state.content))
+    }
+
+    /// The file at `path` if it's set aside, `None` when it's the focused pane's.
+    pub fn document(&self, path: &Option<PathBuf>) -> Option<&Document> {
+        self.documents.iter().find(|document| document.path == *path)
+    }
+
+    pub fn documents_mut(&mut self) -> impl Iterator<Item = &mut Document> {
+        self.documents.iter_mut()
+    }
+
+    /// Sets a file aside while other panes show it.
+    pub fn park(&mut self, document: Document) {
+        self.documents.push(document);
+    }
+
+    /// Takes the file at `path` back out, for the focused pane to edit.
+    pub fn take_document(&mut self, path: &Option<PathBuf>) -> Option<Document> {
+        let index = self.documents.iter().position(|document| document.path == *path)?;
+
+        Some(self.documents.remove(index))
+    }
+
+    /// Whether the files set aside have no unsaved changes.
+    pub fn all_saved(&self) -> bool {
+        self.documents.iter().all(|document| document.saved)
+    }
+
+    /// Lays the panes out with `pane_view`, which gets whether a pane has the
+    /// focus and the file it shows when that's set aside.
+    pub fn view<'a, Message: 'a>(
+        &'a self,
+        pane_view: impl Fn(pane_grid::Pane, &'a Pane, bool, Option<&'a Document>) -> Element<'a, Message>,
+        on_click: impl Fn(pane_grid::Pane) -> Message + 'a,
+        on_resize: impl Fn(ResizeEvent) -> Message + 'a,
+    ) -> Element<'a, Message> {
+        PaneGrid::new(&self.grid, |pane, state, _| {
+            let focused = pane == self.focused;
+            let document = match focused {
+                true => None,
+                false => self.document(&state.path)
+            };
+
+            pane_grid::Content::new(pane_view(pane, state, focused, document))
+        })
+        .spacing(PANE_SPACING)
+        .on_click(on_click)
+        .on_resize(PANE_SPACING, on_resize)
+        .into()
+    }
+}
+
+/// What belongs to a file rather than to the panes showing it, set aside
+/// while the focused pane shows another one.
+pub struct Document {
+    pub path: Option<PathBuf>,
+    pub format: FileFormat,
+    pub saved: bool,
+    pub indentation: Indentation,
+    pub history: History,
+    pub folds: Folds,
+    /// The version the language server last heard of.
+    pub version: i32,
+    /// Diagnostics as the language server sent them, in lines of the document.
+    pub diagnostics: Vec<lsp_types::Diagnostic>,
+}
+
+/// The lines that differ between two versions of a text, taking whatever is
+/// between the lines both start and end with as what changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Change {
+    /// The first line that differs.
+    pub start: usize,
+    /// Where the changed lines end in the old text.
+    pub old_end: usize,
+    /// Where the lines that replace them end in the new text.
+    pub new_end: usize,
+}
+
+impl Change {
+    /// What changed from `old` to `new`, `None` if they're the same.
+    pub fn between(old: &Content, new: &Content) -> Option<Self> {
+        let (old_length, new_length) = (old.line_count(), new.line_count());
+
+        let start = old.lines().zip(new.lines()).take_while(|(old, new)| **old == **new).count();
+
+        if start == old_length && start == new_length {
+            return None;
+        }
+
+        let end = old_length.min(new_length) - start;
+
+        let suffix = (1..=end)
+            .take_while(|back| old.line(old_length - back).as_deref() == new.line(new_length - back).as_deref())
+            .count();
+
+        Some(Self { start, old_end: old_length - suffix, new_end: new_length - suffix })
+    }
+
+    /// Maps a line of the old text to where it is in the new one.
+    pub fn follow(&self, line: usize) -> usize {
+        match line >= self.old_end {
+            // Below the change it moves along with it.
+            true => (line + self.new_end).saturating_sub(self.old_end),
+            // Inside it the line stays put, as far as there still are lines.
+            false if line >= self.start => line.min(self.new_end.saturating_sub(1).max(self.start)),
+            false => line
+        }
+    }
+
+    /// The range of `old` to replace, and what with, given the lines that replace the changed ones.
+    fn replacement(&self, old: &Content, lines: String) -> (Position, Position, String) {
+        let end_of = |line: usize| Position::new(line, buffer::line_length(old, line));
+
+        match (self.start < self.old_end, self.start < self.new_end) {
+            (true, true) => (Position::new(self.start, 0), end_of(self.old_end - 1), lines),
+            // Lines are removed along with their line break, the one before the last line's
+            // as it has none.
+            (true, false) => match self.old_end < old.line_count() {
+                true => (Position::new(self.start, 0), Position::new(self.old_end, 0), String::new()),
+                false => (end_of(self.start - 1), end_of(self.old_end - 1), String::new())
+            },
+            (false, _) => match self.start < old.line_count() {
+                true => (Position::new(self.start, 0), Position::new(self.start, 0), lines + "\n"),
+                false => (end_of(self.start - 1), end_of(self.start - 1), "\n".to_string() + &lines)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(old: &str, new: &str) -> Option<Change> {
+        Change::between(&Content::with_text(old), &Content::with_text(new))
+    }
+
+    fn document(path: &str, saved: bool) -> Document {
+        Document {
+            path: Some(PathBuf::from(path)),
+            format: FileFormat::default(),
+            saved,
+            indentation: Indentation::default(),
+            history: History::default(),
+            folds: Folds::default(),
+            version: 0,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn parked_documents_are_taken_back_by_path() {
+        let mut panes = Panes::new();
+
+        panes.park(document("a.rs", true));
+        panes.park(document("b.rs", true));
+
+        assert!(panes.document(&Some(PathBuf::from("b.rs"))).is_some());
+        assert!(panes.take_document(&Some(PathBuf::from("a.rs"))).is_some());
+        assert!(panes.take_document(&Some(PathBuf::from("a.rs"))).is_none());
+        assert!(panes.document(&Some(PathBuf::from("b.rs"))).is_some());
+    }
+
+    #[test]
+    fn unsaved_parked_documents_are_found() {
+        let mut panes = Panes::new();
+        assert!(panes.all_saved());
+
+        panes.park(document("a.rs", true));
+        assert!(panes.all_saved());
+
+        panes.park(document("b.rs", false));
+        assert!(!panes.all_saved());
+    }
+
+    #[test]
+    fn a_single_pane_shows_nothing_elsewhere() {
+        let mut panes = Panes::new();
+        panes.set_path(Some(PathBuf::from("a.rs")));
+
+        assert!(!panes.is_split());
+        assert!(!panes.shown_elsewhere(&Some(PathBuf::from("a.rs"))));
+        assert_eq!(panes.text(&Some(PathBuf::from("a.rs"))), None);
+        assert!(panes.other_mut(panes.focused()).is_none());
+    }
+
+    #[test]
+    fn same_text_has_no_change() {
+        assert_eq!(change("a\nb\nc", "a\nb\nc"), None);
+    }
+
+    #[test]
+    fn finds_the_lines_between_the_same_start_and_end() {
+        assert_eq!(change("a\nb\nc", "a\nx\nc"), Some(Change { start: 1, old_end: 2, new_end: 2 }));
+        assert_eq!(change("a\nb\nc", "a\nb\nx\ny\nc"), Some(Change { start: 2, old_end: 2, new_end: 4 }));
+        assert_eq!(change("a\nb\nc\nd", "a\nd"), Some(Change { start: 1, old_end: 3, new_end: 1 }));
+        assert_eq!(change("a\nb", "a\nb\nc"), Some(Change { start: 2, old_end: 2, new_end: 3 }));
+    }
+
+    #[test]
+    fn repeated_lines_are_not_counted_twice() {
+        assert_eq!(change("a\na", "a\na\na"), Some(Change { start: 2, old_end: 2, new_end: 3 }));
+    }
+
+    #[test]
+    fn lines_follow_the_change() {
+        let inserted = Change { start: 2, old_end: 2, new_end: 4 };
+
+        assert_eq!(inserted.follow(1), 1);
+        assert_eq!(inserted.follow(2), 4);
+        assert_eq!(inserted.follow(5), 7);
+
+        let removed = Change { start: 1, old_end: 3, new_end: 1 };
+
+        assert_eq!(removed.follow(0), 0);
+        assert_eq!(removed.follow(2), 1);
+        assert_eq!(removed.follow(3), 1);
+        assert_eq!(removed.follow(4), 2);
+    }
+
+    #[test]
+    fn replaces_only_the_changed_lines() {
+        let old = Content::with_text("one\ntwo\nthree");
+        let edited = Change { start: 1, old_end: 2, new_end: 2 };
+
+        assert_eq!(
+            edited.replacement(&old, String::from("TWO")),
+            (Position::new(1, 0), Position::new(1, 3), String::from("TWO"))
+        );
+
+        let inserted = Change { start: 1, old_end: 1, new_end: 2 };
+
+        assert_eq!(
+            inserted.replacement(&old, String::from("new")),
+            (Position::new(1, 0), Position::new(1, 0), String::from("new\n"))
+        );
+    }
+
+    #[test]
+    fn lines_at_the_end_go_with_the_line_break_before_them() {
+        let old = Content::with_text("one\ntwo\nthree");
+
+        let appended = Change { start: 3, old_end: 3, new_end: 4 };
+
+        assert_eq!(
+            appended.replacement(&old, String::from("four")),
+            (Position::new(2, 5), Position::new(2, 5), String::from("\nfour"))
+        );
+
+        let removed = Change { start: 1, old_end: 3, new_end: 1 };
+
+        assert_eq!(
+            removed.replacement(&old, String::new()),
+            (Position::new(0, 3), Position::new(2, 5), String::new())
+        );
+
+        let removed = Change { start: 0, old_end: 1, new_end: 0 };
+
+        assert_eq!(
+            removed.replacement(&old, String::new()),
+            (Position::new(0, 0), Position::new(1, 0), String::new())
+        );
+    }
+}
\ No newline at end of file
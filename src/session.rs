@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::{config::config_dir, folding::FoldRange, GFEError};
+
+/// What's remembered about files between runs, stored as TOML next to the config.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// Keyed by the path of the file.
+    pub files: BTreeMap<String, FileSession>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileSession {
+    pub folds: Vec<FoldRange>,
+}
+
+impl Session {
+    /// The folds left closed in the file at `path`.
+    pub fn folds(&self, path: &Path) -> Option<&[FoldRange]> {
+        self.files.get(&path.display().to_string()).map(|file| file.folds.as_slice())
+    }
+
+    /// Keeps the folds of the file at `path` for when it's opened again, whether
+    /// there was anything to change.
+    pub fn remember(&mut self, path: &Path, folds: Vec<FoldRange>) -> bool {
+        let key = path.display().to_string();
+
+        match folds.is_empty() {
+            true => self.files.remove(&key).is_some(),
+            false => {
+                self.files.insert(key, FileSession { folds });
+                true
+            }
+        }
+    }
+}
+
+pub async fn load_session() -> Result<Session, GFEError> {
+    let path = config_dir().ok_or(GFEError::NoConfigDir)?.join("session.toml");
+
+    let contents = match fs::read_to_string(&path).await {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Session::default()),
+        Err(error) => return Err(GFEError::IO(error.kind()))
+    };
+
+    toml::from_str(&contents).map_err(|error| GFEError::InvalidConfig(error.message().to_owned()))
+}
+
+pub async fn save_session(session: Session) -> Result<(), GFEError> {
+    let directory = config_dir().ok_or(GFEError::NoConfigDir)?;
+
+    let contents = toml::to_string_pretty(&session)
+        .map_err(|error| GFEError::InvalidConfig(error.to_string()))?;
+
+    fs::create_dir_all(&directory)
+        .await
+        .map_err(|error| error.kind())
+        .map_err(GFEError::IO)?;
+
+    fs::write(directory.join("session.toml"), contents)
+        .await
+        .map_err(|error| error.kind())
+        .map_err(GFEError::IO)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_survive_being_saved_as_toml() {
+        let mut session = Session::default();
+
+        session.files.insert(String::from("/project/main.rs"), FileSession {
+            folds: vec![FoldRange { start: 3, end: 10 }, FoldRange { start: 4, end: 6 }]
+        });
+
+        let saved = toml::to_string_pretty(&session).unwrap();
+        let loaded: Session = toml::from_str(&saved).unwrap();
+
+        assert_eq!(loaded.files["/project/main.rs"].folds, session.files["/project/main.rs"].folds);
+    }
+
+    #[test]
+    fn files_without_folds_are_forgotten() {
+        let mut session = Session::default();
+        let path = Path::new("/project/main.rs");
+
+        assert!(!session.remember(path, Vec::new()));
+        assert!(session.remember(path, vec![FoldRange { start: 1, end: 2 }]));
+        assert_eq!(session.folds(path), Some([FoldRange { start: 1, end: 2 }].as_slice()));
+
+        assert!(session.remember(path, Vec::new()));
+        assert_eq!(session.folds(path), None);
+        assert!(session.files.is_empty());
+    }
+
+    #[test]
+    fn missing_fields_fall_back_to_nothing() {
+        let loaded: Session = toml::from_str("[files.\"a.rs\"]").unwrap();
+
+        assert!(loaded.files["a.rs"].folds.is_empty());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,531 @@
+use std::{collections::{BTreeMap, HashMap}, iter::Peekable, ops::Range, path::Path, str::Chars};
+
+use chrono::Local;
+use iced::widget::text_editor::Content;
+use serde::Deserialize;
+use tokio::fs;
+
+use crate::{buffer::{self, Position}, completion::{self, Item, Provider}, config::{config_dir, file_type}, indent::{self, Indentation}, GFEError};
+
+/// A piece of boilerplate that gets typed out when its prefix is expanded.
+///
+/// The body uses the same syntax as VS Code snippets: `$1` or `${1}` is a tab
+/// stop, `${1:text}` a tab stop with a placeholder, a number used more than
+/// once mirrors what's typed into the first one, `$0` is where the cursor ends
+/// up and `$TM_FILENAME` or `${CURRENT_YEAR:default}` are variables.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Snippet {
+    pub prefix: String,
+    pub body: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// User snippets keyed by file type, loaded from `snippets/<file type>.toml`
+/// in the config directory. Snippets in `snippets/global.toml` apply everywhere.
+#[derive(Debug, Clone, Default)]
+pub struct Snippets {
+    file_types: HashMap<String, Vec<Snippet>>,
+}
+
+impl Snippets {
+    /// The snippets available in the file at `path`.
+    pub fn for_file(&self, path: Option<&Path>) -> Vec<&Snippet> {
+        let specific = self.file_types.get(&file_type(path)).into_iter().flatten();
+        let global = self.file_types.get("global").into_iter().flatten();
+
+        specific.chain(global).collect()
+    }
+
+    /// The snippet `prefix` expands to in the file at `path`.
+    pub fn find(&self, path: Option<&Path>, prefix: &str) -> Option<&Snippet> {
+        self.for_file(path).into_iter().find(|snippet| snippet.prefix == prefix)
+    }
+}
+
+/// Offers the snippets of a file as completions.
+pub struct SnippetCompletions<'a>(pub Vec<&'a Snippet>);
+
+impl Provider for SnippetCompletions<'_> {
+    fn complete(&self, _content: &Content, _prefix: &str) -> Vec<Item> {
+        self.0.iter()
+            .map(|snippet| Item {
+                label: snippet.prefix.clone(),
+                insert: snippet.body.clone(),
+                detail: snippet.description.clone(),
+                source: completion::Source::Snippet,
+            })
+            .collect()
+    }
+}
+
+pub async fn load_snippets() -> Result<Snippets, GFEError> {
+    let directory = config_dir().ok_or(GFEError::NoConfigDir)?.join("snippets");
+
+    let mut entries = match fs::read_dir(&directory).await {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Snippets::default()),
+        Err(error) => return Err(GFEError::IO(error.kind()))
+    };
+
+    let mut file_types = HashMap::new();
+
+    while let Some(entry) = entries.next_entry().await.map_err(|error| GFEError::IO(error.kind()))? {
+        let path = entry.path();
+
+        let Some(name) = path.file_stem().and_then(|name| name.to_str()).map(str::to_lowercase) else {
+            continue;
+        };
+
+        if path.extension().and_then(|extension| extension.to_str()) != Some("toml") {
+            continue;
+        }
+
+        let contents = fs::read_to_string(&path)
+            .await
+            .map_err(|error| GFEError::IO(error.kind()))?;
+
+        let snippets: BTreeMap<String, Snippet> = toml::from_str(&contents)
+            .map_err(|error| GFEError::InvalidConfig(format!("{}: {}", path.display(), error.message())))?;
+
+        file_types.insert(name, snippets.into_values().collect());
+    }
+
+    Ok(Snippets { file_types })
+}
+
+/// Values of the variables snippets can use for the file at `path`.
+pub fn variables(path: Option<&Path>) -> HashMap<&'static str, String> {
+    let now = Local::now();
+    let mut variables = HashMap::from([
+        ("CURRENT_YEAR", now.format("%Y").to_string()),
+        ("CURRENT_YEAR_SHORT", now.format("%y").to_string()),
+        ("CURRENT_MONTH", now.format("%m").to_string()),
+        ("CURRENT_MONTH_NAME", now.format("%B").to_string()),
+        ("CURRENT_DATE", now.format("%d").to_string()),
+        ("CURRENT_DAY_NAME", now.format("%A").to_string()),
+        ("CURRENT_HOUR", now.format("%H").to_string()),
+        ("CURRENT_MINUTE", now.format("%M").to_string()),
+        ("CURRENT_SECOND", now.format("%S").to_string()),
+    ]);
+
+    if let Some(path) = path {
+        let name = |part: Option<&std::ffi::OsStr>| part.map(|part| part.to_string_lossy().into_owned()).unwrap_or_default();
+
+        variables.insert("TM_FILENAME", name(path.file_name()));
+        variables.insert("TM_FILENAME_BASE", name(path.file_stem()));
+        variables.insert("TM_DIRECTORY", name(path.parent().map(Path::as_os_str)));
+        variables.insert("TM_FILEPATH", path.display().to_string());
+    }
+
+    variables
+}
+
+/// A snippet body with its variables filled in.
+struct Expansion {
+    text: String,
+    /// Length of `text` in characters.
+    length: usize,
+    /// Character ranges of the tab stops in `text` by their number.
+    stops: BTreeMap<usize, Vec<Range<usize>>>,
+    /// Lines after the first get this in front so the snippet lines up with where it was expanded.
+    indent: String,
+    /// What a `\t` in the body turns into.
+    unit: String,
+}
+
+impl Expansion {
+    fn push(&mut self, character: char) {
+        match character {
+            '\n' => {
+                self.text.push('\n');
+                self.text.push_str(&self.indent);
+                self.length += 1 + self.indent.chars().count();
+            },
+            '\t' => {
+                self.text.push_str(&self.unit);
+                self.length += self.unit.chars().count();
+            },
+            character => {
+                self.text.push(character);
+                self.length += 1;
+            }
+        }
+    }
+
+    fn push_str(&mut self, text: &str) {
+        text.chars().for_each(|character| self.push(character));
+    }
+
+    /// Forgets everything from character `length` on.
+    fn truncate(&mut self, length: usize) {
+        let index = self.text.char_indices().nth(length).map(|(index, _)| index).unwrap_or(self.text.len());
+
+        self.text.truncate(index);
+        self.length = length;
+
+        for ranges in self.stops.values_mut() {
+            ranges.retain(|range| range.start < length);
+        }
+
+        self.stops.retain(|_, ranges| !ranges.is_empty());
+    }
+
+    /// Parses a snippet body up to the end or, when `nested`, up to the `}` closing a placeholder.
+    fn parse(&mut self, characters: &mut Peekable<Chars>, variables: &HashMap<&str, String>, nested: bool) {
+        while let Some(character) = characters.next() {
+            match character {
+                '\\' => match characters.peek() {
+                    Some('$' | '}' | '\\') => {
+                        let escaped = characters.next().unwrap_or('\\');
+                        self.push(escaped);
+                    },
+                    _ => self.push('\\')
+                },
+                '}' if nested => return,
+                '$' => self.parse_dollar(characters, variables),
+                character => self.push(character)
+            }
+        }
+    }
+
+    fn parse_dollar(&mut self, characters: &mut Peekable<Chars>, variables: &HashMap<&str, String>) {
+        let braced = characters.next_if_eq(&'{').is_some();
+
+        let name: String = std::iter::from_fn(|| characters.next_if(|character| character.is_ascii_alphanumeric() || *character == '_'))
+            .collect();
+
+        if name.is_empty() {
+            self.push('$');
+
+            if braced {
+                self.push('{');
+            }
+
+            return;
+        }
+
+        let start = self.length;
+        let has_default = braced && characters.next_if_eq(&':').is_some();
+
+        if has_default {
+            self.parse(characters, variables, true);
+        } else if braced {
+            characters.next_if_eq(&'}');
+        }
+
+        match name.parse::<usize>() {
+            Ok(number) => {
+                self.stops.entry(number).or_default().push(start..self.length);
+            },
+            Err(_) => {
+                if let Some(value) = variables.get(name.as_str()) {
+                    self.truncate(start);
+                    self.push_str(value);
+                }
+            }
+        }
+    }
+
+    /// Copies the placeholder of every stop into the other places it's used.
+    fn fill_mirrors(&mut self) {
+        let mut mirrors: Vec<(usize, usize, String)> = Vec::new();
+
+        for (number, ranges) in &self.stops {
+            let Some(placeholder) = ranges.iter().find(|range| !range.is_empty()) else {
+                continue;
+            };
+
+            let text: String = self.text.chars().skip(placeholder.start).take(placeholder.len()).collect();
+
+            for (index, range) in ranges.iter().enumerate() {
+                if range.is_empty() {
+                    mirrors.push((*number, index, text.clone()));
+                }
+            }
+        }
+
+        // Back to front, so the offsets of the mirrors still to fill stay valid.
+        mirrors.sort_by_key(|(number, index, _)| std::cmp::Reverse(self.stops[number][*index].start));
+
+        for (number, index, text) in mirrors {
+            let at = self.stops[&number][index].start;
+            let length = text.chars().count();
+            let byte = self.text.char_indices().nth(at).map(|(byte, _)| byte).unwrap_or(self.text.len());
+
+            self.text.insert_str(byte, &text);
+            self.length += length;
+
+            for ranges in self.stops.values_mut() {
+                shift(ranges, at, length as isize);
+            }
+
+            self.stops.get_mut(&number).unwrap()[index] = at..at + length;
+        }
+    }
+
+    /// The stops in the order Tab visits them, `$0` last.
+    fn into_stops(self, offset: usize) -> Vec<Vec<Range<usize>>> {
+        let length = self.length;
+        let mut stops = self.stops;
+        let last = stops.remove(&0).unwrap_or_else(|| std::iter::once(length..length).collect());
+
+        stops.into_values()
+            .chain(std::iter::once(last))
+            .map(|ranges| ranges.into_iter().map(|range| range.start + offset..range.end + offset).collect())
+            .collect()
+    }
+}
+
+/// Moves the ranges after `at` by `delta` characters, ranges around `at` grow or shrink.
+fn shift(ranges: &mut [Range<usize>], at: usize, delta: isize) {
+    let moved = |offset: usize| (offset as isize + delta).max(0) as usize;
+
+    for range in ranges {
+        if range.start >= at {
+            range.start = moved(range.start);
+            range.end = moved(range.end);
+        } else if range.end > at {
+            range.end = moved(range.end);
+        }
+    }
+}
+
+/// A snippet that was just expanded, Tab and Shift+Tab move between its stops.
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// Character ranges of every stop in the document, in the order they're visited.
+    /// The first range of a stop is where it's typed, the others mirror it.
+    stops: Vec<Vec<Range<usize>>>,
+    current: usize,
+    /// Length of the document when the current stop was entered.
+    length: usize,
+}
+
+/// Replaces the text between `from` and `to` with the expanded `body` and
+/// selects its first tab stop.
+///
+/// Returns the session to move through the other stops with, `None` if
+/// there's nothing left to visit.
+pub fn expand(content: &mut Content, from: Position, to: Position, body: &str, variables: &HashMap<&str, String>, indentation: &Indentation) -> Option<Session> {
+    let line = content.line(from.line).map(|line| line.to_string()).unwrap_or_default();
+
+    let mut expansion = Expansion {
+        text: String::new(),
+        length: 0,
+        stops: BTreeMap::new(),
+        indent: indent::leading_whitespace(&line).to_owned(),
+        unit: indentation.unit(),
+    };
+
+    expansion.parse(&mut body.chars().peekable(), variables, false);
+    expansion.fill_mirrors();
+
+    let offset = buffer::offset(content, from);
+    let text = expansion.text.clone();
+
+    buffer::replace(content, from, to, &text);
+
+    let mut session = Session {
+        stops: expansion.into_stops(offset),
+        current: 0,
+        length: 0,
+    };
+
+    session.enter(content);
+
+    match session.stops.len() > 1 {
+        true => Some(session),
+        false => None
+    }
+}
+
+impl Session {
+    /// Whether leaving the current stop copies what was typed into other places.
+    pub fn has_mirrors(&self) -> bool {
+        self.stops.get(self.current).is_some_and(|ranges| ranges.len() > 1)
+    }
+
+    /// Moves on to the next stop, returns `false` once the last one was reached.
+    pub fn next(&mut self, content: &mut Content) -> bool {
+        self.leave(content);
+        self.current += 1;
+        self.enter(content);
+
+        self.current + 1 < self.stops.len()
+    }
+
+    /// Goes back to the previous stop.
+    pub fn previous(&mut self, content: &mut Content) {
+        self.leave(content);
+        self.current = self.current.saturating_sub(1);
+        self.enter(content);
+    }
+
+    /// Selects the current stop so typing replaces its placeholder.
+    fn enter(&mut self, content: &mut Content) {
+        let Some(range) = self.stops.get(self.current).and_then(|ranges| ranges.first()).cloned() else {
+            return;
+        };
+
+        let from = buffer::position_at(content, range.start);
+        let to = buffer::position_at(content, range.end);
+
+        match range.is_empty() {
+            true => buffer::move_to(content, from),
+            false => buffer::select(content, from, to)
+        }
+
+        self.length = buffer::length(content);
+    }
+
+    /// Works out how much was typed into the current stop and updates its mirrors.
+    fn leave(&mut self, content: &mut Content) {
+        let Some(primary) = self.stops.get(self.current).and_then(|ranges| ranges.first()).cloned() else {
+            return;
+        };
+
+        let delta = buffer::length(content) as isize - self.length as isize;
+
+        self.shift(primary.end, delta, (self.current, 0));
+
+        let primary = &mut self.stops[self.current][0];
+        primary.end = (primary.end as isize + delta).max(primary.start as isize) as usize;
+
+        let primary = primary.clone();
+        let text = buffer::text_between(
+            content,
+            buffer::position_at(content, primary.start),
+            buffer::position_at(content, primary.end)
+        );
+        let length = text.chars().count();
+
+        let mut mirrors: Vec<usize> = (1..self.stops[self.current].len()).collect();
+        mirrors.sort_by_key(|index| std::cmp::Reverse(self.stops[self.current][*index].start));
+
+        for index in mirrors {
+            let range = self.stops[self.current][index].clone();
+
+            buffer::replace(
+                content,
+                buffer::position_at(content, range.start),
+                buffer::position_at(content, range.end),
+                &text
+            );
+
+            self.shift(range.end, length as isize - range.len() as isize, (self.current, index));
+            self.stops[self.current][index] = range.start..range.start + length;
+        }
+    }
+
+    /// Shifts every range but the one at `skip` for an edit of `delta` characters at `at`.
+    fn shift(&mut self, at: usize, delta: isize, skip: (usize, usize)) {
+        for (stop, ranges) in self.stops.iter_mut().enumerate() {
+            for (index, range) in ranges.iter_mut().enumerate() {
+                if (stop, index) != skip {
+                    shift(std::slice::from_mut(range), at, delta);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expansion(body: &str, variables: &[(&'static str, &str)]) -> Expansion {
+        let variables = variables.iter().map(|(name, value)| (*name, value.to_string())).collect();
+
+        let mut expansion = Expansion {
+            text: String::new(),
+            length: 0,
+            stops: BTreeMap::new(),
+            indent: String::from("    "),
+            unit: String::from("  "),
+        };
+
+        expansion.parse(&mut body.chars().peekable(), &variables, false);
+        expansion.fill_mirrors();
+        expansion
+    }
+
+    /// Ranges as pairs, which read better in assertions.
+    fn spans(stops: Vec<Vec<Range<usize>>>) -> Vec<Vec<(usize, usize)>> {
+        stops.into_iter()
+            .map(|ranges| ranges.into_iter().map(|range| (range.start, range.end)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn placeholders_become_stops_visited_in_order() {
+        let expansion = expansion("for ${1:item} in ${2:items} {$0}", &[]);
+
+        assert_eq!(expansion.text, "for item in items {}");
+        assert_eq!(spans(expansion.into_stops(10)), [[(14, 18)], [(22, 27)], [(29, 29)]]);
+    }
+
+    #[test]
+    fn the_cursor_ends_after_the_snippet_without_a_final_stop() {
+        let expansion = expansion("fn $1()", &[]);
+
+        assert_eq!(spans(expansion.into_stops(0)), [[(3, 3)], [(5, 5)]]);
+    }
+
+    #[test]
+    fn repeated_stops_mirror_the_placeholder() {
+        let expansion = expansion("let ${1:name} = $1; $1", &[]);
+
+        assert_eq!(expansion.text, "let name = name; name");
+        assert_eq!(expansion.stops[&1], [4..8, 11..15, 17..21]);
+    }
+
+    #[test]
+    fn variables_are_filled_in_or_fall_back_to_their_default() {
+        let expansion = expansion("// $TM_FILENAME ${TM_DIRECTORY:none} ${UNKNOWN:kept} $NOPE", &[("TM_FILENAME", "main.rs")]);
+
+        assert_eq!(expansion.text, "// main.rs none kept ");
+        assert!(expansion.stops.is_empty());
+    }
+
+    #[test]
+    fn escapes_and_lone_dollars_are_kept_literally() {
+        let expansion = expansion(r"costs \$5 or $ or \} or \x", &[]);
+
+        assert_eq!(expansion.text, r"costs $5 or $ or } or \x");
+    }
+
+    #[test]
+    fn new_lines_follow_the_indentation() {
+        let expansion = expansion("if $1 {\n\t$0\n}", &[]);
+
+        assert_eq!(expansion.text, "if  {\n      \n    }");
+        assert_eq!(expansion.stops[&0], vec![12..12]);
+    }
+
+    #[test]
+    fn shifting_grows_the_ranges_around_an_edit() {
+        let mut ranges = [0..2, 2..4, 5..6];
+
+        shift(&mut ranges, 3, 2);
+
+        assert_eq!(ranges, [0..2, 2..6, 7..8]);
+    }
+
+    #[test]
+    fn global_snippets_come_after_the_file_type_ones() {
+        let snippet = |prefix: &str| Snippet { prefix: prefix.to_owned(), body: String::new(), description: None };
+        let snippets = Snippets {
+            file_types: HashMap::from([
+                (String::from("rs"), vec![snippet("fn")]),
+                (String::from("global"), vec![snippet("date"), snippet("fn")]),
+            ])
+        };
+
+        let prefixes: Vec<_> = snippets.for_file(Some(Path::new("main.rs"))).into_iter().map(|snippet| snippet.prefix.as_str()).collect();
+
+        assert_eq!(prefixes, ["fn", "date", "fn"]);
+        assert!(snippets.find(Some(Path::new("notes.txt")), "date").is_some());
+        assert!(snippets.find(Some(Path::new("notes.txt")), "nothing").is_none());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,751 @@
+use std::collections::VecDeque;
+
+use iced::Color;
+use vte::{Params, Perform};
+
+/// Lines kept once they scroll off the top of the screen.
+const SCROLLBACK: usize = 10_000;
+const TAB_WIDTH: usize = 8;
+
+/// How a cell is drawn, `None` colours are the panel's own.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Style {
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    pub bold: bool,
+    pub inverse: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub character: char,
+    pub style: Style,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self { character: ' ', style: Style::default() }
+    }
+}
+
+/// A line and column of the grid, lines counted from the oldest one in the scrollback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Where a drag started and where it is now, in either order.
+pub type Selection = (Position, Position);
+
+/// The lines of a screen and the cursor on it.
+type Screen = (Vec<Vec<Cell>>, (usize, usize));
+
+/// The screen of a VT100 compatible terminal, with what scrolled off it above.
+pub struct Grid {
+    rows: usize,
+    columns: usize,
+    screen: Vec<Vec<Cell>>,
+    scrollback: VecDeque<Vec<Cell>>,
+    /// Row and column on the screen.
+    cursor: (usize, usize),
+    saved_cursor: (usize, usize),
+    /// The last column was written, the next character goes on the next line.
+    wrap_pending: bool,
+    style: Style,
+    /// First and last row that scroll, the whole screen unless a program narrowed it.
+    region: (usize, usize),
+    /// The main screen and cursor while a full screen program has the alternate one.
+    alternate: Option<Screen>,
+    /// Whether `ESC ( 0` switched to the DEC line drawing characters.
+    line_drawing: bool,
+    cursor_visible: bool,
+    application_cursor: bool,
+    bracketed_paste: bool,
+    /// Answers to the program's queries, to be written back to it.
+    responses: Vec<u8>,
+}
+
+impl Grid {
+    pub fn new(rows: usize, columns: usize) -> Self {
+        let (rows, columns) = (rows.max(1), columns.max(1));
+
+        Self {
+            rows,
+            columns,
+            screen: vec![vec![Cell::default(); columns]; rows],
+            scrollback: VecDeque::new(),
+            cursor: (0, 0),
+            saved_cursor: (0, 0),
+            wrap_pending: false,
+            style: Style::default(),
+            region: (0, rows - 1),
+            alternate: None,
+            line_drawing: false,
+            cursor_visible: true,
+            application_cursor: false,
+            bracketed_paste: false,
+            responses: Vec::new(),
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Lines in the scrollback and on the screen.
+    pub fn line_count(&self) -> usize {
+        self.scrollback.len() + self.rows
+    }
+
+    pub fn line(&self, line: usize) -> Option<&[Cell]> {
+        match line.checked_sub(self.scrollback.len()) {
+            Some(row) => self.screen.get(row),
+            None => self.scrollback.get(line)
+        }
+        .map(Vec::as_slice)
+    }
+
+    /// Where the cursor is, `None` while the program hides it.
+    pub fn cursor(&self) -> Option<Position> {
+        self.cursor_visible.then_some(Position {
+            line: self.scrollback.len() + self.cursor.0,
+            column: self.cursor.1,
+        })
+    }
+
+    pub fn application_cursor(&self) -> bool {
+        self.application_cursor
+    }
+
+    pub fn bracketed_paste(&self) -> bool {
+        self.bracketed_paste
+    }
+
+    pub fn take_responses(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.responses)
+    }
+
+    /// The text between two positions, trailing blanks of each line left out.
+    pub fn text(&self, from: Position, to: Position) -> String {
+        let (from, to) = (from.min(to), from.max(to));
+
+        (from.line..=to.line)
+            .filter_map(|line| {
+                let cells = self.line(line)?;
+                let start = match line == from.line {
+                    true => from.column.min(cells.len()),
+                    false => 0
+                };
+                let end = match line == to.line {
+                    true => (to.column + 1).min(cells.len()),
+                    false => cells.len()
+                };
+
+                let text: String = cells[start..end.max(start)].iter().map(|cell| cell.character).collect();
+                Some(text.trim_end().to_string())
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Fits the screen to a new size, lines that no longer fit above the cursor go to the scrollback.
+    pub fn resize(&mut self, rows: usize, columns: usize) {
+        let (rows, columns) = (rows.max(1), columns.max(1));
+
+        if (rows, columns) == (self.rows, self.columns) {
+            return;
+        }
+
+        for line in &mut self.screen {
+            line.resize(columns, Cell::default());
+        }
+
+        let overflow = (self.cursor.0 + 1).saturating_sub(rows);
+
+        for line in self.screen.drain(..overflow).collect::<Vec<_>>() {
+            self.push_scrollback(line);
+        }
+
+        self.screen.resize(rows, vec![Cell::default(); columns]);
+
+        if let Some((screen, _)) = &mut self.alternate {
+            for line in screen.iter_mut() {
+                line.resize(columns, Cell::default());
+            }
+
+            screen.resize(rows, vec![Cell::default(); columns]);
+        }
+
+        for line in &mut self.scrollback {
+            line.resize(columns, Cell::default());
+        }
+
+        self.rows = rows;
+        self.columns = columns;
+        self.region = (0, rows - 1);
+        self.cursor = (self.cursor.0.saturating_sub(overflow).min(rows - 1), self.cursor.1.min(columns - 1));
+        self.wrap_pending = false;
+    }
+
+    fn push_scrollback(&mut self, line: Vec<Cell>) {
+        // A full screen program's lines aren't history.
+        if self.alternate.is_some() {
+            return;
+        }
+
+        if self.scrollback.len() == SCROLLBACK {
+            self.scrollback.pop_front();
+        }
+
+        self.scrollback.push_back(line);
+    }
+
+    /// A line of blanks in the current background.
+    fn blank_line(&self) -> Vec<Cell> {
+        vec![self.blank(); self.columns]
+    }
+
+    fn blank(&self) -> Cell {
+        Cell {
+            character: ' ',
+            style: Style { background: self.style.background, ..Style::default() },
+        }
+    }
+
+    /// Moves the lines of the scrolling region up, the top ones leave the screen.
+    fn scroll_up(&mut self, count: usize) {
+        let (top, bottom) = self.region;
+
+        for _ in 0..count.min(bottom - top + 1) {
+            let line = self.screen.remove(top);
+            self.screen.insert(bottom, self.blank_line());
+
+            if top == 0 {
+                self.push_scrollback(line);
+            }
+        }
+    }
+
+    fn scroll_down(&mut self, count: usize) {
+        let (top, bottom) = self.region;
+
+        for _ in 0..count.min(bottom - top + 1) {
+            self.screen.remove(bottom);
+            self.screen.insert(top, self.blank_line());
+        }
+    }
+
+    fn line_feed(&mut self) {
+        match self.cursor.0 {
+            row if row == self.region.1 => self.scroll_up(1),
+            row if row + 1 < self.rows => self.cursor.0 += 1,
+            _ => {}
+        }
+    }
+
+    fn reverse_line_feed(&mut self) {
+        match self.cursor.0 {
+            row if row == self.region.0 => self.scroll_down(1),
+            row => self.cursor.0 = row.saturating_sub(1)
+        }
+    }
+
+    fn move_to(&mut self, row: usize, column: usize) {
+        self.cursor = (row.min(self.rows - 1), column.min(self.columns - 1));
+        self.wrap_pending = false;
+    }
+
+    /// Blanks the columns `from..to` of a screen row.
+    fn erase(&mut self, row: usize, from: usize, to: usize) {
+        let blank = self.blank();
+
+        if let Some(line) = self.screen.get_mut(row) {
+            let to = to.min(line.len());
+            line[from.min(to)..to].fill(blank);
+        }
+    }
+
+    /// Inserts blank lines at the cursor, or deletes lines there when `insert` isn't set,
+    /// moving the rest of the scrolling region along.
+    fn shift_lines(&mut self, count: usize, insert: bool) {
+        let (row, (top, bottom)) = (self.cursor.0, self.region);
+
+        if row < top || row > bottom {
+            return;
+        }
+
+        for _ in 0..count.min(bottom - row + 1) {
+            match insert {
+                true => {
+                    self.screen.remove(bottom);
+                    self.screen.insert(row, self.blank_line());
+                },
+                false => {
+                    self.screen.remove(row);
+                    self.screen.insert(bottom, self.blank_line());
+                }
+            }
+        }
+
+        self.cursor.1 = 0;
+        self.wrap_pending = false;
+    }
+
+    fn set_alternate_screen(&mut self, on: bool) {
+        match (on, self.alternate.take()) {
+            (true, None) => {
+                let screen = std::mem::replace(&mut self.screen, vec![vec![Cell::default(); self.columns]; self.rows]);
+                self.alternate = Some((screen, self.cursor));
+            },
+            (false, Some((screen, cursor))) => {
+                self.screen = screen;
+                self.move_to(cursor.0, cursor.1);
+            },
+            (_, alternate) => self.alternate = alternate
+        }
+
+        self.region = (0, self.rows - 1);
+    }
+
+    fn reset(&mut self) {
+        let scrollback = std::mem::take(&mut self.scrollback);
+        *self = Self::new(self.rows, self.columns);
+        self.scrollback = scrollback;
+    }
+
+    /// Select Graphic Rendition, colours and attributes of what's printed next.
+    fn set_style(&mut self, params: &Params) {
+        let params: Vec<&[u16]> = params.iter().collect();
+
+        if params.is_empty() {
+            self.style = Style::default();
+        }
+
+        let mut index = 0;
+
+        while let Some(param) = params.get(index) {
+            match param[0] {
+                0 => self.style = Style::default(),
+                1 => self.style.bold = true,
+                22 => self.style.bold = false,
+                7 => self.style.inverse = true,
+                27 => self.style.inverse = false,
+                code @ 30..=37 => self.style.foreground = Some(palette(code as u8 - 30)),
+                code @ 90..=97 => self.style.foreground = Some(palette(code as u8 - 90 + 8)),
+                code @ 40..=47 => self.style.background = Some(palette(code as u8 - 40)),
+                code @ 100..=107 => self.style.background = Some(palette(code as u8 - 100 + 8)),
+                39 => self.style.foreground = None,
+                49 => self.style.background = None,
+                code @ (38 | 48) => {
+                    // Either "38;5;n" or "38:5:n", the same for "38;2;r;g;b".
+                    let values: Vec<u16> = match param.len() {
+                        1 => params[index + 1..].iter().take(4).map(|param| param[0]).collect(),
+                        _ => param[1..].to_vec()
+                    };
+
+                    let (color, used) = match values.as_slice() {
+                        [5, color, ..] => (Some(palette(*color as u8)), 2),
+                        [2, red, green, blue, ..] => (Some(Color::from_rgb8(*red as u8, *green as u8, *blue as u8)), 4),
+                        _ => (None, 0)
+                    };
+
+                    match code {
+                        38 => self.style.foreground = color.or(self.style.foreground),
+                        _ => self.style.background = color.or(self.style.background)
+                    }
+
+                    if param.len() == 1 {
+                        index += used;
+                    }
+                },
+                _ => {}
+            }
+
+            index += 1;
+        }
+    }
+}
+
+impl Perform for Grid {
+    fn print(&mut self, character: char) {
+        if self.wrap_pending {
+            self.cursor.1 = 0;
+            self.line_feed();
+            self.wrap_pending = false;
+        }
+
+        let character = match self.line_drawing {
+            true => line_drawing(character),
+            false => character
+        };
+
+        let (row, column) = self.cursor;
+        self.screen[row][column] = Cell { character, style: self.style };
+
+        match column + 1 < self.columns {
+            true => self.cursor.1 += 1,
+            false => self.wrap_pending = true
+        }
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            // Backspace
+            0x08 => {
+                self.cursor.1 = self.cursor.1.saturating_sub(1);
+                self.wrap_pending = false;
+            },
+            b'\t' => {
+                let column = (self.cursor.1 / TAB_WIDTH + 1) * TAB_WIDTH;
+                self.move_to(self.cursor.0, column);
+            },
+            b'\n' | 0x0B | 0x0C => {
+                self.line_feed();
+                self.wrap_pending = false;
+            },
+            b'\r' => {
+                self.cursor.1 = 0;
+                self.wrap_pending = false;
+            },
+            // Shift out and in, to the line drawing characters and back.
+            0x0E => self.line_drawing = true,
+            0x0F => self.line_drawing = false,
+            _ => {}
+        }
+    }
+
+    fn csi_dispatch(&mut self, params: &Params, intermediates: &[u8], _ignore: bool, action: char) {
+        let values: Vec<usize> = params.iter().map(|param| param[0] as usize).collect();
+        // Missing and zero parameters both mean the default.
+        let param = |index: usize, default: usize| values.get(index).copied().filter(|value| *value > 0).unwrap_or(default);
+        let (row, column) = self.cursor;
+
+        if intermediates.first() == Some(&b'?') {
+            let on = match action {
+                'h' => true,
+                'l' => false,
+                _ => return
+            };
+
+            for mode in &values {
+                match mode {
+                    1 => self.application_cursor = on,
+                    25 => self.cursor_visible = on,
+                    47 | 1047 | 1049 => self.set_alternate_screen(on),
+                    2004 => self.bracketed_paste = on,
+                    _ => {}
+                }
+            }
+
+            return;
+        }
+
+        match action {
+            'A' => self.move_to(row.saturating_sub(param(0, 1)), column),
+            'B' | 'e' => self.move_to(row + param(0, 1), column),
+            'C' | 'a' => self.move_to(row, column + param(0, 1)),
+            'D' => self.move_to(row, column.saturating_sub(param(0, 1))),
+            'E' => self.move_to(row + param(0, 1), 0),
+            'F' => self.move_to(row.saturating_sub(param(0, 1)), 0),
+            'G' | '`' => self.move_to(row, param(0, 1) - 1),
+            'd' => self.move_to(param(0, 1) - 1, column),
+            'H' | 'f' => self.move_to(param(0, 1) - 1, param(1, 1) - 1),
+            'J' => {
+                let rows = match values.first().copied().unwrap_or(0) {
+                    0 => {
+                        self.erase(row, column, self.columns);
+                        row + 1..self.rows
+                    },
+                    1 => {
+                        self.erase(row, 0, column + 1);
+                        0..row
+                    },
+                    3 => {
+                        self.scrollback.clear();
+                        0..0
+                    },
+                    _ => 0..self.rows
+                };
+
+                for row in rows {
+                    self.erase(row, 0, self.columns);
+                }
+            },
+            'K' => match values.first().copied().unwrap_or(0) {
+                0 => self.erase(row, column, self.columns),
+                1 => self.erase(row, 0, column + 1),
+                _ => self.erase(row, 0, self.columns)
+            },
+            'L' => self.shift_lines(param(0, 1), true),
+            'M' => self.shift_lines(param(0, 1), false),
+            '@' | 'P' => {
+                let blank = self.blank();
+                let line = &mut self.screen[row];
+                let count = param(0, 1).min(line.len() - column);
+
+                match action {
+                    '@' => {
+                        line.truncate(line.len() - count);
+                        line.splice(column..column, std::iter::repeat_n(blank, count));
+                    },
+                    _ => {
+                        line.drain(column..column + count);
+                        line.extend(std::iter::repeat_n(blank, count));
+                    }
+                }
+            },
+            'X' => self.erase(row, column, column + param(0, 1)),
+            'S' => self.scroll_up(param(0, 1)),
+            'T' => self.scroll_down(param(0, 1)),
+            'm' => self.set_style(params),
+            'r' => {
+                let (top, bottom) = (param(0, 1) - 1, param(1, self.rows).min(self.rows) - 1);
+
+                if top < bottom {
+                    self.region = (top, bottom);
+                    self.move_to(0, 0);
+                }
+            },
+            's' => self.saved_cursor = self.cursor,
+            'u' => self.move_to(self.saved_cursor.0, self.saved_cursor.1),
+            'n' => match values.first() {
+                Some(5) => self.responses.extend_from_slice(b"\x1b[0n"),
+                Some(6) => self.responses.extend(format!("\x1b[{};{}R", row + 1, column + 1).into_bytes()),
+                _ => {}
+            },
+            // A VT100 with the advanced video option.
+            'c' if intermediates.is_empty() => self.responses.extend_from_slice(b"\x1b[?1;2c"),
+            _ => {}
+        }
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, byte: u8) {
+        match (intermediates, byte) {
+            ([b'('], b'0') => self.line_drawing = true,
+            ([b'('], _) => self.line_drawing = false,
+            ([], b'D') => self.line_feed(),
+            ([], b'E') => {
+                self.cursor.1 = 0;
+                self.line_feed();
+            },
+            ([], b'M') => self.reverse_line_feed(),
+            ([], b'7') => self.saved_cursor = self.cursor,
+            ([], b'8') => self.move_to(self.saved_cursor.0, self.saved_cursor.1),
+            ([], b'c') => self.reset(),
+            _ => {}
+        }
+    }
+}
+
+/// The 16 ANSI colours, then a 6×6×6 cube and a grey ramp like xterm's.
+fn palette(index: u8) -> Color {
+    const ANSI: [(u8, u8, u8); 16] = [
+        (0, 0, 0), (205, 49, 49), (13, 188, 121), (229, 229, 16),
+        (36, 114, 200), (188, 63, 188), (17, 168, 205), (229, 229, 229),
+        (102, 102, 102), (241, 76, 76), (35, 209, 139), (245, 245, 67),
+        (59, 142, 234), (214, 112, 214), (41, 184, 219), (255, 255, 255),
+    ];
+
+    let (red, green, blue) = match index {
+        0..=15 => ANSI[index as usize],
+        16..=231 => {
+            let level = |value: u8| match value {
+                0 => 0,
+                value => 55 + value * 40
+            };
+            let index = index - 16;
+
+            (level(index / 36), level(index / 6 % 6), level(index % 6))
+        },
+        _ => {
+            let grey = 8 + (index - 232) * 10;
+            (grey, grey, grey)
+        }
+    };
+
+    Color::from_rgb8(red, green, blue)
+}
+
+/// What the DEC special graphics set draws for the ASCII it replaces.
+fn line_drawing(character: char) -> char {
+    match character {
+        'j' => '┘',
+        'k' => '┐',
+        'l' => '┌',
+        'm' => '└',
+        'n' => '┼',
+        'q' => '─',
+        't' => '├',
+        'u' => '┤',
+        'v' => '┴',
+        'w' => '┬',
+        'x' => '│',
+        'a' => '▒',
+        '`' => '◆',
+        'f' => '°',
+        'g' => '±',
+        '~' => '·',
+        character => character
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: usize, columns: usize, bytes: &str) -> Grid {
+        let mut grid = Grid::new(rows, columns);
+        feed(&mut grid, bytes);
+        grid
+    }
+
+    fn feed(grid: &mut Grid, bytes: &str) {
+        let mut parser = vte::Parser::new();
+
+        for byte in bytes.bytes() {
+            parser.advance(grid, byte);
+        }
+    }
+
+    /// The text of every line, the scrollback first.
+    fn lines(grid: &Grid) -> Vec<String> {
+        (0..grid.line_count())
+            .map(|line| grid.text(Position { line, column: 0 }, Position { line, column: grid.columns() - 1 }))
+            .collect()
+    }
+
+    fn cursor(grid: &Grid) -> (usize, usize) {
+        grid.cursor().map(|cursor| (cursor.line, cursor.column)).unwrap()
+    }
+
+    #[test]
+    fn prints_and_breaks_lines() {
+        let grid = grid(3, 10, "one\r\ntwo\r\n");
+
+        assert_eq!(lines(&grid), vec!["one", "two", ""]);
+        assert_eq!(cursor(&grid), (2, 0));
+    }
+
+    #[test]
+    fn wraps_after_the_last_column() {
+        let grid = grid(3, 4, "abcdef");
+
+        assert_eq!(lines(&grid), vec!["abcd", "ef", ""]);
+
+        // Writing the last column leaves the cursor there until the next character.
+        let grid = self::grid(3, 4, "abcd");
+
+        assert_eq!(cursor(&grid), (0, 3));
+    }
+
+    #[test]
+    fn lines_scrolled_off_the_top_go_to_the_scrollback() {
+        let grid = grid(2, 10, "one\r\ntwo\r\nthree\r\nfour");
+
+        assert_eq!(grid.line_count(), 4);
+        assert_eq!(lines(&grid), vec!["one", "two", "three", "four"]);
+        assert_eq!(cursor(&grid), (3, 4));
+    }
+
+    #[test]
+    fn moves_the_cursor_and_erases() {
+        let mut grid = grid(3, 10, "hello\r\nworld");
+
+        feed(&mut grid, "\x1b[1;3H\x1b[K");
+        assert_eq!(lines(&grid), vec!["he", "world", ""]);
+
+        feed(&mut grid, "\x1b[2J");
+        assert_eq!(lines(&grid), vec!["", "", ""]);
+        assert_eq!(cursor(&grid), (0, 2));
+    }
+
+    #[test]
+    fn inserts_and_deletes_characters() {
+        let mut grid = grid(1, 10, "abcdef\x1b[1;3H\x1b[2P");
+
+        assert_eq!(lines(&grid), vec!["abef"]);
+
+        feed(&mut grid, "\x1b[1@");
+        assert_eq!(lines(&grid), vec!["ab ef"]);
+    }
+
+    #[test]
+    fn scrolls_only_the_region() {
+        let grid = grid(4, 10, "top\r\na\r\nb\r\nbottom\x1b[2;3r\x1b[3;1H\n");
+
+        assert_eq!(lines(&grid), vec!["top", "b", "", "bottom"]);
+        assert_eq!(grid.line_count(), 4);
+    }
+
+    #[test]
+    fn colours_what_is_printed_next() {
+        let grid = grid(1, 10, "\x1b[1;31ma\x1b[0mb\x1b[38;2;1;2;3mc\x1b[48;5;4md");
+        let cells = grid.line(0).unwrap();
+
+        assert_eq!(cells[0].style, Style { foreground: Some(palette(1)), bold: true, ..Style::default() });
+        assert_eq!(cells[1].style, Style::default());
+        assert_eq!(cells[2].style.foreground, Some(Color::from_rgb8(1, 2, 3)));
+        assert_eq!(cells[3].style.background, Some(palette(4)));
+    }
+
+    #[test]
+    fn the_alternate_screen_keeps_the_main_one() {
+        let mut grid = grid(2, 10, "shell\r\n");
+
+        feed(&mut grid, "\x1b[?1049h\x1b[Hfull\r\nscreen\r\nprogram");
+        assert_eq!(lines(&grid), vec!["screen", "program"]);
+
+        feed(&mut grid, "\x1b[?1049l");
+        assert_eq!(lines(&grid), vec!["shell", ""]);
+        assert_eq!(cursor(&grid), (1, 0));
+    }
+
+    #[test]
+    fn answers_where_the_cursor_is() {
+        let mut grid = grid(3, 10, "ab\r\nc\x1b[6n\x1b[5n");
+
+        assert_eq!(grid.take_responses(), b"\x1b[2;2R\x1b[0n");
+        assert!(grid.take_responses().is_empty());
+    }
+
+    #[test]
+    fn modes_are_remembered() {
+        let grid = grid(1, 10, "\x1b[?1h\x1b[?2004h\x1b[?25l");
+
+        assert!(grid.application_cursor());
+        assert!(grid.bracketed_paste());
+        assert_eq!(grid.cursor(), None);
+    }
+
+    #[test]
+    fn draws_lines_with_the_dec_characters() {
+        let grid = grid(1, 10, "\x1b(0lqk\x1b(Bq");
+
+        assert_eq!(lines(&grid), vec!["┌─┐q"]);
+    }
+
+    #[test]
+    fn selected_text_spans_lines() {
+        let grid = grid(3, 10, "first\r\nsecond\r\nthird");
+
+        let text = grid.text(Position { line: 2, column: 2 }, Position { line: 0, column: 2 });
+
+        assert_eq!(text, "rst\nsecond\nthi");
+    }
+
+    #[test]
+    fn resizing_keeps_the_cursor_line() {
+        let mut grid = grid(3, 10, "one\r\ntwo\r\nthree");
+
+        grid.resize(2, 4);
+
+        assert_eq!(lines(&grid), vec!["one", "two", "thre"]);
+        assert_eq!(grid.line_count(), 3);
+        assert_eq!(cursor(&grid), (2, 3));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,365 @@
+use std::{io::{self, Read, Write}, path::PathBuf, thread};
+
+use iced::{futures::{channel::mpsc, SinkExt, StreamExt}, Subscription};
+use iced::keyboard::{key::Named, Key, Modifiers};
+use portable_pty::{native_pty_system, Child, CommandBuilder, PtySize};
+
+mod grid;
+mod panel;
+mod view;
+
+pub use grid::{Cell, Grid, Position, Selection};
+pub use panel::{Panel, PANEL_HEIGHT};
+pub use view::terminal_view;
+
+use crate::worker::{self, Input};
+
+/// What came out of the pseudo terminal the shell runs in.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The shell is up, keys and the panel's size go to it through the [`Handle`].
+    Connected(Handle),
+    /// Bytes the shell wrote, escape sequences included.
+    Output(Vec<u8>),
+    /// The pseudo terminal couldn't be opened or the shell started.
+    Error(String),
+    /// The shell exited, e.g. after `exit`, and the panel closes with it.
+    Exited,
+}
+
+/// What the editor does to the pseudo terminal.
+#[derive(Debug, Clone)]
+pub enum Request {
+    /// Bytes typed or pasted, as if they came from the keyboard.
+    Input(Vec<u8>),
+    /// The panel changed size, the shell gets told with a SIGWINCH.
+    Resize { rows: u16, columns: u16 },
+}
+
+/// Writes to the pseudo terminal of a running shell.
+pub type Handle = worker::Handle<Request>;
+
+/// The user's shell in the panel below the editor, and what it printed.
+pub struct Terminal {
+    /// Where the shell was started, kept so opening other files doesn't restart it.
+    pub directory: PathBuf,
+    /// Told apart from the shells before it, so each gets a subscription of its own.
+    pub generation: usize,
+    grid: Grid,
+    parser: vte::Parser,
+    handle: Option<Handle>,
+    /// Lines scrolled back from the bottom.
+    scroll: usize,
+    selection: Option<Selection>,
+}
+
+impl Terminal {
+    pub fn new(directory: PathBuf, generation: usize, rows: usize, columns: usize) -> Self {
+        Self {
+            directory,
+            generation,
+            grid: Grid::new(rows, columns),
+            parser: vte::Parser::new(),
+            handle: None,
+            scroll: 0,
+            selection: None,
+        }
+    }
+
+    pub fn subscription(&self) -> Subscription<Event> {
+        spawn(self.directory.clone(), self.generation)
+    }
+
+    pub fn connected(&mut self, handle: Handle) {
+        handle.send(Request::Resize { rows: self.grid.rows() as u16, columns: self.grid.columns() as u16 });
+        self.handle = Some(handle);
+    }
+
+    /// Puts what the shell printed on the grid and answers the questions it asked.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.parser.advance(&mut self.grid, *byte);
+        }
+
+        let responses = self.grid.take_responses();
+
+        if let (false, Some(handle)) = (responses.is_empty(), &self.handle) {
+            handle.send(Request::Input(responses));
+        }
+
+        self.scroll = 0;
+    }
+
+    /// Types into the shell, which brings the scrollback back to the bottom.
+    pub fn write(&mut self, bytes: Vec<u8>) {
+        if let Some(handle) = &self.handle {
+            handle.send(Request::Input(bytes));
+        }
+
+        self.scroll = 0;
+    }
+
+    /// Sends a key to the shell the way a VT100 does, keys it has no bytes for are dropped.
+    pub fn key(&mut self, key: Key, modifiers: Modifiers) {
+        if let Some(bytes) = encode(key, modifiers, self.grid.application_cursor()) {
+            self.write(bytes);
+        }
+    }
+
+    /// Types pasted text, marked as pasted when the shell asked for it.
+    pub fn paste(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\r").replace('\n', "\r");
+
+        let bytes = match self.grid.bracketed_paste() {
+            true => format!("\x1b[200~{text}\x1b[201~"),
+            false => text
+        };
+
+        self.write(bytes.into_bytes());
+    }
+
+    pub fn resize(&mut self, rows: usize, columns: usize) {
+        if (rows.max(1), columns.max(1)) == (self.grid.rows(), self.grid.columns()) {
+            return;
+        }
+
+        self.grid.resize(rows, columns);
+        self.selection = None;
+
+        if let Some(handle) = &self.handle {
+            handle.send(Request::Resize { rows: self.grid.rows() as u16, columns: self.grid.columns() as u16 });
+        }
+    }
+
+    /// Scrolls the scrollback by `lines`, up when positive.
+    pub fn scroll(&mut self, lines: isize) {
+        let limit = self.grid.line_count() - self.grid.rows();
+
+        self.scroll = self.scroll.saturating_add_signed(lines).min(limit);
+    }
+
+    pub fn select(&mut self, selection: Option<Selection>) {
+        self.selection = selection;
+    }
+
+    pub fn selection(&self) -> Option<Selection> {
+        self.selection
+    }
+
+    pub fn selected_text(&self) -> Option<String> {
+        self.selection.map(|(from, to)| self.grid.text(from, to))
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// The line of the grid at the top of the panel.
+    pub fn first_line(&self) -> usize {
+        self.grid.line_count() - self.grid.rows() - self.scroll
+    }
+}
+
+/// Starts the user's shell in `directory`, each `generation` gets a shell of its own.
+pub fn spawn(directory: PathBuf, generation: usize) -> Subscription<Event> {
+    worker::subscription(
+        ("terminal", generation),
+        |mut output| async move { start(directory, &mut output).await },
+        |result| match result {
+            Ok(()) => Event::Exited,
+            Err(error) => Event::Error(format!("Terminal: {error}")),
+        },
+    )
+}
+
+/// Hangs up on the shell when the subscription goes away, it would outlive the editor otherwise.
+struct Shell(Box<dyn Child + Send + Sync>);
+
+impl Drop for Shell {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+    }
+}
+
+async fn start(directory: PathBuf, output: &mut mpsc::Sender<Event>) -> io::Result<()> {
+    let pair = native_pty_system()
+        .openpty(PtySize { rows: 24, cols: 80, pixel_width: 0, pixel_height: 0 })
+        .map_err(other)?;
+
+    let mut command = CommandBuilder::new_default_prog();
+    command.cwd(directory);
+    command.env("TERM", "vt100");
+
+    let _shell = Shell(pair.slave.spawn_command(command).map_err(other)?);
+    drop(pair.slave);
+
+    let mut reader = pair.master.try_clone_reader().map_err(other)?;
+    let mut writer = pair.master.take_writer().map_err(other)?;
+
+    // Reading from the pseudo terminal blocks, so it gets a thread of its own.
+    let (bytes, received) = mpsc::unbounded();
+
+    thread::spawn(move || {
+        let mut buffer = [0; 4096];
+
+        while let Ok(read @ 1..) = reader.read(&mut buffer) {
+            if bytes.unbounded_send(buffer[..read].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let (handle, requests) = Handle::new();
+
+    let _ = output.send(Event::Connected(handle)).await;
+
+    let mut inputs = Box::pin(worker::inputs(received, requests));
+
+    while let Some(input) = inputs.next().await {
+        match input {
+            Input::Output(bytes) => {
+                let _ = output.send(Event::Output(bytes)).await;
+            },
+            Input::Request(Request::Input(bytes)) => {
+                writer.write_all(&bytes)?;
+                writer.flush()?;
+            },
+            Input::Request(Request::Resize { rows, columns }) => {
+                pair.master
+                    .resize(PtySize { rows, cols: columns, pixel_width: 0, pixel_height: 0 })
+                    .map_err(other)?;
+            },
+            Input::Closed => break
+        }
+    }
+
+    Ok(())
+}
+
+fn other(error: impl std::fmt::Display) -> io::Error {
+    io::Error::other(error.to_string())
+}
+
+/// The bytes a VT100 sends for a key, arrows differ once a program switched to cursor key mode.
+fn encode(key: Key, modifiers: Modifiers, application_cursor: bool) -> Option<Vec<u8>> {
+    let arrow = |letter: char| match application_cursor {
+        true => format!("\x1bO{letter}"),
+        false => format!("\x1b[{letter}")
+    };
+
+    let sequence = match key {
+        Key::Named(Named::Enter) => "\r".to_string(),
+        Key::Named(Named::Backspace) => "\x7f".to_string(),
+        Key::Named(Named::Tab) if modifiers.shift() => "\x1b[Z".to_string(),
+        Key::Named(Named::Tab) => "\t".to_string(),
+        Key::Named(Named::Escape) => "\x1b".to_string(),
+        Key::Named(Named::Space) if modifiers.control() => "\0".to_string(),
+        Key::Named(Named::Space) => " ".to_string(),
+        Key::Named(Named::ArrowUp) => arrow('A'),
+        Key::Named(Named::ArrowDown) => arrow('B'),
+        Key::Named(Named::ArrowRight) => arrow('C'),
+        Key::Named(Named::ArrowLeft) => arrow('D'),
+        Key::Named(Named::Home) => arrow('H'),
+        Key::Named(Named::End) => arrow('F'),
+        Key::Named(Named::Insert) => "\x1b[2~".to_string(),
+        Key::Named(Named::Delete) => "\x1b[3~".to_string(),
+        Key::Named(Named::PageUp) => "\x1b[5~".to_string(),
+        Key::Named(Named::PageDown) => "\x1b[6~".to_string(),
+        Key::Named(Named::F1) => "\x1bOP".to_string(),
+        Key::Named(Named::F2) => "\x1bOQ".to_string(),
+        Key::Named(Named::F3) => "\x1bOR".to_string(),
+        Key::Named(Named::F4) => "\x1bOS".to_string(),
+        Key::Character(text) => {
+            let mut characters = text.chars();
+
+            match (characters.next(), characters.next(), modifiers.control()) {
+                // Control clears the top bits, Ctrl+C is 0x03.
+                (Some(character @ ('@'..='_' | 'a'..='z' | '?')), None, true) => match character {
+                    '?' => "\x7f".to_string(),
+                    character => ((character.to_ascii_uppercase() as u8 & 0x1f) as char).to_string()
+                },
+                (_, _, true) => return None,
+                _ => text.to_string()
+            }
+        },
+        _ => return None
+    };
+
+    // Alt sends escape first, which is what shells read as meta.
+    match modifiers.alt() {
+        true => Some([b"\x1b", sequence.as_bytes()].concat()),
+        false => Some(sequence.into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn character(text: &str) -> Key {
+        Key::Character(text.into())
+    }
+
+    #[test]
+    fn sends_named_keys_like_a_vt100() {
+        assert_eq!(encode(Key::Named(Named::Enter), Modifiers::empty(), false), Some(b"\r".to_vec()));
+        assert_eq!(encode(Key::Named(Named::Backspace), Modifiers::empty(), false), Some(b"\x7f".to_vec()));
+        assert_eq!(encode(Key::Named(Named::Tab), Modifiers::SHIFT, false), Some(b"\x1b[Z".to_vec()));
+        assert_eq!(encode(Key::Named(Named::Delete), Modifiers::empty(), false), Some(b"\x1b[3~".to_vec()));
+        assert_eq!(encode(Key::Named(Named::F1), Modifiers::empty(), false), Some(b"\x1bOP".to_vec()));
+    }
+
+    #[test]
+    fn arrows_follow_the_cursor_key_mode() {
+        assert_eq!(encode(Key::Named(Named::ArrowUp), Modifiers::empty(), false), Some(b"\x1b[A".to_vec()));
+        assert_eq!(encode(Key::Named(Named::ArrowUp), Modifiers::empty(), true), Some(b"\x1bOA".to_vec()));
+    }
+
+    #[test]
+    fn control_clears_the_top_bits() {
+        assert_eq!(encode(character("c"), Modifiers::CTRL, false), Some(vec![0x03]));
+        assert_eq!(encode(character("["), Modifiers::CTRL, false), Some(vec![0x1b]));
+        assert_eq!(encode(character("?"), Modifiers::CTRL, false), Some(vec![0x7f]));
+        assert_eq!(encode(Key::Named(Named::Space), Modifiers::CTRL, false), Some(vec![0]));
+        assert_eq!(encode(character("1"), Modifiers::CTRL, false), None);
+    }
+
+    #[test]
+    fn alt_sends_escape_first() {
+        assert_eq!(encode(character("b"), Modifiers::ALT, false), Some(b"\x1bb".to_vec()));
+        assert_eq!(encode(character("é"), Modifiers::empty(), false), Some("é".as_bytes().to_vec()));
+    }
+
+    #[test]
+    fn keys_without_bytes_are_dropped() {
+        assert_eq!(encode(Key::Named(Named::Shift), Modifiers::SHIFT, false), None);
+    }
+
+    #[test]
+    fn scrolling_stays_within_the_scrollback() {
+        let mut terminal = Terminal::new(PathBuf::from("."), 0, 2, 10);
+
+        terminal.feed(b"1\r\n2\r\n3\r\n4");
+        assert_eq!(terminal.first_line(), 2);
+
+        terminal.scroll(10);
+        assert_eq!(terminal.first_line(), 0);
+
+        terminal.scroll(-1);
+        assert_eq!(terminal.first_line(), 1);
+
+        // New output brings it back to the bottom.
+        terminal.feed(b"\r\n5");
+        assert_eq!(terminal.first_line(), 3);
+    }
+
+    #[test]
+    fn selections_copy_the_grid_text() {
+        let mut terminal = Terminal::new(PathBuf::from("."), 0, 2, 10);
+
+        terminal.feed(b"hello");
+        terminal.select(Some((Position { line: 0, column: 1 }, Position { line: 0, column: 3 })));
+
+        assert_eq!(terminal.selected_text().as_deref(), Some("ell"));
+    }
+}
\ No newline at end of file
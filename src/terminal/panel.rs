@@ -0,0 +1,165 @@
+use std::path::PathBuf;
+
+use iced::keyboard::{Key, Modifiers};
+use iced::{theme, widget, Command, Element, Length, Subscription};
+
+use super::{terminal_view, Event, Selection, Terminal};
+use crate::viewport::{CHAR_WIDTH, EDITOR_PADDING, LINE_HEIGHT};
+
+/// Height of the panel, header included.
+pub const PANEL_HEIGHT: f32 = 250.0;
+const HEADER_HEIGHT: f32 = 16.0;
+
+/// The terminal below the editor, the shell keeps running while it's hidden.
+#[derive(Default)]
+pub struct Panel {
+    terminal: Option<Terminal>,
+    shown: bool,
+    /// Keys go to the shell instead of the editor.
+    focused: bool,
+    /// Shells started so far, each one gets a subscription of its own.
+    started: usize,
+}
+
+impl Panel {
+    /// Whether the panel takes up room below the editor.
+    pub fn is_shown(&self) -> bool {
+        self.shown
+    }
+
+    /// Whether keys typed go to the shell.
+    pub fn has_keys(&self) -> bool {
+        self.shown && self.focused && self.terminal.is_some()
+    }
+
+    pub fn focus(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    /// Shows or hides the panel, the first time it's shown a shell is started in `directory`.
+    pub fn toggle(&mut self, directory: impl FnOnce() -> PathBuf) {
+        self.shown = !self.shown;
+        self.focused = self.shown;
+
+        if self.shown && self.terminal.is_none() {
+            self.started += 1;
+            // Sized by `fit` once the editor knows where the panel goes.
+            self.terminal = Some(Terminal::new(directory(), self.started, 1, 1));
+        }
+    }
+
+    pub fn subscription(&self) -> Subscription<Event> {
+        match &self.terminal {
+            Some(terminal) => terminal.subscription(),
+            None => Subscription::none()
+        }
+    }
+
+    /// Handles what the shell did, an error the panel was closed over is given back.
+    pub fn event(&mut self, event: Event) -> Option<String> {
+        match (event, &mut self.terminal) {
+            (Event::Connected(handle), Some(terminal)) => terminal.connected(handle),
+            (Event::Output(bytes), Some(terminal)) => terminal.feed(&bytes),
+            (Event::Error(message), _) => {
+                self.close();
+                return Some(message);
+            },
+            (Event::Exited, _) => self.close(),
+            _ => {}
+        }
+
+        None
+    }
+
+    /// Drops the shell that exited, showing the terminal again starts a new one.
+    fn close(&mut self) {
+        self.terminal = None;
+        self.shown = false;
+        self.focused = false;
+    }
+
+    /// Types a key into the shell, Ctrl+Shift+C and Ctrl+Shift+V copy and paste like other terminals.
+    pub fn key<Message>(
+        &mut self,
+        key: Key,
+        modifiers: Modifiers,
+        on_paste: impl Fn(Option<String>) -> Message + 'static,
+    ) -> Command<Message> {
+        let Some(terminal) = &mut self.terminal else {
+            return Command::none();
+        };
+
+        match key.as_ref() {
+            Key::Character("c" | "C") if modifiers.control() && modifiers.shift() => {
+                match terminal.selected_text() {
+                    Some(text) => iced::clipboard::write(text),
+                    None => Command::none()
+                }
+            },
+            Key::Character("v" | "V") if modifiers.control() && modifiers.shift() => {
+                iced::clipboard::read(on_paste)
+            },
+            _ => {
+                terminal.key(key, modifiers);
+                Command::none()
+            }
+        }
+    }
+
+    pub fn paste(&mut self, text: &str) {
+        if let Some(terminal) = &mut self.terminal {
+            terminal.paste(text);
+        }
+    }
+
+    pub fn scroll(&mut self, lines: isize) {
+        if let Some(terminal) = &mut self.terminal {
+            terminal.scroll(lines);
+        }
+    }
+
+    pub fn select(&mut self, selection: Option<Selection>) {
+        if let Some(terminal) = &mut self.terminal {
+            terminal.select(selection);
+        }
+    }
+
+    /// Sizes the grid for a panel `width` wide, less the space around its text.
+    pub fn fit(&mut self, width: f32) {
+        if let Some(terminal) = &mut self.terminal {
+            let width = width - EDITOR_PADDING * 2.0;
+            let height = PANEL_HEIGHT - HEADER_HEIGHT - 5.0 - EDITOR_PADDING * 2.0;
+
+            terminal.resize((height / LINE_HEIGHT) as usize, (width / CHAR_WIDTH) as usize);
+        }
+    }
+
+    /// The shell with a header to hide it, `None` while the panel is hidden.
+    pub fn view<'a, Message: Clone + 'a>(
+        &'a self,
+        on_hide: Message,
+        on_focus: Message,
+        on_scroll: impl Fn(isize) -> Message + 'a,
+        on_select: impl Fn(Option<Selection>) -> Message + 'a,
+    ) -> Option<Element<'a, Message>> {
+        let terminal = self.terminal.as_ref().filter(|_| self.shown)?;
+
+        let header = widget::row![
+            widget::text("Terminal").size(12),
+            widget::horizontal_space(),
+            widget::button(widget::text("Hide").size(12))
+                .style(theme::Button::Text)
+                .padding(0)
+                .on_press(on_hide)
+        ].height(Length::Fixed(HEADER_HEIGHT));
+
+        let panel = widget::column![
+            header,
+            terminal_view(terminal, self.focused, on_focus, on_scroll, on_select)
+        ]
+        .spacing(5)
+        .height(Length::Fixed(PANEL_HEIGHT));
+
+        Some(panel.into())
+    }
+}
\ No newline at end of file
@@ -0,0 +1,283 @@
+use iced::alignment::{Horizontal, Vertical};
+use iced::mouse;
+use iced::{Color, Element, Font, Length, Pixels, Point, Rectangle, Size};
+use iced_wgpu::core::text::{self, LineHeight, Shaping};
+use iced_wgpu::core::widget::tree::{self, Tree};
+use iced_wgpu::core::{event, layout, renderer, Clipboard, Event, Layout, Shell, Widget};
+
+use super::{Cell, Position, Selection, Terminal};
+use crate::viewport::{CHAR_WIDTH, EDITOR_PADDING, LINE_HEIGHT, TEXT_SIZE};
+
+/// Lines scrolled for each notch of the mouse wheel.
+const WHEEL_LINES: f32 = 3.0;
+
+/// Draws the grid of a [`Terminal`], clicking focuses it and dragging selects text.
+pub struct TerminalView<'a, Message> {
+    terminal: &'a Terminal,
+    focused: bool,
+    on_focus: Message,
+    on_scroll: Box<dyn Fn(isize) -> Message + 'a>,
+    on_select: Box<dyn Fn(Option<Selection>) -> Message + 'a>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Where the drag that's selecting text started.
+    anchor: Option<Position>,
+}
+
+pub fn terminal_view<'a, Message>(
+    terminal: &'a Terminal,
+    focused: bool,
+    on_focus: Message,
+    on_scroll: impl Fn(isize) -> Message + 'a,
+    on_select: impl Fn(Option<Selection>) -> Message + 'a,
+) -> TerminalView<'a, Message> {
+    TerminalView {
+        terminal,
+        focused,
+        on_focus,
+        on_scroll: Box::new(on_scroll),
+        on_select: Box::new(on_select),
+    }
+}
+
+impl<'a, Message> TerminalView<'a, Message> {
+    /// The grid position under a point of the panel.
+    fn position(&self, bounds: Rectangle, point: Point) -> Position {
+        let grid = self.terminal.grid();
+        let row = ((point.y - bounds.y - EDITOR_PADDING) / LINE_HEIGHT).max(0.0) as usize;
+        let column = ((point.x - bounds.x - EDITOR_PADDING) / CHAR_WIDTH).max(0.0) as usize;
+
+        Position {
+            line: self.terminal.first_line() + row.min(grid.rows() - 1),
+            column: column.min(grid.columns() - 1),
+        }
+    }
+}
+
+impl<'a, Message, Theme, Renderer> Widget<Message, Theme, Renderer> for TerminalView<'a, Message>
+where
+    Renderer: text::Renderer<Font = Font>,
+    Message: Clone,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn size(&self) -> Size<Length> {
+        Size {
+            width: Length::Fill,
+            height: Length::Fill,
+        }
+    }
+
+    fn layout(
+        &self,
+        _tree: &mut Tree,
+        _renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        layout::Node::new(limits.width(Length::Fill).height(Length::Fill).max())
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        _viewport: &Rectangle,
+    ) -> event::Status {
+        let state = tree.state.downcast_mut::<State>();
+        let bounds = layout.bounds();
+
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let Some(point) = cursor.position_over(bounds) else {
+                    return event::Status::Ignored;
+                };
+
+                state.anchor = Some(self.position(bounds, point));
+                shell.publish(self.on_focus.clone());
+                shell.publish((self.on_select)(None));
+                event::Status::Captured
+            },
+            Event::Mouse(mouse::Event::CursorMoved { position }) => match state.anchor {
+                Some(anchor) => {
+                    shell.publish((self.on_select)(Some((anchor, self.position(bounds, position)))));
+                    event::Status::Captured
+                },
+                None => event::Status::Ignored
+            },
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => match state.anchor.take() {
+                Some(_) => event::Status::Captured,
+                None => event::Status::Ignored
+            },
+            Event::Mouse(mouse::Event::WheelScrolled { delta }) if cursor.is_over(bounds) => {
+                let lines = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => y * WHEEL_LINES,
+                    mouse::ScrollDelta::Pixels { y, .. } => y / LINE_HEIGHT
+                };
+
+                shell.publish((self.on_scroll)(lines.round() as isize));
+                event::Status::Captured
+            },
+            _ => event::Status::Ignored
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        _state: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        match cursor.is_over(layout.bounds()) {
+            true => mouse::Interaction::Text,
+            false => mouse::Interaction::default()
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let grid = self.terminal.grid();
+        let first_line = self.terminal.first_line();
+        let selection = self.terminal.selection().map(|(from, to)| (from.min(to), from.max(to)));
+
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds,
+                ..renderer::Quad::default()
+            },
+            Color { a: 0.8, ..Color::BLACK },
+        );
+
+        let cell_bounds = |row: usize, column: usize, width: usize| Rectangle::new(
+            Point::new(bounds.x + EDITOR_PADDING + column as f32 * CHAR_WIDTH, bounds.y + EDITOR_PADDING + row as f32 * LINE_HEIGHT),
+            Size::new(width as f32 * CHAR_WIDTH, LINE_HEIGHT)
+        );
+
+        for row in 0..grid.rows() {
+            let Some(cells) = grid.line(first_line + row) else {
+                break;
+            };
+
+            // Cells that look the same are drawn together.
+            let mut column = 0;
+
+            for run in cells.chunk_by(|left, right| left.style == right.style) {
+                let Cell { style, .. } = run[0];
+                let (foreground, background) = match style.inverse {
+                    true => (style.background.unwrap_or(Color::BLACK), style.foreground.or(Some(Color::WHITE))),
+                    false => (style.foreground.unwrap_or(Color::WHITE), style.background)
+                };
+
+                let area = cell_bounds(row, column, run.len());
+
+                if let Some(background) = background {
+                    renderer.fill_quad(
+                        renderer::Quad {
+                            bounds: area,
+                            ..renderer::Quad::default()
+                        },
+                        background,
+                    );
+                }
+
+                let content: String = run.iter().map(|cell| cell.character).collect();
+
+                if !content.trim().is_empty() {
+                    renderer.fill_text(
+                        text::Text {
+                            content: &content,
+                            bounds: area.size(),
+                            size: Pixels(TEXT_SIZE),
+                            line_height: LineHeight::default(),
+                            font: match style.bold {
+                                true => Font { weight: iced::font::Weight::Bold, ..Font::MONOSPACE },
+                                false => Font::MONOSPACE
+                            },
+                            horizontal_alignment: Horizontal::Left,
+                            vertical_alignment: Vertical::Top,
+                            shaping: Shaping::Basic,
+                        },
+                        area.position(),
+                        foreground,
+                        bounds,
+                    );
+                }
+
+                column += run.len();
+            }
+
+            if let Some((from, to)) = selection {
+                let line = first_line + row;
+
+                if (from.line..=to.line).contains(&line) {
+                    let start = match line == from.line {
+                        true => from.column,
+                        false => 0
+                    };
+                    let end = match line == to.line {
+                        true => to.column + 1,
+                        false => grid.columns()
+                    };
+
+                    renderer.fill_quad(
+                        renderer::Quad {
+                            bounds: cell_bounds(row, start, end.saturating_sub(start)),
+                            ..renderer::Quad::default()
+                        },
+                        Color { a: 0.3, ..Color::from_rgb(0.4, 0.6, 1.0) },
+                    );
+                }
+            }
+        }
+
+        if let Some(cursor) = grid.cursor().filter(|cursor| cursor.line >= first_line && cursor.line < first_line + grid.rows()) {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: cell_bounds(cursor.line - first_line, cursor.column, 1),
+                    ..renderer::Quad::default()
+                },
+                Color {
+                    a: match self.focused {
+                        true => 0.6,
+                        false => 0.25
+                    },
+                    ..Color::WHITE
+                },
+            );
+        }
+    }
+}
+
+impl<'a, Message, Theme, Renderer> From<TerminalView<'a, Message>>
+    for Element<'a, Message, Theme, Renderer>
+where
+    Renderer: text::Renderer<Font = Font>,
+    Message: Clone + 'a,
+{
+    fn from(terminal: TerminalView<'a, Message>) -> Self {
+        Self::new(terminal)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,166 @@
+use std::{process::Stdio, time::Duration};
+
+use iced::futures::join;
+use tokio::{io::AsyncWriteExt, process, time};
+
+use crate::{config::FileTypeConfig, editorconfig::EditorConfig, encoding::LineEnding, GFEError};
+
+/// How long a formatter gets before the save goes on without it.
+const FORMAT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Steps run over the text of a buffer right before it's written to disk.
+#[derive(Debug, Clone, Default)]
+pub struct SavePipeline {
+    pub trim_trailing_whitespace: bool,
+    /// `Some(true)` leaves exactly one final newline, `Some(false)` none at all.
+    pub final_newline: Option<bool>,
+    /// Line ending the file is written with, stray carriage returns get dropped.
+    pub line_ending: Option<LineEnding>,
+    /// Command that gets the text on stdin and prints the formatted text.
+    pub formatter: Option<Vec<String>>,
+}
+
+impl SavePipeline {
+    /// Builds the pipeline for a file, `.editorconfig` wins over the user config.
+    pub fn new(config: &FileTypeConfig, editorconfig: &EditorConfig) -> Self {
+        Self {
+            trim_trailing_whitespace: editorconfig.trim_trailing_whitespace
+                .or(config.trim_trailing_whitespace)
+                .unwrap_or(false),
+            final_newline: editorconfig.insert_final_newline.or(config.final_newline),
+            line_ending: editorconfig.end_of_line.or(config.line_ending),
+            formatter: config.formatter.clone().filter(|command| !command.is_empty()),
+        }
+    }
+
+    /// Runs the text transforms over `text`, the formatter is run separately by [`format`].
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = match self.line_ending {
+            Some(_) => text.replace('\r', ""),
+            None => text.to_owned(),
+        };
+
+        if self.trim_trailing_whitespace {
+            let ends_with_newline = text.ends_with('\n');
+
+            text = text.lines().map(str::trim_end).collect::<Vec<_>>().join("\n");
+
+            if ends_with_newline {
+                text.push('\n');
+            }
+        }
+
+        match self.final_newline {
+            Some(true) => {
+                text.truncate(text.trim_end_matches('\n').len());
+                text.push('\n');
+            },
+            Some(false) => {
+                text.truncate(text.trim_end_matches('\n').len());
+            },
+            None => {}
+        }
+
+        text
+    }
+}
+
+/// Pipes `text` through an external formatter and returns what it printed.
+pub async fn format(command: Vec<String>, text: String) -> Result<String, GFEError> {
+    run_formatter(command, text, FORMAT_TIMEOUT).await
+}
+
+async fn run_formatter(command: Vec<String>, text: String, timeout: Duration) -> Result<String, GFEError> {
+    let (program, args) = command.split_first().ok_or(GFEError::Formatter(String::from("No formatter command")))?;
+
+    let mut child = process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|error| GFEError::Formatter(format!("Couldn't start {program}: {error}")))?;
+
+    let stdin = child.stdin.take();
+
+    // Stdin is written while the output is read, a formatter that fills its stdout
+    // before it has read everything would otherwise wait on us forever.
+    let write = async move {
+        match stdin {
+            Some(mut stdin) => stdin.write_all(text.as_bytes()).await,
+            None => Ok(())
+        }
+    };
+
+    let (written, output) = time::timeout(timeout, async { join!(write, child.wait_with_output()) })
+        .await
+        .map_err(|_| GFEError::Formatter(format!("{program} didn't finish within {} seconds", timeout.as_secs())))?;
+
+    let output = output.map_err(|error| GFEError::Formatter(error.to_string()))?;
+
+    if !output.status.success() {
+        return Err(GFEError::Formatter(String::from_utf8_lossy(&output.stderr).into_owned()));
+    }
+
+    written.map_err(|error| GFEError::Formatter(error.to_string()))?;
+
+    String::from_utf8(output.stdout).map_err(|error| GFEError::Formatter(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trimming_keeps_the_final_newline_as_it_was() {
+        let pipeline = SavePipeline { trim_trailing_whitespace: true, ..SavePipeline::default() };
+
+        assert_eq!(pipeline.apply("a  \nb\t"), "a\nb");
+        assert_eq!(pipeline.apply("a  \nb\t\n"), "a\nb\n");
+    }
+
+    #[test]
+    fn final_newline_and_line_endings() {
+        let add = SavePipeline { final_newline: Some(true), ..SavePipeline::default() };
+        let remove = SavePipeline { final_newline: Some(false), line_ending: Some(LineEnding::Lf), ..SavePipeline::default() };
+
+        assert_eq!(add.apply("a\n\n\n"), "a\n");
+        assert_eq!(add.apply("a"), "a\n");
+        assert_eq!(remove.apply("a\r\nb\n\n"), "a\nb");
+        assert_eq!(SavePipeline::default().apply("a \r\n"), "a \r\n");
+    }
+
+    #[test]
+    fn editorconfig_wins_over_the_config() {
+        let config = FileTypeConfig { trim_trailing_whitespace: Some(true), final_newline: Some(true), ..FileTypeConfig::default() };
+        let editorconfig = EditorConfig { insert_final_newline: Some(false), ..EditorConfig::default() };
+        let pipeline = SavePipeline::new(&config, &editorconfig);
+
+        assert!(pipeline.trim_trailing_whitespace);
+        assert_eq!(pipeline.final_newline, Some(false));
+    }
+
+    #[tokio::test]
+    async fn large_texts_go_through_the_formatter() {
+        let text = "0123456789\n".repeat(200_000);
+        let formatted = format(vec![String::from("cat")], text.clone()).await;
+
+        assert_eq!(formatted.ok(), Some(text));
+    }
+
+    #[tokio::test]
+    async fn formatters_that_hang_time_out() {
+        let command = vec![String::from("sleep"), String::from("5")];
+        let result = run_formatter(command, String::new(), Duration::from_millis(100)).await;
+
+        assert!(matches!(result, Err(GFEError::Formatter(message)) if message.contains("didn't finish")));
+    }
+
+    #[tokio::test]
+    async fn failing_formatters_report_stderr() {
+        let command = vec![String::from("sh"), String::from("-c"), String::from("echo bad input >&2; exit 1")];
+
+        assert!(matches!(format(command, String::from("x")).await, Err(GFEError::Formatter(message)) if message.trim() == "bad input"));
+    }
+}
@@ -0,0 +1,326 @@
+use iced::widget::text_editor::{Action, Content};
+use iced::Point;
+use serde::{Deserialize, Serialize};
+
+use crate::buffer::{self, Position};
+
+/// Text size the `text_editor` falls back to (the renderer default).
+pub const TEXT_SIZE: f32 = 16.0;
+/// Height of a single visual row, iced's default `LineHeight` of 1.3.
+pub const LINE_HEIGHT: f32 = TEXT_SIZE * 1.3;
+/// Rough advance of one glyph of the monospace font.
+pub const CHAR_WIDTH: f32 = TEXT_SIZE * 0.6;
+/// Default padding of the `text_editor` widget.
+pub const EDITOR_PADDING: f32 = 5.0;
+/// Column used by [`Wrap::Column`] when the config doesn't pick one.
+pub const DEFAULT_WRAP_COLUMN: usize = 80;
+
+/// How long lines are wrapped in the editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Wrap {
+    /// Lines are never wrapped, the editor scrolls horizontally instead.
+    Off,
+    /// Lines wrap at the edge of the window.
+    #[default]
+    Window,
+    /// Lines wrap at a fixed column.
+    Column,
+}
+
+impl Wrap {
+    /// The mode that follows this one when cycling through them.
+    pub fn next(self) -> Self {
+        match self {
+            Wrap::Window => Wrap::Column,
+            Wrap::Column => Wrap::Off,
+            Wrap::Off => Wrap::Window,
+        }
+    }
+}
+
+/// Keeps track of which rows the `text_editor` is showing.
+///
+/// The editor doesn't expose its scroll offset so we mirror the
+/// scrolling rules of cosmic-text from the actions we feed it.
+#[derive(Debug, Clone)]
+pub struct Viewport {
+    top: usize,
+    rows: usize,
+    columns: usize,
+    wrap: Wrap,
+    wrap_column: usize,
+}
+
+/// A document line that is (at least partly) on screen.
+#[derive(Debug, Clone, Copy)]
+pub struct VisibleLine {
+    /// Index of the line in the document.
+    pub line: usize,
+    /// Visual row the line starts at, relative to the top of the viewport.
+    pub row: isize,
+    /// How many visual rows the line takes up once wrapped.
+    pub rows: usize,
+}
+
+impl Viewport {
+    pub fn new() -> Self {
+        Self {
+            top: 0,
+            rows: 1,
+            columns: usize::MAX,
+            wrap: Wrap::default(),
+            wrap_column: DEFAULT_WRAP_COLUMN,
+        }
+    }
+
+    /// Updates the size of the text area of the editor in logical pixels.
+    pub fn resize(&mut self, width: f32, height: f32) {
+        let width = width - EDITOR_PADDING * 2.0;
+        let height = height - EDITOR_PADDING * 2.0;
+
+        self.rows = ((height / LINE_HEIGHT) as usize).max(1);
+        self.columns = match self.wrap {
+            Wrap::Off => usize::MAX,
+            Wrap::Window => ((width / CHAR_WIDTH) as usize).max(1),
+            Wrap::Column => self.wrap_column.max(1),
+        };
+    }
+
+    pub fn wrap(&self) -> Wrap {
+        self.wrap
+    }
+
+    pub fn wrap_column(&self) -> usize {
+        self.wrap_column
+    }
+
+    /// How many characters fit on a visual row, `usize::MAX` when lines don't wrap.
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Changes how lines are wrapped, takes effect on the next [`Viewport::resize`].
+    pub fn set_wrap(&mut self, wrap: Wrap, column: usize) {
+        self.wrap = wrap;
+        self.wrap_column = column;
+    }
+
+    /// Mirrors the effect of an [`Action`] that was just performed on `content`.
+    pub fn follow(&mut self, action: &Action, content: &Content) {
+        match action {
+            Action::Scroll { lines } => {
+                let total = self.total_rows(content);
+                let max = total.saturating_sub(self.rows.saturating_sub(1));
+
+                self.top = (self.top as isize + *lines as isize).clamp(0, max as isize) as usize;
+            },
+            Action::Click(_) | Action::Drag(_) => {},
+            Action::Move(_) | Action::Select(_) | Action::SelectWord | Action::SelectLine | Action::Edit(_) => {
+                self.reveal_cursor(content);
+            }
+        }
+    }
+
+    /// Scrolls just enough for the cursor to be visible, like cosmic-text does
+    /// whenever the cursor moves.
+    pub fn reveal_cursor(&mut self, content: &Content) {
+        let row = self.cursor_row(content);
+
+        if row < self.top {
+            self.top = row;
+        } else if row >= self.top + self.rows {
+            self.top = row + 1 - self.rows;
+        }
+    }
+
+    /// Visual row of the cursor, counted from the top of the document.
+    fn cursor_row(&self, content: &Content) -> usize {
+        let cursor = buffer::cursor(content);
+
+        self.row_of(content, cursor.line) + cursor.column / self.columns
+    }
+
+    /// Back at the top, where the `text_editor` is with a new [`Content`].
+    pub fn scroll_to_top(&mut self) {
+        self.top = 0;
+    }
+
+    /// How many rows to scroll for `line` to start at the top of the viewport.
+    pub fn rows_to(&self, content: &Content, line: usize) -> isize {
+        self.row_of(content, line) as isize - self.top as isize
+    }
+
+    /// Visual row of the cursor relative to the top of the viewport.
+    pub fn visible_cursor_row(&self, content: &Content) -> isize {
+        self.cursor_row(content) as isize - self.top as isize
+    }
+
+    /// The document position drawn at `point`, relative to the text area.
+    ///
+    /// Columns past the end of a line are kept, which is what block
+    /// selections need.
+    pub fn position_at(&self, content: &Content, point: Point) -> Position {
+        let target = self.top + (point.y.max(0.0) / LINE_HEIGHT) as usize;
+        let column = (point.x.max(0.0) / CHAR_WIDTH).round() as usize;
+        let mut row = 0;
+
+        for (index, line) in content.lines().enumerate() {
+            let rows = self.rows_of(&line);
+
+            if target < row + rows {
+                return Position::new(index, (target - row).saturating_mul(self.columns).saturating_add(column));
+            }
+
+            row += rows;
+        }
+
+        let last = content.line_count().saturating_sub(1);
+
+        Position::new(last, column)
+    }
+
+    /// Returns the lines that are currently on screen.
+    pub fn visible_lines(&self, content: &Content) -> Vec<VisibleLine> {
+        let mut visible = Vec::new();
+        let mut row = 0;
+
+        for (index, line) in content.lines().enumerate() {
+            let rows = self.rows_of(&line);
+
+            if row + rows > self.top {
+                visible.push(VisibleLine {
+                    line: index,
+                    row: row as isize - self.top as isize,
+                    rows,
+                });
+            }
+
+            row += rows;
+
+            if row >= self.top + self.rows {
+                break;
+            }
+        }
+
+        visible
+    }
+
+    /// Visual row that the given document line starts at.
+    fn row_of(&self, content: &Content, line: usize) -> usize {
+        content.lines().take(line).map(|line| self.rows_of(&line)).sum()
+    }
+
+    fn total_rows(&self, content: &Content) -> usize {
+        content.lines().map(|line| self.rows_of(&line)).sum()
+    }
+
+    fn rows_of(&self, line: &str) -> usize {
+        let length = line.chars().count();
+
+        length.div_ceil(self.columns).max(1)
+    }
+}
+
+/// Where `position` is drawn, as the visual row relative to the top of the
+/// viewport and the column on that row. `None` if it's off screen.
+pub fn locate(visible: &[VisibleLine], position: Position, columns: usize) -> Option<(isize, usize)> {
+    let line = visible.iter().find(|visible| visible.line == position.line)?;
+    let row = (position.column / columns).min(line.rows - 1);
+
+    Some((line.row + row as isize, position.column - row * columns))
+}
+
+/// Splits the text between `from` and `to` into the parts drawn on each
+/// visual row on screen, as the row and the columns the part spans on it.
+pub fn spans(content: &Content, visible: &[VisibleLine], from: Position, to: Position, columns: usize) -> Vec<(isize, usize, usize)> {
+    let mut spans = Vec::new();
+
+    for visible in visible.iter().filter(|visible| from.line <= visible.line && visible.line <= to.line) {
+        let start = match visible.line == from.line {
+            true => from.column,
+            false => 0
+        };
+
+        let end = match visible.line == to.line {
+            true => to.column,
+            false => buffer::line_length(content, visible.line)
+        };
+
+        for row in 0..visible.rows {
+            let row_start = row.saturating_mul(columns);
+            let span_from = start.max(row_start);
+            let span_to = end.min(row_start.saturating_add(columns));
+
+            if span_from < span_to {
+                spans.push((visible.row + row as isize, span_from - row_start, span_to - row_start));
+            }
+        }
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewport(rows: usize, columns: usize) -> Viewport {
+        let mut viewport = Viewport::new();
+        viewport.set_wrap(Wrap::Column, columns);
+        viewport.resize(0.0, rows as f32 * LINE_HEIGHT + EDITOR_PADDING * 2.0);
+        viewport
+    }
+
+    #[test]
+    fn wrapped_lines_take_several_rows() {
+        let content = Content::with_text("short\n0123456789abc\nlast");
+        let visible = viewport(10, 5).visible_lines(&content);
+
+        let rows: Vec<(usize, isize, usize)> = visible.iter().map(|line| (line.line, line.row, line.rows)).collect();
+        assert_eq!(rows, [(0, 0, 1), (1, 1, 3), (2, 4, 1)]);
+    }
+
+    #[test]
+    fn scrolling_is_clamped_to_the_document() {
+        let content = Content::with_text("a\nb\nc\nd\ne");
+        let mut viewport = viewport(2, 80);
+
+        viewport.follow(&Action::Scroll { lines: 3 }, &content);
+        assert_eq!(viewport.visible_lines(&content)[0].line, 3);
+
+        viewport.follow(&Action::Scroll { lines: 10 }, &content);
+        assert_eq!(viewport.visible_lines(&content)[0].line, 4);
+
+        viewport.follow(&Action::Scroll { lines: -10 }, &content);
+        assert_eq!(viewport.visible_lines(&content)[0].line, 0);
+    }
+
+    #[test]
+    fn wrap_modes_cycle() {
+        assert_eq!(Wrap::Window.next(), Wrap::Column);
+        assert_eq!(Wrap::Column.next(), Wrap::Off);
+        assert_eq!(Wrap::Off.next(), Wrap::Window);
+    }
+
+    #[test]
+    fn positions_are_located_on_their_wrapped_row() {
+        let content = Content::with_text("0123456789\nx");
+        let viewport = viewport(10, 4);
+        let visible = viewport.visible_lines(&content);
+
+        assert_eq!(locate(&visible, Position::new(0, 6), 4), Some((1, 2)));
+        assert_eq!(locate(&visible, Position::new(1, 0), 4), Some((3, 0)));
+        assert_eq!(viewport.position_at(&content, Point::new(2.0 * CHAR_WIDTH, 1.5 * LINE_HEIGHT)), Position::new(0, 6));
+    }
+
+    #[test]
+    fn spans_split_a_selection_by_row() {
+        let content = Content::with_text("0123456789\nabc");
+        let viewport = viewport(10, 4);
+        let visible = viewport.visible_lines(&content);
+
+        let spans = spans(&content, &visible, Position::new(0, 2), Position::new(1, 2), 4);
+        assert_eq!(spans, [(0, 2, 4), (1, 0, 4), (2, 0, 2), (3, 0, 2)]);
+    }
+}
@@ -0,0 +1,1171 @@
+use std::collections::HashMap;
+
+use iced::keyboard::{self, key::Named, Modifiers};
+use iced::widget::text_editor::{Action, Content, Edit, Motion};
+use iced::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::{buffer::{self, Position}, decorations::Decoration, keymap::Effect, viewport::{self, VisibleLine}};
+
+/// The modes of the Vim layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    #[default]
+    Normal,
+    Insert,
+    Visual,
+    VisualLine,
+    /// Typing an ex command after `:`.
+    Command,
+}
+
+impl Mode {
+    pub fn label(self) -> &'static str {
+        match self {
+            Mode::Normal => "NORMAL",
+            Mode::Insert => "INSERT",
+            Mode::Visual => "VISUAL",
+            Mode::VisualLine => "VISUAL LINE",
+            Mode::Command => "COMMAND",
+        }
+    }
+}
+
+/// A key press as the Vim layer sees it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Key {
+    Char(char),
+    Escape,
+    Enter,
+    Backspace,
+    Delete,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+}
+
+impl Key {
+    /// Translates a key press, `None` for chords the rest of the editor handles.
+    pub fn from_keyboard(key: keyboard::Key<&str>, modifiers: Modifiers) -> Option<Self> {
+        if modifiers.control() || modifiers.alt() || modifiers.logo() {
+            return None;
+        }
+
+        match key {
+            keyboard::Key::Character(text) => text.chars().next().map(Key::Char),
+            keyboard::Key::Named(named) => match named {
+                Named::Space => Some(Key::Char(' ')),
+                Named::Escape => Some(Key::Escape),
+                Named::Enter => Some(Key::Enter),
+                Named::Backspace => Some(Key::Backspace),
+                Named::Delete => Some(Key::Delete),
+                Named::ArrowUp => Some(Key::Up),
+                Named::ArrowDown => Some(Key::Down),
+                Named::ArrowLeft => Some(Key::Left),
+                Named::ArrowRight => Some(Key::Right),
+                Named::Home => Some(Key::Home),
+                Named::End => Some(Key::End),
+                _ => None
+            },
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Register {
+    text: String,
+    linewise: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MotionKey {
+    Left,
+    Right,
+    Up,
+    Down,
+    /// `w` and `W`, the latter only stops at whitespace.
+    WordForward(bool),
+    WordBackward(bool),
+    WordEnd(bool),
+    LineStart,
+    FirstNonBlank,
+    LineEnd,
+    DocumentStart,
+    DocumentEnd,
+}
+
+/// How much of the text a motion covers when an operator uses it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Exclusive,
+    Inclusive,
+    Linewise,
+}
+
+impl MotionKey {
+    fn kind(self) -> Kind {
+        match self {
+            MotionKey::Up | MotionKey::Down | MotionKey::DocumentStart | MotionKey::DocumentEnd => Kind::Linewise,
+            MotionKey::WordEnd(_) | MotionKey::LineEnd => Kind::Inclusive,
+            _ => Kind::Exclusive
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Motion(MotionKey),
+    /// The operator was doubled, as in `dd`.
+    Lines,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verb {
+    Move(MotionKey),
+    Operate(char, Option<usize>, Target),
+    Replace(char),
+    Command(char),
+    Escape,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Parsed {
+    register: Option<char>,
+    count: Option<usize>,
+    verb: Verb,
+}
+
+enum Parse {
+    Incomplete,
+    Invalid,
+    Complete(Parsed),
+}
+
+/// Text an operator works on.
+#[derive(Debug, Clone, Copy)]
+enum Range {
+    Chars(Position, Position),
+    Lines(usize, usize),
+}
+
+/// Whether a command changed the text, for `.`.
+enum Change {
+    None,
+    Done,
+    /// The change goes on until insert mode is left.
+    Insert,
+}
+
+/// Vim's modal editing on top of the `text_editor`.
+///
+/// Keys are turned into [`Effect`]s the editor carries out, so edits go
+/// through the same undo history and language server sync as typing does.
+#[derive(Debug, Clone, Default)]
+pub struct Vim {
+    mode: Mode,
+    /// Keys of the normal mode command typed so far.
+    pending: Vec<Key>,
+    registers: HashMap<char, Register>,
+    /// Keys of the change being made, and of the last finished one.
+    change: Vec<Key>,
+    last_change: Vec<Key>,
+    /// Where visual mode started and the end that moves.
+    anchor: Position,
+    head: Position,
+    command_line: String,
+    message: Option<String>,
+}
+
+impl Vim {
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Says the window stays open because the buffer has unsaved changes, like `:q` does.
+    pub fn refuse_quit(&mut self) {
+        self.message = Some(String::from("No write since last change (add ! to override)"));
+    }
+
+    /// What the bottom panel shows: the mode, the command being typed or a message.
+    pub fn status(&self) -> String {
+        if self.mode == Mode::Command {
+            return format!(":{}", self.command_line);
+        }
+
+        if let Some(message) = &self.message {
+            return message.clone();
+        }
+
+        let pending: String = self.pending.iter()
+            .filter_map(|key| match key {
+                Key::Char(character) => Some(*character),
+                _ => None
+            })
+            .collect();
+
+        match pending.is_empty() {
+            true => format!("-- {} --", self.mode.label()),
+            false => format!("-- {} -- {pending}", self.mode.label())
+        }
+    }
+
+    /// Handles a key press.
+    pub fn key(&mut self, key: Key, content: &Content) -> Vec<Effect> {
+        self.message = None;
+
+        match self.mode {
+            Mode::Insert => self.insert(key, content),
+            Mode::Command => self.command_line(key, content),
+            Mode::Normal | Mode::Visual | Mode::VisualLine => {
+                let visual = self.mode != Mode::Normal;
+
+                self.pending.push(key);
+
+                match parse(&self.pending, visual) {
+                    Parse::Incomplete => Vec::new(),
+                    Parse::Invalid => {
+                        self.pending.clear();
+                        Vec::new()
+                    },
+                    Parse::Complete(parsed) => {
+                        let keys = std::mem::take(&mut self.pending);
+
+                        match visual {
+                            true => self.visual(parsed, content),
+                            false => self.normal(parsed, keys, content)
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The cursor and selection to draw, the `text_editor` hides its own once
+    /// Escape has unfocused it.
+    pub fn decorations(&self, content: &Content, visible: &[VisibleLine], columns: usize, cursor: Color, selection: Color) -> Vec<Decoration> {
+        let mut decorations = Vec::new();
+
+        let head = match self.mode {
+            Mode::Visual | Mode::VisualLine => self.head,
+            _ => buffer::cursor(content)
+        };
+
+        if let Some((from, to)) = self.selection(content) {
+            for (row, from, to) in viewport::spans(content, visible, from, to, columns) {
+                decorations.push(Decoration::Selection { row, from, to, color: selection });
+            }
+        }
+
+        if let Some((row, column)) = viewport::locate(visible, head, columns) {
+            decorations.push(match self.mode {
+                Mode::Insert => Decoration::Cursor { row, column },
+                _ => Decoration::Selection { row, from: column, to: column + 1, color: cursor }
+            });
+        }
+
+        decorations
+    }
+
+    /// The text selected in visual mode, start first.
+    fn selection(&self, content: &Content) -> Option<(Position, Position)> {
+        match self.mode {
+            Mode::Visual => {
+                let (from, to) = (self.anchor.min(self.head), self.anchor.max(self.head));
+
+                Some((from, Position::new(to.line, (to.column + 1).min(buffer::line_length(content, to.line)))))
+            },
+            Mode::VisualLine => {
+                let (from, to) = (self.anchor.line.min(self.head.line), self.anchor.line.max(self.head.line));
+
+                Some((Position::new(from, 0), Position::new(to, buffer::line_length(content, to))))
+            },
+            _ => None
+        }
+    }
+
+    fn insert(&mut self, key: Key, content: &Content) -> Vec<Effect> {
+        self.change.push(key);
+
+        let action = match key {
+            Key::Escape => {
+                self.mode = Mode::Normal;
+                self.last_change = std::mem::take(&mut self.change);
+
+                return match buffer::cursor(content).column {
+                    0 => Vec::new(),
+                    _ => vec![Effect::Perform(Action::Move(Motion::Left))]
+                };
+            },
+            Key::Char(character) => Action::Edit(Edit::Insert(character)),
+            Key::Enter => Action::Edit(Edit::Enter),
+            Key::Backspace => Action::Edit(Edit::Backspace),
+            Key::Delete => Action::Edit(Edit::Delete),
+            Key::Up => Action::Move(Motion::Up),
+            Key::Down => Action::Move(Motion::Down),
+            Key::Left => Action::Move(Motion::Left),
+            Key::Right => Action::Move(Motion::Right),
+            Key::Home => Action::Move(Motion::Home),
+            Key::End => Action::Move(Motion::End),
+        };
+
+        vec![Effect::Perform(action)]
+    }
+
+    fn command_line(&mut self, key: Key, content: &Content) -> Vec<Effect> {
+        match key {
+            Key::Char(character) => {
+                self.command_line.push(character);
+            },
+            Key::Backspace if self.command_line.pop().is_none() => {
+                self.mode = Mode::Normal;
+            },
+            Key::Escape => {
+                self.mode = Mode::Normal;
+                self.command_line.clear();
+            },
+            Key::Enter => {
+                self.mode = Mode::Normal;
+
+                let command = std::mem::take(&mut self.command_line);
+
+                return self.ex(command.trim(), content);
+            },
+            _ => {}
+        }
+
+        Vec::new()
+    }
+
+    /// Runs an ex command typed after `:`.
+    fn ex(&mut self, command: &str, content: &Content) -> Vec<Effect> {
+        let (name, argument) = match command.split_once(' ') {
+            Some((name, argument)) => (name, Some(argument.trim()).filter(|argument| !argument.is_empty())),
+            None => (command, None)
+        };
+
+        match (name, argument) {
+            ("", None) => Vec::new(),
+            ("w" | "write", None) => vec![Effect::Save],
+            ("q" | "quit", None) => vec![Effect::Quit { force: false }],
+            ("q!" | "quit!", None) => vec![Effect::Quit { force: true }],
+            ("wq" | "x", None) => vec![Effect::SaveAndQuit],
+            ("e" | "edit", argument) => vec![Effect::Open(argument.map(str::to_owned))],
+            _ => match command.parse::<usize>() {
+                Ok(line) => {
+                    let line = line.saturating_sub(1).min(last_line(content));
+
+                    vec![Effect::MoveTo(Position::new(line, first_non_blank(content, line)))]
+                },
+                Err(_) => {
+                    self.message = Some(format!("Not an editor command: {command}"));
+                    Vec::new()
+                }
+            }
+        }
+    }
+
+    fn normal(&mut self, parsed: Parsed, keys: Vec<Key>, content: &Content) -> Vec<Effect> {
+        let (effects, change) = self.run(parsed, content);
+
+        match change {
+            Change::None => {},
+            Change::Done => self.last_change = keys,
+            Change::Insert => self.change = keys,
+        }
+
+        effects
+    }
+
+    fn run(&mut self, parsed: Parsed, content: &Content) -> (Vec<Effect>, Change) {
+        let Parsed { register, count, verb } = parsed;
+        let cursor = buffer::cursor(content);
+        let length = buffer::line_length(content, cursor.line);
+        let times = count.unwrap_or(1);
+
+        match verb {
+            Verb::Escape => (Vec::new(), Change::None),
+            Verb::Move(motion) => {
+                let target = target(content, cursor, motion, count);
+
+                (vec![Effect::MoveTo(on_character(content, target))], Change::None)
+            },
+            Verb::Operate(operator, motion_count, target) => {
+                let count = match (count, motion_count) {
+                    (None, None) => None,
+                    (count, motion_count) => Some(count.unwrap_or(1) * motion_count.unwrap_or(1))
+                };
+
+                let range = match target {
+                    Target::Lines => {
+                        let last = (cursor.line + count.unwrap_or(1) - 1).min(last_line(content));
+
+                        Range::Lines(cursor.line, last)
+                    },
+                    // `cw` on a word changes to its end, like `ce`.
+                    Target::Motion(MotionKey::WordForward(big)) if operator == 'c' && !character_at(content, cursor).is_some_and(char::is_whitespace) => {
+                        motion_range(content, cursor, MotionKey::WordEnd(big), count)
+                    },
+                    Target::Motion(motion) => motion_range(content, cursor, motion, count)
+                };
+
+                self.operate(operator, range, register, content)
+            },
+            Verb::Replace(character) => {
+                if cursor.column + times > length {
+                    return (Vec::new(), Change::None);
+                }
+
+                let replacement: String = std::iter::repeat_n(character, times).collect();
+
+                (vec![
+                    Effect::Select { anchor: cursor, head: Position::new(cursor.line, cursor.column + times) },
+                    Effect::Perform(Action::Edit(Edit::Paste(replacement.into()))),
+                    Effect::Perform(Action::Move(Motion::Left)),
+                ], Change::Done)
+            },
+            Verb::Command(command) => match command {
+                'x' => {
+                    let to = Position::new(cursor.line, (cursor.column + times).min(length));
+                    self.operate('d', Range::Chars(cursor, to), register, content)
+                },
+                'X' => {
+                    let from = Position::new(cursor.line, cursor.column.saturating_sub(times));
+                    self.operate('d', Range::Chars(from, cursor), register, content)
+                },
+                'D' | 'C' => {
+                    let range = motion_range(content, cursor, MotionKey::LineEnd, count);
+                    self.operate(command.to_ascii_lowercase(), range, register, content)
+                },
+                's' => {
+                    let to = Position::new(cursor.line, (cursor.column + times).min(length));
+                    self.operate('c', Range::Chars(cursor, to), register, content)
+                },
+                'S' => {
+                    let last = (cursor.line + times - 1).min(last_line(content));
+                    self.operate('c', Range::Lines(cursor.line, last), register, content)
+                },
+                'p' | 'P' => (self.put(register, command == 'P', times, content), Change::Done),
+                'u' => ((0..times).map(|_| Effect::Undo).collect(), Change::None),
+                'i' => self.enter_insert(Vec::new()),
+                'a' => self.enter_insert(vec![Effect::MoveTo(Position::new(cursor.line, (cursor.column + 1).min(length)))]),
+                'I' => self.enter_insert(vec![Effect::MoveTo(Position::new(cursor.line, first_non_blank(content, cursor.line)))]),
+                'A' => self.enter_insert(vec![Effect::MoveTo(Position::new(cursor.line, length))]),
+                'o' => self.enter_insert(vec![
+                    Effect::MoveTo(Position::new(cursor.line, length)),
+                    Effect::Perform(Action::Edit(Edit::Enter)),
+                ]),
+                'O' => self.enter_insert(vec![
+                    Effect::MoveTo(Position::new(cursor.line, 0)),
+                    Effect::Perform(Action::Edit(Edit::Enter)),
+                    Effect::Perform(Action::Move(Motion::Up)),
+                ]),
+                'J' => (join(content, cursor.line, times.max(2) - 1), Change::Done),
+                'v' | 'V' => {
+                    self.mode = match command {
+                        'v' => Mode::Visual,
+                        _ => Mode::VisualLine
+                    };
+                    self.anchor = cursor;
+                    self.head = cursor;
+
+                    (self.select(content), Change::None)
+                },
+                ':' => {
+                    self.mode = Mode::Command;
+                    self.command_line.clear();
+
+                    (Vec::new(), Change::None)
+                },
+                '.' => {
+                    let keys = (0..times).flat_map(|_| self.last_change.iter().copied()).collect();
+
+                    (vec![Effect::Replay(keys)], Change::None)
+                },
+                _ => (Vec::new(), Change::None)
+            }
+        }
+    }
+
+    fn visual(&mut self, parsed: Parsed, content: &Content) -> Vec<Effect> {
+        let Parsed { register, count, verb } = parsed;
+
+        match verb {
+            Verb::Move(motion) => {
+                self.head = on_character(content, target(content, self.head, motion, count));
+                self.select(content)
+            },
+            Verb::Escape => self.leave_visual(),
+            Verb::Command(command @ ('v' | 'V')) => {
+                let mode = match command {
+                    'v' => Mode::Visual,
+                    _ => Mode::VisualLine
+                };
+
+                match self.mode == mode {
+                    true => self.leave_visual(),
+                    false => {
+                        self.mode = mode;
+                        self.select(content)
+                    }
+                }
+            },
+            Verb::Command('o') => {
+                std::mem::swap(&mut self.anchor, &mut self.head);
+                self.select(content)
+            },
+            Verb::Command(command @ ('d' | 'x' | 'c' | 's' | 'y' | '>' | '<')) => {
+                let range = match self.mode {
+                    Mode::VisualLine => Range::Lines(self.anchor.line.min(self.head.line), self.anchor.line.max(self.head.line)),
+                    _ => {
+                        let (from, to) = self.selection(content).unwrap_or((self.head, self.head));
+                        Range::Chars(from, to)
+                    }
+                };
+
+                let operator = match command {
+                    'x' => 'd',
+                    's' => 'c',
+                    other => other
+                };
+
+                self.mode = Mode::Normal;
+
+                self.operate(operator, range, register, content).0
+            },
+            _ => Vec::new()
+        }
+    }
+
+    fn leave_visual(&mut self) -> Vec<Effect> {
+        self.mode = Mode::Normal;
+
+        vec![Effect::MoveTo(self.head)]
+    }
+
+    /// Selects what visual mode covers, leaving the cursor at the head.
+    fn select(&self, content: &Content) -> Vec<Effect> {
+        let length = |line| buffer::line_length(content, line);
+        let (anchor, head) = (self.anchor, self.head);
+
+        let (anchor, head) = match self.mode {
+            Mode::VisualLine => match head.line >= anchor.line {
+                true => (Position::new(anchor.line, 0), Position::new(head.line, length(head.line))),
+                false => (Position::new(anchor.line, length(anchor.line)), Position::new(head.line, 0))
+            },
+            _ => match head >= anchor {
+                true => (anchor, Position::new(head.line, (head.column + 1).min(length(head.line)))),
+                false => (Position::new(anchor.line, (anchor.column + 1).min(length(anchor.line))), head)
+            }
+        };
+
+        vec![Effect::Select { anchor, head }]
+    }
+
+    fn enter_insert(&mut self, effects: Vec<Effect>) -> (Vec<Effect>, Change) {
+        self.mode = Mode::Insert;
+
+        (effects, Change::Insert)
+    }
+
+    /// Applies `operator` to `range`.
+    fn operate(&mut self, operator: char, range: Range, register: Option<char>, content: &Content) -> (Vec<Effect>, Change) {
+        match range {
+            Range::Chars(from, to) => {
+                let text = buffer::text_between(content, from, to);
+
+                match operator {
+                    'y' => {
+                        self.store(register, text, false, true);
+                        (vec![Effect::MoveTo(from)], Change::None)
+                    },
+                    'd' | 'c' => {
+                        self.store(register, text, false, false);
+
+                        let mut effects = Vec::new();
+
+                        if from != to {
+                            effects.push(Effect::Select { anchor: from, head: to });
+                            effects.push(Effect::Perform(Action::Edit(Edit::Delete)));
+                        }
+
+                        if operator == 'c' {
+                            return self.enter_insert(effects);
+                        }
+
+                        // The cursor stays on a character, not past the end of the line.
+                        let remaining = from.column + buffer::line_length(content, to.line) - to.column;
+
+                        if from.column >= remaining && remaining > 0 {
+                            effects.push(Effect::Perform(Action::Move(Motion::Left)));
+                        }
+
+                        (effects, Change::Done)
+                    },
+                    '>' | '<' => (vec![Effect::Indent { from: from.line, to: to.line, outdent: operator == '<' }], Change::Done),
+                    _ => (Vec::new(), Change::None)
+                }
+            },
+            Range::Lines(first, last) => {
+                let text: String = (first..=last)
+                    .map(|line| content.line(line).map(|line| line.to_string()).unwrap_or_default() + "\n")
+                    .collect();
+
+                match operator {
+                    'y' => {
+                        self.store(register, text, true, true);
+
+                        let cursor = buffer::cursor(content);
+
+                        let position = match cursor.line == first {
+                            true => cursor,
+                            false => Position::new(first, cursor.column.min(buffer::line_length(content, first)))
+                        };
+
+                        (vec![Effect::MoveTo(position)], Change::None)
+                    },
+                    'd' => {
+                        self.store(register, text, true, false);
+
+                        let (from, to, next) = if last < last_line(content) {
+                            (Position::new(first, 0), Position::new(last + 1, 0), last + 1)
+                        } else if first > 0 {
+                            (Position::new(first - 1, buffer::line_length(content, first - 1)), Position::new(last, buffer::line_length(content, last)), first - 1)
+                        } else {
+                            (Position::new(first, 0), Position::new(last, buffer::line_length(content, last)), last + 1)
+                        };
+
+                        // The line after the deleted ones moves up to `first`, without one the cursor goes up.
+                        let line = match last < last_line(content) {
+                            true => first,
+                            false => first.saturating_sub(1)
+                        };
+
+                        (vec![
+                            Effect::Select { anchor: from, head: to },
+                            Effect::Perform(Action::Edit(Edit::Delete)),
+                            Effect::MoveTo(Position::new(line, first_non_blank(content, next))),
+                        ], Change::Done)
+                    },
+                    'c' => {
+                        self.store(register, text, true, false);
+
+                        let from = Position::new(first, first_non_blank(content, first));
+                        let to = Position::new(last, buffer::line_length(content, last));
+
+                        let effects = match from == to {
+                            true => vec![Effect::MoveTo(from)],
+                            false => vec![
+                                Effect::Select { anchor: from, head: to },
+                                Effect::Perform(Action::Edit(Edit::Delete)),
+                            ]
+                        };
+
+                        self.enter_insert(effects)
+                    },
+                    '>' | '<' => (vec![Effect::Indent { from: first, to: last, outdent: operator == '<' }], Change::Done),
+                    _ => (Vec::new(), Change::None)
+                }
+            }
+        }
+    }
+
+    /// Puts yanked or deleted text into a register, the unnamed one always
+    /// gets it too and `0` keeps the last yank.
+    fn store(&mut self, register: Option<char>, text: String, linewise: bool, yank: bool) {
+        match register {
+            Some('_') => return,
+            Some(name) if name.is_ascii_uppercase() => {
+                let register = self.registers.entry(name.to_ascii_lowercase()).or_default();
+
+                register.text.push_str(&text);
+                register.linewise |= linewise;
+            },
+            Some(name) if name != '"' => {
+                self.registers.insert(name, Register { text: text.clone(), linewise });
+            },
+            _ => {
+                if yank {
+                    self.registers.insert('0', Register { text: text.clone(), linewise });
+                }
+            }
+        }
+
+        self.registers.insert('"', Register { text, linewise });
+    }
+
+    /// Pastes a register after the cursor, or before it for `P`.
+    fn put(&self, register: Option<char>, before: bool, times: usize, content: &Content) -> Vec<Effect> {
+        let Some(register) = self.registers.get(&register.unwrap_or('"').to_ascii_lowercase()) else {
+            return Vec::new();
+        };
+
+        let cursor = buffer::cursor(content);
+        let text = register.text.repeat(times);
+
+        if register.linewise {
+            let indent = text.chars().take_while(|character| *character == ' ' || *character == '\t').count();
+
+            return match before {
+                true => vec![
+                    Effect::MoveTo(Position::new(cursor.line, 0)),
+                    Effect::Perform(Action::Edit(Edit::Paste(text.into()))),
+                    Effect::MoveTo(Position::new(cursor.line, indent)),
+                ],
+                false => vec![
+                    Effect::MoveTo(Position::new(cursor.line, buffer::line_length(content, cursor.line))),
+                    Effect::Perform(Action::Edit(Edit::Paste(format!("\n{}", text.strip_suffix('\n').unwrap_or(&text)).into()))),
+                    Effect::MoveTo(Position::new(cursor.line + 1, indent)),
+                ]
+            };
+        }
+
+        let at = match before || buffer::line_length(content, cursor.line) == 0 {
+            true => cursor,
+            false => Position::new(cursor.line, cursor.column + 1)
+        };
+
+        vec![
+            Effect::MoveTo(at),
+            Effect::Perform(Action::Edit(Edit::Paste(text.into()))),
+            Effect::Perform(Action::Move(Motion::Left)),
+        ]
+    }
+}
+
+/// Parses the keys of a normal or visual mode command.
+fn parse(keys: &[Key], visual: bool) -> Parse {
+    let mut index = 0;
+    let mut register = None;
+
+    if keys.first() == Some(&Key::Char('"')) {
+        match keys.get(1) {
+            None => return Parse::Incomplete,
+            Some(Key::Char(name)) if name.is_ascii_alphanumeric() || *name == '"' || *name == '_' => register = Some(*name),
+            Some(_) => return Parse::Invalid
+        }
+
+        index = 2;
+    }
+
+    let (count, next) = parse_count(keys, index);
+
+    let Some(&key) = keys.get(next) else {
+        return Parse::Incomplete;
+    };
+
+    let rest = &keys[next + 1..];
+
+    let verb = match key {
+        Key::Escape => Verb::Escape,
+        Key::Char(operator @ ('d' | 'c' | 'y' | '>' | '<')) if !visual => {
+            let (motion_count, next) = parse_count(rest, 0);
+
+            match rest.get(next) {
+                None => return Parse::Incomplete,
+                Some(Key::Char(character)) if *character == operator => Verb::Operate(operator, motion_count, Target::Lines),
+                Some(_) => match parse_motion(&rest[next..]) {
+                    Parse::Complete(Parsed { verb: Verb::Move(motion), .. }) => Verb::Operate(operator, motion_count, Target::Motion(motion)),
+                    Parse::Incomplete => return Parse::Incomplete,
+                    _ => return Parse::Invalid
+                }
+            }
+        },
+        Key::Char('r') if !visual => match rest.first() {
+            None => return Parse::Incomplete,
+            Some(Key::Char(character)) => Verb::Replace(*character),
+            Some(_) => return Parse::Invalid
+        },
+        Key::Char(command @ ('x' | 'X' | 'D' | 'C' | 's' | 'S' | 'p' | 'P' | 'u' | 'i' | 'a' | 'I' | 'A' | 'o' | 'O' | 'J' | 'v' | 'V' | ':' | '.')) if !visual => Verb::Command(command),
+        Key::Char(command @ ('d' | 'x' | 'c' | 's' | 'y' | '>' | '<' | 'o' | 'v' | 'V')) if visual => Verb::Command(command),
+        _ => return match parse_motion(&keys[next..]) {
+            Parse::Complete(parsed) => Parse::Complete(Parsed { register, count, ..parsed }),
+            other => other
+        }
+    };
+
+    Parse::Complete(Parsed { register, count, verb })
+}
+
+/// Reads a count like the `12` of `12j`, a leading `0` is a motion instead.
+fn parse_count(keys: &[Key], start: usize) -> (Option<usize>, usize) {
+    let mut count: Option<usize> = None;
+    let mut index = start;
+
+    while let Some(Key::Char(digit @ '0'..='9')) = keys.get(index) {
+        if count.is_none() && *digit == '0' {
+            break;
+        }
+
+        count = Some(count.unwrap_or(0).saturating_mul(10).saturating_add(*digit as usize - '0' as usize));
+        index += 1;
+    }
+
+    (count, index)
+}
+
+/// Parses the keys of a motion, which must be all of `keys`.
+fn parse_motion(keys: &[Key]) -> Parse {
+    let motion = match keys {
+        [] => return Parse::Incomplete,
+        [Key::Char('g')] => return Parse::Incomplete,
+        [Key::Char('g'), Key::Char('g')] => MotionKey::DocumentStart,
+        [key] => match key {
+            Key::Char('h') | Key::Left | Key::Backspace => MotionKey::Left,
+            Key::Char('l') | Key::Right | Key::Char(' ') => MotionKey::Right,
+            Key::Char('j') | Key::Down | Key::Enter => MotionKey::Down,
+            Key::Char('k') | Key::Up => MotionKey::Up,
+            Key::Char('w') => MotionKey::WordForward(false),
+            Key::Char('W') => MotionKey::WordForward(true),
+            Key::Char('b') => MotionKey::WordBackward(false),
+            Key::Char('B') => MotionKey::WordBackward(true),
+            Key::Char('e') => MotionKey::WordEnd(false),
+            Key::Char('E') => MotionKey::WordEnd(true),
+            Key::Char('0') | Key::Home => MotionKey::LineStart,
+            Key::Char('^') => MotionKey::FirstNonBlank,
+            Key::Char('$') | Key::End => MotionKey::LineEnd,
+            Key::Char('G') => MotionKey::DocumentEnd,
+            _ => return Parse::Invalid
+        },
+        _ => return Parse::Invalid
+    };
+
+    Parse::Complete(Parsed { register: None, count: None, verb: Verb::Move(motion) })
+}
+
+/// Where `motion` repeated `count` times takes the cursor from `from`.
+fn target(content: &Content, from: Position, motion: MotionKey, count: Option<usize>) -> Position {
+    let times = count.unwrap_or(1);
+    let length = |line| buffer::line_length(content, line);
+
+    match motion {
+        MotionKey::Left => Position::new(from.line, from.column.saturating_sub(times)),
+        MotionKey::Right => Position::new(from.line, (from.column + times).min(length(from.line))),
+        MotionKey::Up => Position::new(from.line.saturating_sub(times), from.column),
+        MotionKey::Down => Position::new((from.line + times).min(last_line(content)), from.column),
+        MotionKey::LineStart => Position::new(from.line, 0),
+        MotionKey::FirstNonBlank => Position::new(from.line, first_non_blank(content, from.line)),
+        MotionKey::LineEnd => {
+            let line = (from.line + times - 1).min(last_line(content));
+
+            Position::new(line, length(line).saturating_sub(1))
+        },
+        MotionKey::DocumentStart | MotionKey::DocumentEnd => {
+            let line = match (motion, count) {
+                (_, Some(line)) => line.saturating_sub(1).min(last_line(content)),
+                (MotionKey::DocumentStart, None) => 0,
+                _ => last_line(content)
+            };
+
+            Position::new(line, first_non_blank(content, line))
+        },
+        MotionKey::WordForward(big) | MotionKey::WordBackward(big) | MotionKey::WordEnd(big) => {
+            let text: Vec<char> = buffer::text(content).chars().collect();
+            let mut offset = buffer::offset(content, from);
+
+            for _ in 0..times {
+                offset = match motion {
+                    MotionKey::WordForward(_) => next_word_start(&text, offset, big),
+                    MotionKey::WordBackward(_) => previous_word_start(&text, offset, big),
+                    _ => word_end(&text, offset, big)
+                };
+            }
+
+            buffer::position_at(content, offset)
+        }
+    }
+}
+
+/// The text a motion covers when an operator uses it.
+fn motion_range(content: &Content, from: Position, motion: MotionKey, count: Option<usize>) -> Range {
+    let mut to = target(content, from, motion, count);
+
+    // `dw` on the last word of a line stops at its end instead of eating the line break.
+    if let MotionKey::WordForward(_) = motion {
+        if to.line > from.line {
+            to = Position::new(to.line - 1, buffer::line_length(content, to.line - 1));
+        }
+    }
+
+    let (start, end) = (from.min(to), from.max(to));
+
+    match motion.kind() {
+        Kind::Linewise => Range::Lines(start.line, end.line),
+        Kind::Exclusive => Range::Chars(start, end),
+        Kind::Inclusive => Range::Chars(start, Position::new(end.line, (end.column + 1).min(buffer::line_length(content, end.line)))),
+    }
+}
+
+/// Joins `times` lines onto the cursor line, separated by a space.
+fn join(content: &Content, line: usize, times: usize) -> Vec<Effect> {
+    let mut effects = Vec::new();
+    let mut length = buffer::line_length(content, line);
+
+    for next in line + 1..=(line + times).min(last_line(content)) {
+        let start = first_non_blank(content, next);
+        let rest = buffer::line_length(content, next) - start;
+
+        effects.push(Effect::Select { anchor: Position::new(line, length), head: Position::new(line + 1, start) });
+
+        match length > 0 && rest > 0 {
+            true => {
+                effects.push(Effect::Perform(Action::Edit(Edit::Paste(String::from(" ").into()))));
+                length += 1;
+            },
+            false => effects.push(Effect::Perform(Action::Edit(Edit::Delete)))
+        }
+
+        length += rest;
+    }
+
+    effects
+}
+
+/// Keeps the cursor on a character, Vim never leaves it past the end of a line in normal mode.
+fn on_character(content: &Content, position: Position) -> Position {
+    let length = buffer::line_length(content, position.line);
+
+    Position::new(position.line, position.column.min(length.saturating_sub(1)))
+}
+
+fn character_at(content: &Content, position: Position) -> Option<char> {
+    content.line(position.line)?.chars().nth(position.column)
+}
+
+fn first_non_blank(content: &Content, line: usize) -> usize {
+    content.line(line)
+        .map(|text| text.chars().take_while(|character| character.is_whitespace()).count())
+        .unwrap_or(0)
+}
+
+fn last_line(content: &Content) -> usize {
+    content.line_count().saturating_sub(1)
+}
+
+/// Groups characters the way word motions see them: whitespace, word
+/// characters and punctuation, or just whitespace and the rest for `W`.
+fn class(character: char, big: bool) -> u8 {
+    if character.is_whitespace() {
+        0
+    } else if big || character.is_alphanumeric() || character == '_' {
+        1
+    } else {
+        2
+    }
+}
+
+fn next_word_start(text: &[char], offset: usize, big: bool) -> usize {
+    let mut index = offset;
+
+    if let Some(&character) = text.get(index) {
+        let current = class(character, big);
+
+        while current != 0 && text.get(index).is_some_and(|&next| class(next, big) == current) {
+            index += 1;
+        }
+    }
+
+    while text.get(index).is_some_and(|&next| class(next, big) == 0) {
+        index += 1;
+    }
+
+    index.min(text.len())
+}
+
+fn previous_word_start(text: &[char], offset: usize, big: bool) -> usize {
+    let mut index = offset.min(text.len());
+
+    while index > 0 && class(text[index - 1], big) == 0 {
+        index -= 1;
+    }
+
+    let Some(current) = index.checked_sub(1).map(|previous| class(text[previous], big)) else {
+        return 0;
+    };
+
+    while index > 0 && class(text[index - 1], big) == current {
+        index -= 1;
+    }
+
+    index
+}
+
+fn word_end(text: &[char], offset: usize, big: bool) -> usize {
+    let mut index = offset + 1;
+
+    while text.get(index).is_some_and(|&next| class(next, big) == 0) {
+        index += 1;
+    }
+
+    let Some(current) = text.get(index).map(|&character| class(character, big)) else {
+        return text.len().saturating_sub(1);
+    };
+
+    while text.get(index + 1).is_some_and(|&next| class(next, big) == current) {
+        index += 1;
+    }
+
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Keys typed as text, with `\x1b` for Escape and `\n` for Enter.
+    fn keys(text: &str) -> Vec<Key> {
+        text.chars()
+            .map(|character| match character {
+                '\x1b' => Key::Escape,
+                '\n' => Key::Enter,
+                character => Key::Char(character)
+            })
+            .collect()
+    }
+
+    /// Types `text` and returns the effects of the last key.
+    fn type_keys(vim: &mut Vim, content: &Content, text: &str) -> Vec<Effect> {
+        keys(text).into_iter().fold(Vec::new(), |_, key| vim.key(key, content))
+    }
+
+    fn complete(text: &str, visual: bool) -> Option<(Option<char>, Option<usize>, Verb)> {
+        match parse(&keys(text), visual) {
+            Parse::Complete(Parsed { register, count, verb }) => Some((register, count, verb)),
+            _ => None
+        }
+    }
+
+    #[test]
+    fn refusing_to_quit_shows_in_place_of_the_mode() {
+        let mut vim = Vim::default();
+        let content = Content::with_text("hello");
+
+        vim.refuse_quit();
+        assert_eq!(vim.status(), "No write since last change (add ! to override)");
+
+        type_keys(&mut vim, &content, "l");
+        assert_eq!(vim.status(), "-- NORMAL --");
+    }
+
+    #[test]
+    fn commands_are_parsed_with_their_counts_and_registers() {
+        assert_eq!(complete("3dw", false), Some((None, Some(3), Verb::Operate('d', None, Target::Motion(MotionKey::WordForward(false))))));
+        assert_eq!(complete("d2W", false), Some((None, None, Verb::Operate('d', Some(2), Target::Motion(MotionKey::WordForward(true))))));
+        assert_eq!(complete("\"ayy", false), Some((Some('a'), None, Verb::Operate('y', None, Target::Lines))));
+        assert_eq!(complete("10j", false), Some((None, Some(10), Verb::Move(MotionKey::Down))));
+        assert_eq!(complete("0", false), Some((None, None, Verb::Move(MotionKey::LineStart))));
+        assert_eq!(complete("gg", false), Some((None, None, Verb::Move(MotionKey::DocumentStart))));
+        assert_eq!(complete("rx", false), Some((None, None, Verb::Replace('x'))));
+    }
+
+    #[test]
+    fn unfinished_and_unknown_commands_are_told_apart() {
+        assert!(matches!(parse(&keys("d"), false), Parse::Incomplete));
+        assert!(matches!(parse(&keys("2d3"), false), Parse::Incomplete));
+        assert!(matches!(parse(&keys("g"), false), Parse::Incomplete));
+        assert!(matches!(parse(&keys("\""), false), Parse::Incomplete));
+        assert!(matches!(parse(&keys("dz"), false), Parse::Invalid));
+        assert!(matches!(parse(&keys("z"), false), Parse::Invalid));
+    }
+
+    #[test]
+    fn visual_mode_reads_operators_as_commands() {
+        assert_eq!(complete("d", true), Some((None, None, Verb::Command('d'))));
+        assert_eq!(complete("2w", true), Some((None, Some(2), Verb::Move(MotionKey::WordForward(false)))));
+        assert!(complete("i", true).is_none());
+    }
+
+    #[test]
+    fn deleting_a_word_selects_and_deletes_it() {
+        let content = Content::with_text("one two");
+        let mut vim = Vim::default();
+
+        let effects = type_keys(&mut vim, &content, "dw");
+
+        assert!(matches!(effects[..], [
+            Effect::Select { anchor: Position { line: 0, column: 0 }, head: Position { line: 0, column: 4 } },
+            Effect::Perform(Action::Edit(Edit::Delete))
+        ]));
+        assert_eq!(vim.registers[&'"'].text, "one ");
+    }
+
+    #[test]
+    fn dot_repeats_the_last_change_but_not_motions() {
+        let content = Content::with_text("one two three");
+        let mut vim = Vim::default();
+
+        type_keys(&mut vim, &content, "dw");
+        type_keys(&mut vim, &content, "w");
+
+        let effects = type_keys(&mut vim, &content, "2.");
+
+        assert!(matches!(&effects[..], [Effect::Replay(replayed)] if *replayed == keys("dwdw")));
+    }
+
+    #[test]
+    fn dot_repeats_inserts_up_to_escape() {
+        let content = Content::with_text("");
+        let mut vim = Vim::default();
+
+        type_keys(&mut vim, &content, "ahi\x1b");
+        assert_eq!(vim.mode(), Mode::Normal);
+
+        let effects = type_keys(&mut vim, &content, ".");
+
+        assert!(matches!(&effects[..], [Effect::Replay(replayed)] if *replayed == keys("ahi\x1b")));
+    }
+
+    #[test]
+    fn yanked_lines_go_to_named_registers() {
+        let content = Content::with_text("first\nsecond");
+        let mut vim = Vim::default();
+
+        type_keys(&mut vim, &content, "\"ayy");
+        type_keys(&mut vim, &content, "\"Ayy");
+
+        assert_eq!(vim.registers[&'a'].text, "first\nfirst\n");
+        assert!(vim.registers[&'a'].linewise);
+        assert!(!vim.registers.contains_key(&'0'));
+
+        let effects = type_keys(&mut vim, &content, "\"ap");
+
+        assert!(matches!(&effects[1], Effect::Perform(Action::Edit(Edit::Paste(text))) if text.as_str() == "\nfirst\nfirst"));
+    }
+
+    #[test]
+    fn ex_commands_turn_into_effects() {
+        let content = Content::with_text("one\n  two");
+        let mut vim = Vim::default();
+
+        assert!(matches!(type_keys(&mut vim, &content, ":w\n")[..], [Effect::Save]));
+        assert!(matches!(type_keys(&mut vim, &content, ":q!\n")[..], [Effect::Quit { force: true }]));
+        assert!(matches!(&type_keys(&mut vim, &content, ":e notes.txt\n")[..], [Effect::Open(Some(path))] if path == "notes.txt"));
+        assert!(matches!(type_keys(&mut vim, &content, ":9\n")[..], [Effect::MoveTo(Position { line: 1, column: 2 })]));
+
+        assert!(type_keys(&mut vim, &content, ":nope\n").is_empty());
+        assert_eq!(vim.status(), "Not an editor command: nope");
+    }
+
+    #[test]
+    fn word_motions_follow_character_classes() {
+        let text: Vec<char> = "foo.bar  baz".chars().collect();
+
+        assert_eq!(next_word_start(&text, 0, false), 3);
+        assert_eq!(next_word_start(&text, 0, true), 9);
+        assert_eq!(previous_word_start(&text, 9, false), 4);
+        assert_eq!(previous_word_start(&text, 9, true), 0);
+        assert_eq!(word_end(&text, 0, false), 2);
+        assert_eq!(word_end(&text, 2, true), 6);
+        assert_eq!(word_end(&text, 11, false), 11);
+    }
+
+    #[test]
+    fn counts_start_with_a_non_zero_digit() {
+        assert_eq!(parse_count(&keys("12j"), 0), (Some(12), 2));
+        assert_eq!(parse_count(&keys("0"), 0), (None, 0));
+        assert_eq!(parse_count(&keys("x10"), 1), (Some(10), 3));
+    }
+}
\ No newline at end of file
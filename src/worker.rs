@@ -0,0 +1,105 @@
+use std::{hash::Hash, io};
+
+use iced::futures::{channel::mpsc, future, stream, Future, SinkExt, Stream, StreamExt};
+use iced::{subscription, Subscription};
+
+/// Sends requests to a process a [`subscription`] keeps running, like the
+/// language server or the terminal's shell.
+#[derive(Debug)]
+pub struct Handle<Request>(mpsc::UnboundedSender<Request>);
+
+impl<Request> Handle<Request> {
+    /// A handle, and the end its requests come out of for the process' loop.
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<Request>) {
+        let (sender, receiver) = mpsc::unbounded();
+
+        (Self(sender), receiver)
+    }
+
+    pub fn send(&self, request: Request) {
+        // The receiving end goes away with the loop, requests sent after that have no process to reach.
+        let _ = self.0.unbounded_send(request);
+    }
+}
+
+impl<Request> Clone for Handle<Request> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+/// What the loop talking to a process waits for next.
+pub enum Input<Output, Request> {
+    /// Something the process sent.
+    Output(Output),
+    /// Something the editor asked for through the [`Handle`].
+    Request(Request),
+    /// The process closed its side, the loop is done.
+    Closed,
+}
+
+/// Interleaves what a process sends with the requests of its [`Handle`],
+/// followed by [`Input::Closed`] once the process stops sending.
+pub fn inputs<Output, Request>(
+    output: impl Stream<Item = Output>,
+    requests: mpsc::UnboundedReceiver<Request>,
+) -> impl Stream<Item = Input<Output, Request>> {
+    stream::select(
+        output.map(Input::Output).chain(stream::once(async { Input::Closed })),
+        requests.map(Input::Request),
+    )
+}
+
+/// Runs `process` once in a subscription identified by `id`, then sends the
+/// event `ended` makes of how it went.
+///
+/// A subscription can't finish, so it idles after that until the editor drops
+/// it. Recreating it with another `id` runs the process again.
+pub fn subscription<Id, Event, Process>(
+    id: Id,
+    process: impl FnOnce(mpsc::Sender<Event>) -> Process + Send + 'static,
+    ended: impl FnOnce(io::Result<()>) -> Event + Send + 'static,
+) -> Subscription<Event>
+where
+    Id: Hash + 'static,
+    Event: Send + 'static,
+    Process: Future<Output = io::Result<()>> + Send,
+{
+    subscription::channel(id, 100, |mut output| async move {
+        let event = ended(process(output.clone()).await);
+
+        let _ = output.send(event).await;
+
+        future::pending().await
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_reach_the_loop_until_it_stops() {
+        let (handle, mut requests) = Handle::new();
+
+        handle.clone().send(1);
+        assert_eq!(requests.try_recv().ok(), Some(1));
+
+        drop(requests);
+        handle.send(2);
+    }
+
+    #[tokio::test]
+    async fn inputs_close_once_the_process_stops_sending() {
+        let (handle, requests) = Handle::new();
+        handle.send("request");
+
+        let inputs: Vec<_> = inputs(stream::iter(["output"]), requests).take(3).collect().await;
+
+        let output = inputs.iter().position(|input| matches!(input, Input::Output("output")));
+        let closed = inputs.iter().position(|input| matches!(input, Input::Closed));
+
+        assert!(inputs.iter().any(|input| matches!(input, Input::Request("request"))));
+        assert!(output.is_some() && closed > output);
+    }
+}
\ No newline at end of file